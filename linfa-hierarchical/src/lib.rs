@@ -236,6 +236,36 @@ mod tests {
         assert_ne!(first_cluster_id, second_cluster_id);
     }
 
+    #[test]
+    fn test_histograms() {
+        // two groups of histograms, which concentrate on the first or last bins
+        let entries = ndarray::arr2(&[
+            [0.5, 0.3, 0.1, 0.1, 0.0],
+            [0.6, 0.2, 0.2, 0.0, 0.0],
+            [0.4, 0.4, 0.1, 0.0, 0.1],
+            [0.0, 0.1, 0.1, 0.3, 0.5],
+            [0.1, 0.0, 0.2, 0.2, 0.5],
+            [0.0, 0.0, 0.2, 0.4, 0.4],
+        ]);
+
+        for method in vec![
+            KernelMethod::ChiSquared(1.0),
+            KernelMethod::HistogramIntersection,
+            KernelMethod::Cosine,
+        ] {
+            let kernel = Kernel::params().method(method).transform(&entries);
+
+            let kernel = HierarchicalCluster::default()
+                .num_clusters(2)
+                .transform(kernel);
+
+            let ids = kernel.targets();
+            assert!(ids.iter().take(3).all(|x| x == &ids[0]));
+            assert!(ids.iter().skip(3).all(|x| x == &ids[3]));
+            assert_ne!(ids[0], ids[3]);
+        }
+    }
+
     #[test]
     fn test_noise() {
         // generate 1000 normal distributed points
@@ -0,0 +1,68 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use linfa_kernel::{Kernel, KernelMethod, KernelType, Neighbourhood};
+use ndarray::{Array2, ArrayView1};
+use ndarray_rand::rand::SeedableRng;
+use ndarray_rand::rand_distr::Uniform;
+use ndarray_rand::RandomExt;
+use rand_isaac::Isaac64Rng;
+
+fn kernel_bench(c: &mut Criterion) {
+    let mut rng = Isaac64Rng::seed_from_u64(42);
+
+    let n_features = 10;
+    let sizes = [100, 500, 2000];
+
+    // the custom kernel evaluates each pair of observations separately, like the previous
+    // implementation, while the built-in Gaussian kernel uses a matrix product
+    let methods = [
+        ("gaussian", KernelMethod::Gaussian(1.0)),
+        (
+            "custom_gaussian",
+            KernelMethod::custom(|a: ArrayView1<f64>, b: ArrayView1<f64>| {
+                (-(&a - &b).mapv(|x| x * x).sum()).exp()
+            }),
+        ),
+        ("linear", KernelMethod::Linear),
+        ("polynomial", KernelMethod::Polynomial(1.0, 3.0)),
+    ];
+
+    let mut group = c.benchmark_group("dense_kernel");
+    group.sample_size(10);
+
+    for n in sizes.iter() {
+        let data: Array2<f64> =
+            Array2::random_using((*n, n_features), Uniform::new(-1., 1.), &mut rng);
+
+        for (name, method) in methods.iter() {
+            group.bench_with_input(BenchmarkId::new(*name, n), &data, |b, d| {
+                b.iter(|| Kernel::new(d.view(), method.clone(), KernelType::Dense, false))
+            });
+        }
+    }
+
+    group.finish();
+
+    let mut group = c.benchmark_group("sparse_kernel");
+    group.sample_size(10);
+
+    for n in sizes.iter() {
+        let data: Array2<f64> =
+            Array2::random_using((*n, n_features), Uniform::new(-1., 1.), &mut rng);
+
+        group.bench_with_input(BenchmarkId::new("gaussian", n), &data, |b, d| {
+            b.iter(|| {
+                Kernel::new(
+                    d.view(),
+                    KernelMethod::Gaussian(1.0),
+                    KernelType::Sparse(Neighbourhood::knn(10)),
+                    false,
+                )
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, kernel_bench);
+criterion_main!(benches);
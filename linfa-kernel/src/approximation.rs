@@ -0,0 +1,437 @@
+//! Low-rank kernel approximations
+//!
+//! Instead of building the full kernel matrix, both approximations map each observation to an
+//! explicit feature vector, whose inner products approximate the kernel function. The resulting
+//! feature matrix can be used with any linear algorithm.
+//!
+//! * `Nystroem` samples landmark observations and projects onto the span of their kernel
+//!   evaluations, this works with any kernel method
+//! * `RandomFourierFeatures` samples from the Fourier transform of the Gaussian kernel
+use linfa::error::{Error, Result};
+use linfa::{dataset::Dataset, dataset::Targets, traits::Fit, traits::Transformer, Float};
+use ndarray::{Array1, Array2, ArrayBase, Axis, Data, Ix2};
+use ndarray_rand::rand::{seq::index, Rng, SeedableRng};
+use ndarray_rand::rand_distr::{Normal, Uniform};
+use ndarray_rand::RandomExt;
+use rand_isaac::Isaac64Rng;
+
+use super::KernelMethod;
+
+/// Eigenvalues relative to the largest one below this threshold are treated as zero
+const EIGENVALUE_THRESHOLD: f64 = 1e-10;
+
+/// Maximal number of sweeps of the Jacobi method, each sweep costs `O(n^3)` for `n` landmarks
+const MAX_SWEEPS: usize = 100;
+
+/// Parameters of the Nyström approximation
+pub struct NystroemParams<F, R> {
+    n_components: usize,
+    method: KernelMethod<F>,
+    rng: R,
+}
+
+impl<F: Float, R: Rng + Clone> NystroemParams<F, R> {
+    /// Set the kernel method to approximate
+    pub fn method(mut self, method: KernelMethod<F>) -> NystroemParams<F, R> {
+        self.method = method;
+
+        self
+    }
+}
+
+/// Fitted Nyström approximation
+///
+/// Maps an observation `x` to `K(x, L) K(L, L)^(-1/2)`, where `L` are the landmark
+/// observations sampled from the training data. The inner product of two mapped observations is
+/// then the Nyström approximation of their kernel value.
+///
+/// ```
+/// use linfa::prelude::*;
+/// use linfa_kernel::{KernelMethod, Nystroem};
+/// use ndarray::Array2;
+///
+/// let records = Array2::from_shape_fn((100, 3), |(i, j)| ((i * 7 + j * 13) % 17) as f64 / 17.0);
+/// let dataset = Dataset::from(records);
+///
+/// let nystroem = Nystroem::params(20)
+///     .method(KernelMethod::Gaussian(1.0))
+///     .fit(&dataset)
+///     .unwrap();
+///
+/// let features = nystroem.transform(dataset.records());
+/// assert_eq!(features.dim(), (100, 20));
+/// ```
+pub struct Nystroem<F> {
+    landmarks: Array2<F>,
+    method: KernelMethod<F>,
+    normalization: Array2<F>,
+}
+
+impl<F: Float> Nystroem<F> {
+    /// Sample `n_components` landmarks with a seeded random number generator
+    pub fn params(n_components: usize) -> NystroemParams<F, Isaac64Rng> {
+        Nystroem::params_with_rng(n_components, Isaac64Rng::seed_from_u64(42))
+    }
+
+    /// Sample `n_components` landmarks with the given random number generator
+    pub fn params_with_rng<R: Rng + Clone>(n_components: usize, rng: R) -> NystroemParams<F, R> {
+        NystroemParams {
+            n_components,
+            method: KernelMethod::Gaussian(F::from(0.5).unwrap()),
+            rng,
+        }
+    }
+
+    /// Observations sampled from the training data
+    pub fn landmarks(&self) -> &Array2<F> {
+        &self.landmarks
+    }
+
+    /// Number of features produced for each observation
+    pub fn n_components(&self) -> usize {
+        self.landmarks.nrows()
+    }
+}
+
+impl<'a, F: Float, R: Rng + Clone, D: Data<Elem = F>, T: Targets> Fit<'a, ArrayBase<D, Ix2>, T>
+    for NystroemParams<F, R>
+{
+    type Object = Result<Nystroem<F>>;
+
+    fn fit(&self, dataset: &Dataset<ArrayBase<D, Ix2>, T>) -> Self::Object {
+        let records = dataset.records();
+        let n_observations = records.nrows();
+
+        if self.n_components == 0 || self.n_components > n_observations {
+            return Err(Error::Parameters(format!(
+                "number of components {} should be in range [1, {}]",
+                self.n_components, n_observations
+            )));
+        }
+
+        let mut rng = self.rng.clone();
+        let mut indices = index::sample(&mut rng, n_observations, self.n_components).into_vec();
+        indices.sort_unstable();
+
+        let landmarks = records.select(Axis(0), &indices);
+        let kernel = self.method.cross(&landmarks, &landmarks);
+
+        // inverse square root of the landmark kernel, ignoring vanishing directions
+        let (eigvals, eigvecs) = symmetric_eigen(kernel, MAX_SWEEPS)?;
+        let max = eigvals.iter().fold(F::zero(), |a, b| F::max(a, *b));
+        let threshold = max * F::from(EIGENVALUE_THRESHOLD).unwrap();
+        let scale = eigvals.mapv(|x| {
+            if x > threshold {
+                F::one() / x.sqrt()
+            } else {
+                F::zero()
+            }
+        });
+
+        let normalization = (&eigvecs * &scale.insert_axis(Axis(0))).dot(&eigvecs.t());
+
+        Ok(Nystroem {
+            landmarks,
+            method: self.method.clone(),
+            normalization,
+        })
+    }
+}
+
+impl<F: Float, D: Data<Elem = F>> Transformer<&ArrayBase<D, Ix2>, Array2<F>> for Nystroem<F> {
+    fn transform(&self, x: &ArrayBase<D, Ix2>) -> Array2<F> {
+        self.method
+            .cross(x, &self.landmarks)
+            .dot(&self.normalization)
+    }
+}
+
+/// Parameters of the random Fourier features
+pub struct RandomFourierFeaturesParams<F, R> {
+    n_components: usize,
+    eps: F,
+    rng: R,
+}
+
+impl<F: Float, R: Rng + Clone> RandomFourierFeaturesParams<F, R> {
+    /// Set the bandwidth of the Gaussian kernel, as in `KernelMethod::Gaussian(eps)`
+    pub fn eps(mut self, eps: F) -> RandomFourierFeaturesParams<F, R> {
+        self.eps = eps;
+
+        self
+    }
+}
+
+/// Fitted random Fourier features of the Gaussian kernel
+///
+/// Maps an observation `x` to `sqrt(2/D) cos(W x + b)`, where the rows of `W` are sampled from
+/// the Fourier transform of the kernel and `b` uniformly from `[0, 2π)`. In expectation the
+/// inner product of two mapped observations is equal to their Gaussian kernel value.
+///
+/// ```
+/// use linfa::prelude::*;
+/// use linfa_kernel::RandomFourierFeatures;
+/// use ndarray::Array2;
+///
+/// let records = Array2::from_shape_fn((100, 3), |(i, j)| ((i * 7 + j * 13) % 17) as f64 / 17.0);
+/// let dataset = Dataset::from(records);
+///
+/// let rff = RandomFourierFeatures::params(500).eps(2.0).fit(&dataset).unwrap();
+///
+/// let features = rff.transform(dataset.records());
+/// assert_eq!(features.dim(), (100, 500));
+/// ```
+pub struct RandomFourierFeatures<F> {
+    weights: Array2<F>,
+    offsets: Array1<F>,
+}
+
+impl<F: Float> RandomFourierFeatures<F> {
+    /// Sample `n_components` features with a seeded random number generator
+    pub fn params(n_components: usize) -> RandomFourierFeaturesParams<F, Isaac64Rng> {
+        RandomFourierFeatures::params_with_rng(n_components, Isaac64Rng::seed_from_u64(42))
+    }
+
+    /// Sample `n_components` features with the given random number generator
+    pub fn params_with_rng<R: Rng + Clone>(
+        n_components: usize,
+        rng: R,
+    ) -> RandomFourierFeaturesParams<F, R> {
+        RandomFourierFeaturesParams {
+            n_components,
+            eps: F::from(0.5).unwrap(),
+            rng,
+        }
+    }
+
+    /// Number of features produced for each observation
+    pub fn n_components(&self) -> usize {
+        self.offsets.len()
+    }
+}
+
+impl<'a, F: Float, R: Rng + Clone, D: Data<Elem = F>, T: Targets> Fit<'a, ArrayBase<D, Ix2>, T>
+    for RandomFourierFeaturesParams<F, R>
+{
+    type Object = Result<RandomFourierFeatures<F>>;
+
+    fn fit(&self, dataset: &Dataset<ArrayBase<D, Ix2>, T>) -> Self::Object {
+        if self.n_components == 0 {
+            return Err(Error::Parameters(
+                "number of components should be positive".into(),
+            ));
+        }
+        if self.eps.is_nan() || self.eps <= F::zero() {
+            return Err(Error::Parameters(format!(
+                "bandwidth {} should be positive",
+                self.eps
+            )));
+        }
+
+        let n_features = dataset.records().ncols();
+        let mut rng = self.rng.clone();
+
+        // the kernel exp(-|x - y|^2 / eps) is the characteristic function of a normal
+        // distribution with variance 2 / eps
+        let std_dev = (2.0 / self.eps.to_f64().unwrap()).sqrt();
+        let weights = Array2::random_using(
+            (n_features, self.n_components),
+            Normal::new(0.0, std_dev).unwrap(),
+            &mut rng,
+        )
+        .mapv(|x| F::from(x).unwrap());
+        let offsets = Array1::random_using(
+            self.n_components,
+            Uniform::new(0.0, 2.0 * std::f64::consts::PI),
+            &mut rng,
+        )
+        .mapv(|x| F::from(x).unwrap());
+
+        Ok(RandomFourierFeatures { weights, offsets })
+    }
+}
+
+impl<F: Float, D: Data<Elem = F>> Transformer<&ArrayBase<D, Ix2>, Array2<F>>
+    for RandomFourierFeatures<F>
+{
+    fn transform(&self, x: &ArrayBase<D, Ix2>) -> Array2<F> {
+        let scale = F::from(2.0 / self.n_components() as f64).unwrap().sqrt();
+
+        (x.dot(&self.weights) + &self.offsets).mapv(|x| scale * x.cos())
+    }
+}
+
+/// Eigendecomposition of a symmetric matrix with the cyclic Jacobi method
+///
+/// Returns the eigenvalues and a matrix with the corresponding eigenvectors as columns, or an
+/// error if the off-diagonal entries have not vanished after `max_sweeps` sweeps.
+fn symmetric_eigen<F: Float>(
+    mut a: Array2<F>,
+    max_sweeps: usize,
+) -> Result<(Array1<F>, Array2<F>)> {
+    let n = a.nrows();
+    let mut v = Array2::eye(n);
+    let eps = F::epsilon();
+
+    let is_diagonal = |a: &Array2<F>| {
+        let off_diagonal = (0..n)
+            .flat_map(|i| (i + 1..n).map(move |j| (i, j)))
+            .map(|(i, j)| a[(i, j)] * a[(i, j)])
+            .sum::<F>();
+        let diagonal = a.diag().mapv(|x| x * x).sum();
+
+        off_diagonal <= eps * eps * diagonal
+    };
+
+    for _ in 0..max_sweeps {
+        if is_diagonal(&a) {
+            break;
+        }
+
+        for p in 0..n {
+            for q in p + 1..n {
+                if a[(p, q)] == F::zero() {
+                    continue;
+                }
+
+                // rotation annihilating the entry (p, q)
+                let theta = (a[(q, q)] - a[(p, p)]) / (F::from(2.0).unwrap() * a[(p, q)]);
+                let t = theta.signum() / (theta.abs() + (theta * theta + F::one()).sqrt());
+                let c = F::one() / (t * t + F::one()).sqrt();
+                let s = t * c;
+
+                for k in 0..n {
+                    let (akp, akq) = (a[(k, p)], a[(k, q)]);
+                    a[(k, p)] = c * akp - s * akq;
+                    a[(k, q)] = s * akp + c * akq;
+                }
+                for k in 0..n {
+                    let (apk, aqk) = (a[(p, k)], a[(q, k)]);
+                    a[(p, k)] = c * apk - s * aqk;
+                    a[(q, k)] = s * apk + c * aqk;
+                }
+                for k in 0..n {
+                    let (vkp, vkq) = (v[(k, p)], v[(k, q)]);
+                    v[(k, p)] = c * vkp - s * vkq;
+                    v[(k, q)] = s * vkp + c * vkq;
+                }
+            }
+        }
+    }
+
+    if !is_diagonal(&a) {
+        return Err(Error::NotConverged(format!(
+            "eigendecomposition of the landmark kernel after {} sweeps",
+            max_sweeps
+        )));
+    }
+
+    Ok((a.diag().to_owned(), v))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{symmetric_eigen, Nystroem, RandomFourierFeatures};
+    use crate::KernelMethod;
+    use approx::assert_abs_diff_eq;
+    use linfa::{dataset::Dataset, traits::Fit, traits::Transformer};
+    use ndarray::{array, Array2};
+
+    fn records() -> Array2<f64> {
+        Array2::from_shape_fn((60, 3), |(i, j)| {
+            ((i * 7 + j * 13 + i * j) % 23) as f64 / 23.0
+        })
+    }
+
+    #[test]
+    fn test_symmetric_eigen() {
+        let a = array![[4.0, 1.0, 2.0], [1.0, 3.0, 0.5], [2.0, 0.5, 5.0]];
+        let (vals, vecs) = symmetric_eigen(a.clone(), 100).unwrap();
+
+        let reconstructed = (&vecs * &vals.insert_axis(ndarray::Axis(0))).dot(&vecs.t());
+        for (x, y) in reconstructed.iter().zip(a.iter()) {
+            assert_abs_diff_eq!(x, y, epsilon = 1e-10);
+        }
+        for (x, y) in vecs.t().dot(&vecs).iter().zip(Array2::<f64>::eye(3).iter()) {
+            assert_abs_diff_eq!(x, y, epsilon = 1e-10);
+        }
+
+        // a single sweep does not diagonalize the matrix
+        assert!(symmetric_eigen(a, 1).is_err());
+    }
+
+    #[test]
+    fn test_nystroem_exact_with_all_landmarks() {
+        let records = records();
+        let dataset = Dataset::from(records.clone());
+
+        for method in vec![
+            KernelMethod::Gaussian(0.5),
+            KernelMethod::Polynomial(1.0, 2.0),
+            KernelMethod::Laplacian(1.0),
+        ] {
+            let nystroem = Nystroem::params(60)
+                .method(method.clone())
+                .fit(&dataset)
+                .unwrap();
+            let features = nystroem.transform(&records);
+
+            // with every observation as landmark the approximation reproduces the kernel
+            let approximation = features.dot(&features.t());
+            let kernel = method.cross(&records, &records);
+            for (x, y) in approximation.iter().zip(kernel.iter()) {
+                assert_abs_diff_eq!(x, y, epsilon = 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn test_nystroem_landmarks() {
+        let records = records();
+        let dataset = Dataset::from(records.clone());
+
+        let nystroem = Nystroem::params(10)
+            .method(KernelMethod::Gaussian(1.0))
+            .fit(&dataset)
+            .unwrap();
+
+        assert_eq!(nystroem.n_components(), 10);
+        assert_eq!(nystroem.transform(&records).dim(), (60, 10));
+        // the landmarks are observations of the training data
+        assert!(nystroem
+            .landmarks()
+            .genrows()
+            .into_iter()
+            .all(|l| records.genrows().into_iter().any(|r| r == l)));
+
+        assert!(Nystroem::<f64>::params(0).fit(&dataset).is_err());
+        assert!(Nystroem::<f64>::params(61).fit(&dataset).is_err());
+    }
+
+    #[test]
+    fn test_random_fourier_features() {
+        let records = records();
+        let dataset = Dataset::from(records.clone());
+
+        let rff = RandomFourierFeatures::params(20000)
+            .eps(2.0)
+            .fit(&dataset)
+            .unwrap();
+        let features = rff.transform(&records);
+
+        let approximation = features.dot(&features.t());
+        let kernel = KernelMethod::Gaussian(2.0).cross(&records, &records);
+
+        let max_error = approximation
+            .iter()
+            .zip(kernel.iter())
+            .map(|(x, y)| (x - y).abs())
+            .fold(0.0, f64::max);
+        assert!(max_error < 0.05);
+
+        assert!(RandomFourierFeatures::<f64>::params(10)
+            .eps(0.0)
+            .fit(&dataset)
+            .is_err());
+    }
+}
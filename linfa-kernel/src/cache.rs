@@ -0,0 +1,134 @@
+//! Row cache for lazily evaluated kernel matrices
+//!
+//! Instead of materializing the full kernel matrix, rows are computed on demand and kept in a
+//! cache of bounded size. If the cache is full, the least recently used row is evicted.
+use std::collections::{BTreeMap, HashMap};
+use std::mem;
+
+/// Least recently used cache of kernel rows
+#[derive(Debug)]
+pub struct RowCache<F> {
+    capacity: usize,
+    rows: HashMap<usize, (Vec<F>, u64)>,
+    // maps the time of last access to the row index
+    order: BTreeMap<u64, usize>,
+    clock: u64,
+}
+
+impl<F> RowCache<F> {
+    /// Create a cache for rows of length `n`, bounded by `size_mb` megabytes
+    ///
+    /// At least two rows are always kept, because the SMO solver updates pairs of variables.
+    pub fn new(size_mb: usize, n: usize) -> RowCache<F> {
+        let row_size = usize::max(n * mem::size_of::<F>(), 1);
+        let capacity = usize::max(size_mb * 1024 * 1024 / row_size, 2);
+
+        RowCache::with_capacity(capacity)
+    }
+
+    /// Create a cache holding at most `capacity` rows
+    pub fn with_capacity(capacity: usize) -> RowCache<F> {
+        RowCache {
+            capacity,
+            rows: HashMap::new(),
+            order: BTreeMap::new(),
+            clock: 0,
+        }
+    }
+
+    /// Maximal number of rows in the cache
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Number of rows currently in the cache
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Return a row and mark it as recently used
+    pub fn get(&mut self, idx: usize) -> Option<&[F]> {
+        let clock = self.clock + 1;
+
+        match self.rows.get_mut(&idx) {
+            Some((row, last_used)) => {
+                self.order.remove(last_used);
+                self.order.insert(clock, idx);
+                *last_used = clock;
+                self.clock = clock;
+
+                Some(row)
+            }
+            None => None,
+        }
+    }
+
+    /// Return a row without changing the order of eviction
+    pub fn peek(&self, idx: usize) -> Option<&[F]> {
+        self.rows.get(&idx).map(|(row, _)| row.as_slice())
+    }
+
+    /// Insert a row and evict the least recently used one, if the cache is full
+    pub fn insert(&mut self, idx: usize, row: Vec<F>) {
+        self.clock += 1;
+
+        if let Some((_, last_used)) = self.rows.remove(&idx) {
+            self.order.remove(&last_used);
+        } else if self.rows.len() >= self.capacity {
+            let oldest = *self.order.keys().next().unwrap();
+            let evicted = self.order.remove(&oldest).unwrap();
+            self.rows.remove(&evicted);
+        }
+
+        self.rows.insert(idx, (row, self.clock));
+        self.order.insert(self.clock, idx);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RowCache;
+
+    #[test]
+    fn test_capacity() {
+        // 1MB with rows of 1024 doubles
+        let cache = RowCache::<f64>::new(1, 1024);
+        assert_eq!(cache.capacity(), 128);
+
+        // at least two rows are kept
+        let cache = RowCache::<f64>::new(0, 1024);
+        assert_eq!(cache.capacity(), 2);
+    }
+
+    #[test]
+    fn test_eviction() {
+        let mut cache = RowCache::with_capacity(2);
+
+        cache.insert(0, vec![0.0]);
+        cache.insert(1, vec![1.0]);
+
+        // access the first row, so that the second row is evicted next
+        assert_eq!(cache.get(0), Some(&[0.0][..]));
+        cache.insert(2, vec![2.0]);
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.peek(1).is_none());
+        assert_eq!(cache.peek(0), Some(&[0.0][..]));
+
+        // peeking does not change the order
+        cache.peek(0);
+        cache.insert(3, vec![3.0]);
+        assert!(cache.peek(0).is_none());
+        assert_eq!(cache.get(2), Some(&[2.0][..]));
+        assert_eq!(cache.get(3), Some(&[3.0][..]));
+
+        // replacing a row keeps the size
+        cache.insert(3, vec![4.0]);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(3), Some(&[4.0][..]));
+    }
+}
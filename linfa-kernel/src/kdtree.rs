@@ -0,0 +1,218 @@
+//! K-d tree for exact neighbour search
+//!
+//! The tree recursively splits the observations at the median of the feature with the largest
+//! spread. Queries descend into the closer child first and only visit the other child, if the
+//! splitting plane is closer than the current candidates. This pruning is valid for all
+//! Minkowski distances.
+use linfa::Float;
+use ndarray::{ArrayView1, ArrayView2};
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use super::sparse::Metric;
+
+/// Maximal number of observations in a leaf
+const LEAF_SIZE: usize = 16;
+
+enum Node<F> {
+    Leaf(Vec<usize>),
+    Split {
+        dim: usize,
+        value: F,
+        left: Box<Node<F>>,
+        right: Box<Node<F>>,
+    },
+}
+
+/// A neighbour candidate, ordered by distance and index
+struct Candidate<F> {
+    distance: F,
+    index: usize,
+}
+
+impl<F: Float> PartialEq for Candidate<F> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<F: Float> Eq for Candidate<F> {}
+
+impl<F: Float> PartialOrd for Candidate<F> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<F: Float> Ord for Candidate<F> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.distance
+            .partial_cmp(&other.distance)
+            .unwrap_or(Ordering::Equal)
+            .then(self.index.cmp(&other.index))
+    }
+}
+
+pub struct KdTree<'a, F> {
+    data: ArrayView2<'a, F>,
+    metric: Metric,
+    root: Node<F>,
+}
+
+impl<'a, F: Float> KdTree<'a, F> {
+    pub fn new(data: ArrayView2<'a, F>, metric: Metric) -> KdTree<'a, F> {
+        let indices = (0..data.nrows()).collect();
+        let root = build(&data, indices);
+
+        KdTree { data, metric, root }
+    }
+
+    /// Return the `k` nearest neighbours of an observation, excluding the observation itself
+    pub fn nearest(&self, idx: usize, k: usize) -> Vec<usize> {
+        let point = self.data.row(idx);
+        let mut heap = BinaryHeap::with_capacity(k + 1);
+
+        self.nearest_in(&self.root, point, idx, k, &mut heap);
+
+        heap.into_sorted_vec()
+            .into_iter()
+            .map(|x| x.index)
+            .collect()
+    }
+
+    /// Return all neighbours within distance `radius` of an observation, excluding itself
+    pub fn within(&self, idx: usize, radius: F) -> Vec<usize> {
+        let point = self.data.row(idx);
+        let mut found = Vec::new();
+
+        self.within_in(&self.root, point, idx, radius, &mut found);
+        found.sort_unstable();
+
+        found
+    }
+
+    fn nearest_in(
+        &self,
+        node: &Node<F>,
+        point: ArrayView1<F>,
+        exclude: usize,
+        k: usize,
+        heap: &mut BinaryHeap<Candidate<F>>,
+    ) {
+        match node {
+            Node::Leaf(indices) => {
+                for &index in indices.iter().filter(|x| **x != exclude) {
+                    let candidate = Candidate {
+                        distance: self.metric.distance(point, self.data.row(index)),
+                        index,
+                    };
+
+                    if heap.len() < k {
+                        heap.push(candidate);
+                    } else if candidate < *heap.peek().unwrap() {
+                        heap.pop();
+                        heap.push(candidate);
+                    }
+                }
+            }
+            Node::Split {
+                dim,
+                value,
+                left,
+                right,
+            } => {
+                let diff = point[*dim] - *value;
+                let (near, far) = if diff <= F::zero() {
+                    (left, right)
+                } else {
+                    (right, left)
+                };
+
+                self.nearest_in(near, point, exclude, k, heap);
+
+                if heap.len() < k || diff.abs() <= heap.peek().unwrap().distance {
+                    self.nearest_in(far, point, exclude, k, heap);
+                }
+            }
+        }
+    }
+
+    fn within_in(
+        &self,
+        node: &Node<F>,
+        point: ArrayView1<F>,
+        exclude: usize,
+        radius: F,
+        found: &mut Vec<usize>,
+    ) {
+        match node {
+            Node::Leaf(indices) => found.extend(indices.iter().filter(|&&index| {
+                index != exclude && self.metric.distance(point, self.data.row(index)) <= radius
+            })),
+            Node::Split {
+                dim,
+                value,
+                left,
+                right,
+            } => {
+                let diff = point[*dim] - *value;
+
+                if diff <= radius {
+                    self.within_in(left, point, exclude, radius, found);
+                }
+                if -diff <= radius {
+                    self.within_in(right, point, exclude, radius, found);
+                }
+            }
+        }
+    }
+}
+
+fn build<F: Float>(data: &ArrayView2<F>, mut indices: Vec<usize>) -> Node<F> {
+    if indices.len() <= LEAF_SIZE {
+        return Node::Leaf(indices);
+    }
+
+    // split along the feature with the largest spread
+    let (dim, spread) = (0..data.ncols())
+        .map(|dim| {
+            let (min, max) = indices
+                .iter()
+                .map(|i| data[(*i, dim)])
+                .fold((F::infinity(), F::neg_infinity()), |(min, max), x| {
+                    (min.min(x), max.max(x))
+                });
+
+            (dim, max - min)
+        })
+        .fold(
+            (0, F::neg_infinity()),
+            |best, x| {
+                if x.1 > best.1 {
+                    x
+                } else {
+                    best
+                }
+            },
+        );
+
+    // all points are identical
+    if spread <= F::zero() {
+        return Node::Leaf(indices);
+    }
+
+    indices.sort_by(|a, b| {
+        data[(*a, dim)]
+            .partial_cmp(&data[(*b, dim)])
+            .unwrap_or(Ordering::Equal)
+    });
+    let right = indices.split_off(indices.len() / 2);
+    let value = data[(indices[indices.len() - 1], dim)];
+
+    Node::Split {
+        dim,
+        value,
+        left: Box::new(build(data, indices)),
+        right: Box::new(build(data, right)),
+    }
+}
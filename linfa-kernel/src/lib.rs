@@ -1,20 +1,41 @@
 //! Kernel methods
 //!
+mod approximation;
+mod cache;
+mod kdtree;
 mod sparse;
 
+#[cfg(feature = "rayon")]
+use ndarray::parallel::prelude::*;
 use ndarray::prelude::*;
 use ndarray::{linalg::Dot, Data};
 #[cfg(feature = "serde")]
 use serde_crate::{Deserialize, Serialize};
 use sprs::CsMat;
+use std::fmt;
+use std::sync::{Arc, Mutex};
 
-use linfa::{dataset::Dataset, dataset::Records, dataset::Targets, traits::Transformer, Float};
+pub use approximation::{
+    Nystroem, NystroemParams, RandomFourierFeatures, RandomFourierFeaturesParams,
+};
+pub use cache::RowCache;
+pub use sparse::{Graph, Metric, NeighbourSearch, Neighbourhood};
 
-/// Kernel representation, can be either dense or sparse
+use linfa::{
+    dataset::Dataset, dataset::Records, dataset::Targets, error::Result, traits::Transformer, Float,
+};
+
+/// Number of rows evaluated at once when computing weighted sums for new observations
+const CROSS_BATCH_SIZE: usize = 1024;
+
+/// Kernel representation, can be either dense, sparse or lazy
 #[derive(Clone)]
 pub enum KernelType {
     Dense,
-    Sparse(usize),
+    /// Only evaluate the kernel for neighbouring observations
+    Sparse(Neighbourhood),
+    /// Compute rows on demand and cache them, the cache size is given in megabytes
+    Lazy(usize),
 }
 
 /// Storage for the kernel matrix
@@ -27,6 +48,9 @@ pub enum KernelType {
 pub enum KernelInner<F: Float> {
     Dense(Array2<F>),
     Sparse(CsMat<F>),
+    /// Lazily evaluated kernel, the cache is not serialized
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Lazy(Mutex<RowCache<F>>),
 }
 
 /// A generic kernel
@@ -62,29 +86,61 @@ where
 }
 
 impl<'a, F: Float> Kernel<ArrayView2<'a, F>> {
+    /// Create a new kernel
+    ///
+    /// # Panics
+    ///
+    /// If the neighbourhood of a sparse kernel is invalid for the dataset, see `try_new`
     pub fn new(
         dataset: ArrayView2<'a, F>,
         method: KernelMethod<F>,
         kind: KernelType,
         linear: bool,
     ) -> Kernel<ArrayView2<'a, F>> {
+        match Kernel::try_new(dataset, method, kind, linear) {
+            Ok(kernel) => kernel,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Create a new kernel, returning an error if the neighbourhood of a sparse kernel is
+    /// invalid for the dataset
+    pub fn try_new(
+        dataset: ArrayView2<'a, F>,
+        method: KernelMethod<F>,
+        kind: KernelType,
+        linear: bool,
+    ) -> Result<Kernel<ArrayView2<'a, F>>> {
         let inner = match kind {
             KernelType::Dense => KernelInner::Dense(dense_from_fn(&dataset, &method)),
-            KernelType::Sparse(k) => KernelInner::Sparse(sparse_from_fn(&dataset, k, &method)),
+            KernelType::Sparse(neighbourhood) => {
+                KernelInner::Sparse(sparse_from_fn(&dataset, &neighbourhood, &method)?)
+            }
+            KernelType::Lazy(size_mb) => {
+                KernelInner::Lazy(Mutex::new(RowCache::new(size_mb, dataset.nrows())))
+            }
         };
 
-        Kernel {
+        Ok(Kernel {
             inner,
             method,
             dataset,
             linear,
-        }
+        })
     }
 
     pub fn dot(&self, rhs: &ArrayView2<F>) -> Array2<F> {
         match &self.inner {
             KernelInner::Dense(mat) => mat.dot(rhs),
             KernelInner::Sparse(mat) => mat.dot(rhs),
+            KernelInner::Lazy(_) => {
+                let mut out = Array2::zeros((self.size(), rhs.ncols()));
+                for (i, mut row) in out.outer_iter_mut().enumerate() {
+                    row.assign(&Array1::from(self.column(i)).dot(rhs));
+                }
+
+                out
+            }
         }
     }
 
@@ -100,6 +156,9 @@ impl<'a, F: Float> Kernel<ArrayView2<'a, F>> {
 
                 sum
             }
+            KernelInner::Lazy(_) => (0..self.size())
+                .map(|i| self.column(i).into_iter().sum())
+                .collect(),
         }
     }
 
@@ -107,6 +166,7 @@ impl<'a, F: Float> Kernel<ArrayView2<'a, F>> {
         match &self.inner {
             KernelInner::Dense(mat) => mat.ncols(),
             KernelInner::Sparse(mat) => mat.cols(),
+            KernelInner::Lazy(_) => self.dataset.nrows(),
         }
     }
 
@@ -124,13 +184,16 @@ impl<'a, F: Float> Kernel<ArrayView2<'a, F>> {
                     .map(|(_, val)| *val)
                     .collect()
             }
+            KernelInner::Lazy(_) => (0..self.size())
+                .flat_map(|i| self.column(i).into_iter().skip(i + 1))
+                .collect(),
         }
     }
 
     pub fn diagonal(&self) -> Array1<F> {
         match &self.inner {
             KernelInner::Dense(mat) => mat.diag().to_owned(),
-            KernelInner::Sparse(_) => self
+            KernelInner::Sparse(_) | KernelInner::Lazy(_) => self
                 .dataset
                 .outer_iter()
                 .map(|x| self.method.distance(x.view(), x.view()))
@@ -144,6 +207,27 @@ impl<'a, F: Float> Kernel<ArrayView2<'a, F>> {
             KernelInner::Sparse(mat) => (0..self.size())
                 .map(|j| *mat.get(j, i).unwrap_or(&F::neg_zero()))
                 .collect::<Vec<_>>(),
+            KernelInner::Lazy(cache) => {
+                let mut cache = cache.lock().unwrap();
+                if let Some(row) = cache.get(i) {
+                    return row.to_vec();
+                }
+
+                // exploit the symmetry and reuse entries of cached rows
+                let row = self
+                    .dataset
+                    .outer_iter()
+                    .enumerate()
+                    .map(|(j, x)| match cache.peek(j) {
+                        Some(other) => other[i],
+                        None => self.method.distance(self.dataset.row(i), x),
+                    })
+                    .collect::<Vec<_>>();
+
+                cache.insert(i, row.clone());
+
+                row
+            }
         }
     }
 
@@ -155,6 +239,59 @@ impl<'a, F: Float> Kernel<ArrayView2<'a, F>> {
             .sum()
     }
 
+    /// Evaluate the kernel between new observations and the dataset of the kernel
+    ///
+    /// Returns the cross-kernel matrix `K(x, dataset)` with shape `(x.nrows(), self.size())`.
+    pub fn cross<D: Data<Elem = F>>(&self, x: &ArrayBase<D, Ix2>) -> Array2<F> {
+        self.method.cross(x, &self.dataset)
+    }
+
+    /// Evaluate the cross-kernel matrix in batches of rows
+    ///
+    /// This bounds the memory to `batch_size * self.size()` elements at a time for large sets of
+    /// new observations.
+    ///
+    /// # Panics
+    ///
+    /// If `batch_size` is zero
+    pub fn cross_batched<'b, D: Data<Elem = F>>(
+        &'b self,
+        x: &'b ArrayBase<D, Ix2>,
+        batch_size: usize,
+    ) -> impl Iterator<Item = Array2<F>> + 'b {
+        assert!(batch_size > 0, "batch size should be greater than zero");
+
+        // reborrow, so that the iterator does not depend on the lifetime of the dataset
+        let (method, dataset) = (&self.method, self.dataset.view());
+
+        (0..x.nrows()).step_by(batch_size).map(move |start| {
+            let end = usize::min(start + batch_size, x.nrows());
+
+            method.cross(&x.slice(s![start..end, ..]), &dataset)
+        })
+    }
+
+    /// Weighted sum of kernel evaluations for each new observation
+    ///
+    /// The vectorized version of `weighted_sum`, equivalent to `K(x, dataset) * weights`.
+    pub fn weighted_sums<D: Data<Elem = F>>(
+        &self,
+        weights: &[F],
+        x: &ArrayBase<D, Ix2>,
+    ) -> Array1<F> {
+        let weights = ArrayView1::from(weights);
+        let mut sums = Array1::zeros(x.nrows());
+
+        for (batch, mut sums) in self
+            .cross_batched(x, CROSS_BATCH_SIZE)
+            .zip(sums.axis_chunks_iter_mut(Axis(0), CROSS_BATCH_SIZE))
+        {
+            sums.assign(&batch.dot(&weights));
+        }
+
+        sums
+    }
+
     pub fn is_linear(&self) -> bool {
         self.linear
     }
@@ -175,19 +312,75 @@ impl<'a, F: Float> Records for Kernel<ArrayView2<'a, F>> {
     }
 }
 
+/// A user-supplied kernel function
+///
+/// This is implemented for all closures taking two observations and returning their similarity.
+/// The function should be symmetric and positive semi-definite to be a valid kernel.
+pub trait KernelFn<F>: Send + Sync {
+    fn distance(&self, a: ArrayView1<F>, b: ArrayView1<F>) -> F;
+}
+
+impl<F, T: Fn(ArrayView1<F>, ArrayView1<F>) -> F + Send + Sync> KernelFn<F> for T {
+    fn distance(&self, a: ArrayView1<F>, b: ArrayView1<F>) -> F {
+        self(a, b)
+    }
+}
+
 #[cfg_attr(
     feature = "serde",
     derive(Serialize, Deserialize),
     serde(crate = "serde_crate")
 )]
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub enum KernelMethod<F> {
+    /// Gaussian kernel `exp(-|a - b|^2 / eps)`
     Gaussian(F),
+    /// Linear kernel `<a, b>`
     Linear,
+    /// Polynomial kernel `(<a, b> + c)^d`
     Polynomial(F, F),
+    /// Laplacian kernel `exp(-|a - b|_1 / eps)`
+    Laplacian(F),
+    /// Sigmoid kernel `tanh(gamma * <a, b> + c)`
+    Sigmoid(F, F),
+    /// Exponential chi-squared kernel `exp(-sum((a - b)^2 / (a + b)) / eps)` for non-negative
+    /// features, like histograms
+    ChiSquared(F),
+    /// Histogram intersection kernel `sum(min(a, b))` for non-negative features
+    HistogramIntersection,
+    /// Cosine similarity `<a, b> / (|a| |b|)`
+    Cosine,
+    /// User-supplied kernel function, can not be serialized
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Custom(Arc<dyn KernelFn<F>>),
+}
+
+impl<F: fmt::Debug> fmt::Debug for KernelMethod<F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KernelMethod::Gaussian(eps) => f.debug_tuple("Gaussian").field(eps).finish(),
+            KernelMethod::Linear => write!(f, "Linear"),
+            KernelMethod::Polynomial(c, d) => {
+                f.debug_tuple("Polynomial").field(c).field(d).finish()
+            }
+            KernelMethod::Laplacian(eps) => f.debug_tuple("Laplacian").field(eps).finish(),
+            KernelMethod::Sigmoid(gamma, c) => {
+                f.debug_tuple("Sigmoid").field(gamma).field(c).finish()
+            }
+            KernelMethod::ChiSquared(eps) => f.debug_tuple("ChiSquared").field(eps).finish(),
+            KernelMethod::HistogramIntersection => write!(f, "HistogramIntersection"),
+            KernelMethod::Cosine => write!(f, "Cosine"),
+            KernelMethod::Custom(_) => write!(f, "Custom(..)"),
+        }
+    }
 }
 
 impl<F: Float> KernelMethod<F> {
+    /// Create a kernel from a user-supplied function
+    pub fn custom<T: KernelFn<F> + 'static>(fnc: T) -> KernelMethod<F> {
+        KernelMethod::Custom(Arc::new(fnc))
+    }
+
     pub fn distance(&self, a: ArrayView1<F>, b: ArrayView1<F>) -> F {
         match *self {
             KernelMethod::Gaussian(eps) => {
@@ -201,6 +394,105 @@ impl<F: Float> KernelMethod<F> {
             }
             KernelMethod::Linear => a.dot(&b),
             KernelMethod::Polynomial(c, d) => (a.dot(&b) + c).powf(d),
+            KernelMethod::Laplacian(eps) => {
+                let distance = a
+                    .iter()
+                    .zip(b.iter())
+                    .map(|(x, y)| (*x - *y).abs())
+                    .sum::<F>();
+
+                (-distance / eps).exp()
+            }
+            KernelMethod::Sigmoid(gamma, c) => (gamma * a.dot(&b) + c).tanh(),
+            KernelMethod::ChiSquared(eps) => {
+                let distance = a
+                    .iter()
+                    .zip(b.iter())
+                    // skip bins which are empty in both histograms
+                    .filter(|(x, y)| **x + **y > F::zero())
+                    .map(|(x, y)| (*x - *y) * (*x - *y) / (*x + *y))
+                    .sum::<F>();
+
+                (-distance / eps).exp()
+            }
+            KernelMethod::HistogramIntersection => {
+                a.iter().zip(b.iter()).map(|(x, y)| x.min(*y)).sum()
+            }
+            KernelMethod::Cosine => {
+                let norm = (a.dot(&a) * b.dot(&b)).sqrt();
+
+                if norm > F::zero() {
+                    a.dot(&b) / norm
+                } else {
+                    F::zero()
+                }
+            }
+            KernelMethod::Custom(ref fnc) => fnc.distance(a, b),
+        }
+    }
+
+    /// Evaluate the kernel between all pairs of rows in `a` and `b`
+    ///
+    /// Returns a matrix with shape `(a.nrows(), b.nrows())`.
+    pub fn cross<D1: Data<Elem = F>, D2: Data<Elem = F>>(
+        &self,
+        a: &ArrayBase<D1, Ix2>,
+        b: &ArrayBase<D2, Ix2>,
+    ) -> Array2<F> {
+        assert_eq!(
+            a.ncols(),
+            b.ncols(),
+            "observations should have the same number of features"
+        );
+
+        let (a, b) = (a.view(), b.view());
+        if let Some(similarity) = self.gram_cross(&a, &b) {
+            return similarity;
+        }
+
+        let mut similarity = Array2::zeros((a.nrows(), b.nrows()));
+        for_each_row(&mut similarity, |i, mut row| {
+            for (val, y) in row.iter_mut().zip(b.outer_iter()) {
+                *val = self.distance(a.row(i), y);
+            }
+        });
+
+        similarity
+    }
+
+    /// Evaluate kernels, which only depend on inner products, with a matrix product
+    ///
+    /// The matrix product is computed by BLAS and much faster than evaluating each pair of
+    /// observations separately. The Gaussian kernel uses `|a - b|^2 = |a|^2 + |b|^2 - 2 <a, b>`
+    /// on records centered by the mean of `b`. The distance does not change with the centering,
+    /// but it avoids the cancellation of large norms for features with a large magnitude.
+    /// Returns `None` for all other kernels.
+    fn gram_cross(&self, a: &ArrayView2<F>, b: &ArrayView2<F>) -> Option<Array2<F>> {
+        match *self {
+            KernelMethod::Linear => Some(a.dot(&b.t())),
+            KernelMethod::Gaussian(eps) => {
+                let (a, b) = match b.mean_axis(Axis(0)) {
+                    Some(mean) => (a - &mean, b - &mean),
+                    None => (a.to_owned(), b.to_owned()),
+                };
+
+                let mut similarity = a.dot(&b.t());
+                let norm_a = a.map_axis(Axis(1), |x| x.dot(&x));
+                let norm_b = b.map_axis(Axis(1), |x| x.dot(&x));
+                let two = F::from(2.0).unwrap();
+
+                for_each_row(&mut similarity, |i, mut row| {
+                    for (val, norm_b) in row.iter_mut().zip(norm_b.iter()) {
+                        // clamp negative distances caused by rounding errors
+                        let distance = (norm_a[i] + *norm_b - two * *val).max(F::zero());
+
+                        *val = (-distance / eps).exp();
+                    }
+                });
+
+                Some(similarity)
+            }
+            _ => None,
         }
     }
 
@@ -226,6 +518,14 @@ impl<F: Float> KernelParams<F> {
 
         self
     }
+
+    /// Create a kernel for the records, returning an error instead of panicking if the
+    /// neighbourhood of a sparse kernel is invalid
+    pub fn try_transform<'a>(&self, x: ArrayView2<'a, F>) -> Result<Kernel<ArrayView2<'a, F>>> {
+        let is_linear = self.method.is_linear();
+
+        Kernel::try_new(x, self.method.clone(), self.kind.clone(), is_linear)
+    }
 }
 
 impl<'a, F: Float> Transformer<&'a Array2<F>, Kernel<ArrayView2<'a, F>>> for KernelParams<F> {
@@ -258,7 +558,7 @@ impl<'a, F: Float, T: Targets>
             is_linear,
         );
 
-        Dataset::new(kernel, &x.targets)
+        Dataset::new(kernel, &x.targets).with_weights(weights_of(x))
     }
 }
 
@@ -276,44 +576,334 @@ impl<'a, F: Float, T: Targets>
 
         let kernel = Kernel::new(x.records, self.method.clone(), self.kind.clone(), is_linear);
 
-        Dataset::new(kernel, x.targets.as_slice())
+        Dataset::new(kernel, x.targets.as_slice()).with_weights(weights_of(x))
     }
 }
 
-fn dense_from_fn<F: Float, D: Data<Elem = F>>(
-    dataset: &ArrayBase<D, Ix2>,
-    method: &KernelMethod<F>,
-) -> Array2<F> {
+/// Copy the observation weights, which are kept for the kernel matrix
+fn weights_of<R: Records, T: Targets>(x: &Dataset<R, T>) -> Vec<f32> {
+    x.weights().map(|w| w.to_vec()).unwrap_or_default()
+}
+
+/// Apply a function to each row of a matrix, in parallel if the `rayon` feature is enabled
+#[cfg(feature = "rayon")]
+fn for_each_row<F: Float, G: Fn(usize, ArrayViewMut1<F>) + Send + Sync>(
+    matrix: &mut Array2<F>,
+    fnc: G,
+) {
+    matrix
+        .axis_iter_mut(Axis(0))
+        .into_par_iter()
+        .enumerate()
+        .for_each(|(i, row)| fnc(i, row));
+}
+
+/// Apply a function to each row of a matrix, in parallel if the `rayon` feature is enabled
+#[cfg(not(feature = "rayon"))]
+fn for_each_row<F: Float, G: Fn(usize, ArrayViewMut1<F>)>(matrix: &mut Array2<F>, fnc: G) {
+    matrix
+        .axis_iter_mut(Axis(0))
+        .enumerate()
+        .for_each(|(i, row)| fnc(i, row));
+}
+
+/// Set each value to the result of a function of its index, in parallel if the `rayon` feature is
+/// enabled
+#[cfg(feature = "rayon")]
+fn fill_values<F: Float, G: Fn(usize) -> F + Send + Sync>(values: &mut [F], fnc: G) {
+    values
+        .par_iter_mut()
+        .enumerate()
+        .for_each(|(i, val)| *val = fnc(i));
+}
+
+/// Set each value to the result of a function of its index, in parallel if the `rayon` feature is
+/// enabled
+#[cfg(not(feature = "rayon"))]
+fn fill_values<F: Float, G: Fn(usize) -> F>(values: &mut [F], fnc: G) {
+    values
+        .iter_mut()
+        .enumerate()
+        .for_each(|(i, val)| *val = fnc(i));
+}
+
+fn dense_from_fn<F: Float>(dataset: &ArrayView2<F>, method: &KernelMethod<F>) -> Array2<F> {
+    if let Some(mut similarity) = method.gram_cross(dataset, dataset) {
+        // the distance of an observation to itself is zero, without rounding errors
+        if let KernelMethod::Gaussian(_) = method {
+            similarity.diag_mut().fill(F::one());
+        }
+
+        return similarity;
+    }
+
     let n_observations = dataset.len_of(Axis(0));
-    let mut similarity = Array2::eye(n_observations);
+    let mut similarity = Array2::zeros((n_observations, n_observations));
 
-    for i in 0..n_observations {
-        for j in 0..n_observations {
-            let a = dataset.row(i);
-            let b = dataset.row(j);
+    // only compute the upper triangle, the kernel is symmetric
+    for_each_row(&mut similarity, |i, mut row| {
+        let a = dataset.row(i);
 
-            similarity[(i, j)] = method.distance(a, b);
+        for j in i..n_observations {
+            row[j] = method.distance(a, dataset.row(j));
+        }
+    });
+
+    // and mirror it to the lower triangle
+    for i in 0..n_observations {
+        for j in 0..i {
+            similarity[(i, j)] = similarity[(j, i)];
         }
     }
 
     similarity
 }
 
-fn sparse_from_fn<F: Float, D: Data<Elem = F>>(
-    dataset: &ArrayBase<D, Ix2>,
-    k: usize,
+fn sparse_from_fn<F: Float>(
+    dataset: &ArrayView2<F>,
+    neighbourhood: &Neighbourhood,
     method: &KernelMethod<F>,
-) -> CsMat<F> {
-    let mut data = sparse::adjacency_matrix(dataset, k);
+) -> Result<CsMat<F>> {
+    let mut data = sparse::adjacency_matrix(dataset, neighbourhood)?;
+
+    // row and column of each non-zero entry
+    let rows = data
+        .indptr()
+        .windows(2)
+        .enumerate()
+        .flat_map(|(i, x)| (x[0]..x[1]).map(move |_| i))
+        .collect::<Vec<_>>();
+    let cols = data.indices().to_vec();
+
+    fill_values(data.data_mut(), |idx| {
+        method.distance(dataset.row(rows[idx]), dataset.row(cols[idx]))
+    });
+
+    Ok(data)
+}
 
-    for (i, mut vec) in data.outer_iterator_mut().enumerate() {
-        for (j, val) in vec.iter_mut() {
-            let a = dataset.row(i);
-            let b = dataset.row(j);
+#[cfg(test)]
+mod tests {
+    use super::{Kernel, KernelMethod, KernelType, Neighbourhood};
+    use approx::assert_abs_diff_eq;
+    use linfa::traits::Transformer;
+    use ndarray::{array, stack, Array2, ArrayView1, Axis};
 
-            *val = method.distance(a, b);
+    #[test]
+    fn test_cross_kernel() {
+        let train = array![[0.0, 0.0], [1.0, 0.0], [0.0, 2.0]];
+        let test = array![[1.0, 1.0], [0.0, 0.0]];
+
+        let kernel = Kernel::new(
+            train.view(),
+            KernelMethod::Gaussian(1.0),
+            KernelType::Dense,
+            false,
+        );
+
+        let cross = kernel.cross(&test);
+        assert_eq!(cross.dim(), (2, 3));
+        for (i, x) in test.outer_iter().enumerate() {
+            for (j, y) in train.outer_iter().enumerate() {
+                assert_abs_diff_eq!(cross[(i, j)], (-(&x - &y).mapv(|v: f64| v * v).sum()).exp());
+            }
+        }
+
+        // the cross kernel with the training data is the kernel matrix itself
+        let cross = kernel.cross(&train);
+        for i in 0..3 {
+            for (a, b) in cross.row(i).iter().zip(kernel.column(i).iter()) {
+                assert_abs_diff_eq!(a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn test_gaussian_kernel_large_magnitude() {
+        // small distances between observations far away from the origin
+        let train = Array2::from_shape_fn((8, 3), |(i, j)| 1e6 + (i * 3 + j) as f64 / 10.0);
+        let test = Array2::from_shape_fn((5, 3), |(i, j)| 1e6 + (i + j) as f64 / 5.0);
+        let method = KernelMethod::Gaussian(2.0);
+
+        let kernel = Kernel::new(train.view(), method.clone(), KernelType::Dense, false);
+        for (i, x) in train.outer_iter().enumerate() {
+            // the diagonal is exactly one
+            assert_eq!(kernel.column(i)[i], 1.0);
+
+            for (j, y) in train.outer_iter().enumerate() {
+                assert_abs_diff_eq!(kernel.column(j)[i], method.distance(x, y), epsilon = 1e-10);
+            }
+        }
+
+        let cross = kernel.cross(&test);
+        for (i, x) in test.outer_iter().enumerate() {
+            for (j, y) in train.outer_iter().enumerate() {
+                assert_abs_diff_eq!(cross[(i, j)], method.distance(x, y), epsilon = 1e-10);
+            }
         }
     }
 
-    data
+    #[test]
+    fn test_batched_cross_kernel() {
+        let train = Array2::from_shape_fn((10, 3), |(i, j)| (i * 3 + j) as f64 / 30.0);
+        let test = Array2::from_shape_fn((7, 3), |(i, j)| (i + j) as f64 / 10.0);
+
+        let kernel = Kernel::new(
+            train.view(),
+            KernelMethod::Polynomial(1.0, 2.0),
+            KernelType::Sparse(Neighbourhood::knn(3)),
+            false,
+        );
+
+        let batches = kernel.cross_batched(&test, 3).collect::<Vec<_>>();
+        assert_eq!(batches.len(), 3);
+        assert_eq!(batches[2].dim(), (1, 10));
+
+        let views = batches.iter().map(|x| x.view()).collect::<Vec<_>>();
+        let batched = stack(Axis(0), &views).unwrap();
+        for (a, b) in batched.iter().zip(kernel.cross(&test).iter()) {
+            assert_abs_diff_eq!(a, b);
+        }
+
+        // the vectorized weighted sum agrees with the single sample version
+        let weights = (0..10).map(|x| x as f64 - 4.5).collect::<Vec<_>>();
+        let sums = kernel.weighted_sums(&weights, &test);
+        for (sum, x) in sums.iter().zip(test.outer_iter()) {
+            assert_abs_diff_eq!(*sum, kernel.weighted_sum(&weights, x), epsilon = 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_kernel_methods() {
+        let a = array![1.0, 0.0, 2.0];
+        let b = array![0.0, 1.0, 2.0];
+        let (a, b) = (a.view(), b.view());
+
+        assert_abs_diff_eq!(KernelMethod::Laplacian(2.0).distance(a, b), (-1.0f64).exp());
+        assert_abs_diff_eq!(
+            KernelMethod::Sigmoid(0.5, -1.0).distance(a, b),
+            1.0f64.tanh()
+        );
+        assert_abs_diff_eq!(
+            KernelMethod::ChiSquared(1.0).distance(a, b),
+            (-2.0f64).exp()
+        );
+        assert_abs_diff_eq!(KernelMethod::HistogramIntersection.distance(a, b), 2.0);
+        assert_abs_diff_eq!(KernelMethod::Cosine.distance(a, b), 0.8);
+        assert_abs_diff_eq!(
+            KernelMethod::Cosine.distance(a, array![0.0, 0.0, 0.0].view()),
+            0.0
+        );
+
+        let custom = KernelMethod::custom(|a: ArrayView1<f64>, b: ArrayView1<f64>| a.dot(&b) * 2.0);
+        assert_abs_diff_eq!(custom.distance(a, b), 8.0);
+        assert_eq!(format!("{:?}", custom), "Custom(..)");
+    }
+
+    #[test]
+    fn test_custom_kernel_params() {
+        let data = array![[1.0, 2.0], [3.0, 4.0]];
+
+        let kernel = Kernel::params()
+            .method(KernelMethod::custom(
+                |a: ArrayView1<f64>, b: ArrayView1<f64>| a.dot(&b),
+            ))
+            .transform(&data);
+
+        assert_eq!(kernel.column(1), vec![11.0, 25.0]);
+        assert!(!kernel.is_linear());
+    }
+
+    #[test]
+    fn test_lazy_kernel() {
+        let data = Array2::from_shape_fn((20, 3), |(i, j)| ((i * 7 + j * 5) % 11) as f64 / 11.0);
+
+        let dense = Kernel::new(
+            data.view(),
+            KernelMethod::Gaussian(0.5),
+            KernelType::Dense,
+            false,
+        );
+        let lazy = Kernel::new(
+            data.view(),
+            KernelMethod::Gaussian(0.5),
+            KernelType::Lazy(0),
+            false,
+        );
+
+        assert_eq!(lazy.size(), 20);
+        // with a capacity of two rows, most rows are evicted in between
+        for i in (0..20).chain((0..20).rev()) {
+            for (a, b) in lazy.column(i).iter().zip(dense.column(i).iter()) {
+                assert_abs_diff_eq!(a, b, epsilon = 1e-12);
+            }
+        }
+
+        for (a, b) in lazy.sum().iter().zip(dense.sum().iter()) {
+            assert_abs_diff_eq!(a, b, epsilon = 1e-12);
+        }
+        for (a, b) in lazy.diagonal().iter().zip(dense.diagonal().iter()) {
+            assert_abs_diff_eq!(a, b, epsilon = 1e-12);
+        }
+        for (a, b) in lazy
+            .to_upper_triangle()
+            .iter()
+            .zip(dense.to_upper_triangle().iter())
+        {
+            assert_abs_diff_eq!(a, b, epsilon = 1e-12);
+        }
+
+        let rhs = Array2::from_shape_fn((20, 2), |(i, j)| (i + j) as f64);
+        for (a, b) in lazy
+            .dot(&rhs.view())
+            .iter()
+            .zip(dense.dot(&rhs.view()).iter())
+        {
+            assert_abs_diff_eq!(a, b, epsilon = 1e-10);
+        }
+    }
+
+    #[test]
+    fn test_kernel_construction() {
+        let data = Array2::from_shape_fn((15, 2), |(i, j)| ((i * 3 + j * 7) % 5) as f64 / 5.0);
+
+        for method in vec![
+            KernelMethod::Gaussian(0.3),
+            KernelMethod::Linear,
+            KernelMethod::Polynomial(1.0, 2.0),
+        ] {
+            let dense = Kernel::new(data.view(), method.clone(), KernelType::Dense, false);
+            let sparse = Kernel::new(
+                data.view(),
+                method.clone(),
+                KernelType::Sparse(Neighbourhood::knn(3)),
+                false,
+            );
+
+            for i in 0..15 {
+                let (dense, sparse) = (dense.column(i), sparse.column(i));
+
+                for j in 0..15 {
+                    let expected = method.distance(data.row(i), data.row(j));
+
+                    assert_abs_diff_eq!(dense[j], expected, epsilon = 1e-12);
+                    if sparse[j] != 0.0 {
+                        assert_abs_diff_eq!(sparse[j], expected, epsilon = 1e-12);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_sparse_kernel() {
+        let data = Array2::from_shape_fn((5, 2), |(i, j)| (i + j) as f64);
+
+        // five observations have at most four neighbours
+        let params = Kernel::params().kind(KernelType::Sparse(Neighbourhood::knn(5)));
+        assert!(params.try_transform(data.view()).is_err());
+
+        let params = Kernel::params().kind(KernelType::Sparse(Neighbourhood::knn(4)));
+        assert!(params.try_transform(data.view()).is_ok());
+    }
 }
@@ -1,94 +1,418 @@
 use hnsw::{Params, Searcher, HNSW};
+use linfa::error::{Error, Result};
 use linfa::Float;
 use ndarray::{ArrayBase, ArrayView1, Axis, Data, Ix2};
 use space::{MetricPoint, Neighbor};
 use sprs::{CsMat, CsMatBase};
 
-/// Implementation of euclidean distance for ndarray
-struct Euclidean<'a, F>(ArrayView1<'a, F>);
+use super::kdtree::KdTree;
 
-impl<F: Float> MetricPoint for Euclidean<'_, F> {
+/// Distance metric used to find the neighbours of an observation
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Metric {
+    /// Square root of the sum of squared differences
+    Euclidean,
+    /// Sum of absolute differences
+    Manhattan,
+    /// Largest absolute difference
+    Chebyshev,
+}
+
+impl Metric {
+    pub fn distance<F: Float>(&self, a: ArrayView1<F>, b: ArrayView1<F>) -> F {
+        let diffs = a.iter().zip(b.iter()).map(|(&a, &b)| (a - b).abs());
+
+        match self {
+            Metric::Euclidean => diffs.map(|x| x * x).sum::<F>().sqrt(),
+            Metric::Manhattan => diffs.sum(),
+            Metric::Chebyshev => diffs.fold(F::zero(), F::max),
+        }
+    }
+}
+
+/// Strategy to search the neighbours of an observation
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NeighbourSearch {
+    /// Compare each pair of observations, exact but quadratic in the number of observations
+    BruteForce,
+    /// Exact search with a k-d tree, fast for low-dimensional data
+    KdTree,
+    /// Approximate search with a hierarchical navigable small world graph
+    Hnsw,
+}
+
+/// Construction of the adjacency graph
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Graph {
+    /// Connect two observations, if either is among the `k` nearest neighbours of the other
+    Knn(usize),
+    /// Connect two observations, if both are among the `k` nearest neighbours of each other
+    MutualKnn(usize),
+    /// Connect all observations within the given distance of each other
+    Radius(f64),
+}
+
+/// Neighbourhood defining the non-zero entries of a sparse kernel
+///
+/// ```
+/// use linfa_kernel::{Metric, NeighbourSearch, Neighbourhood};
+///
+/// let neighbourhood = Neighbourhood::mutual_knn(10)
+///     .search(NeighbourSearch::KdTree)
+///     .metric(Metric::Manhattan);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Neighbourhood {
+    graph: Graph,
+    search: NeighbourSearch,
+    metric: Metric,
+}
+
+impl Neighbourhood {
+    /// Symmetric k-nearest neighbour graph, searched approximately with HNSW
+    pub fn knn(k: usize) -> Neighbourhood {
+        Neighbourhood {
+            graph: Graph::Knn(k),
+            search: NeighbourSearch::Hnsw,
+            metric: Metric::Euclidean,
+        }
+    }
+
+    /// Mutual k-nearest neighbour graph, searched approximately with HNSW
+    pub fn mutual_knn(k: usize) -> Neighbourhood {
+        Neighbourhood {
+            graph: Graph::MutualKnn(k),
+            ..Neighbourhood::knn(k)
+        }
+    }
+
+    /// Epsilon-radius graph, searched exactly with a k-d tree
+    pub fn radius(eps: f64) -> Neighbourhood {
+        Neighbourhood {
+            graph: Graph::Radius(eps),
+            search: NeighbourSearch::KdTree,
+            metric: Metric::Euclidean,
+        }
+    }
+
+    pub fn search(mut self, search: NeighbourSearch) -> Neighbourhood {
+        self.search = search;
+
+        self
+    }
+
+    pub fn metric(mut self, metric: Metric) -> Neighbourhood {
+        self.metric = metric;
+
+        self
+    }
+
+    pub fn graph(&self) -> Graph {
+        self.graph
+    }
+
+    /// Check that the neighbourhood can be constructed for `n_points` observations
+    pub fn validate(&self, n_points: usize) -> Result<()> {
+        match self.graph {
+            Graph::Knn(k) | Graph::MutualKnn(k) => {
+                if k == 0 || k >= n_points {
+                    return Err(Error::Parameters(format!(
+                        "number of neighbours {} should be in range [1, {})",
+                        k, n_points
+                    )));
+                }
+            }
+            Graph::Radius(eps) => {
+                if eps.is_nan() || eps <= 0.0 {
+                    return Err(Error::Parameters(format!(
+                        "radius {} should be positive",
+                        eps
+                    )));
+                }
+                if self.search == NeighbourSearch::Hnsw {
+                    return Err(Error::Parameters(
+                        "radius queries are not supported by HNSW".into(),
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Observation with a distance metric, used as point in the HNSW graph
+struct Point<'a, F> {
+    features: ArrayView1<'a, F>,
+    metric: Metric,
+}
+
+impl<F: Float> MetricPoint for Point<'_, F> {
     fn distance(&self, rhs: &Self) -> u32 {
-        let val = self
-            .0
-            .iter()
-            .zip(rhs.0.iter())
-            .map(|(&a, &b)| (a - b) * (a - b))
-            .sum::<F>()
-            .sqrt();
+        let val = self.metric.distance(self.features, rhs.features);
 
         space::f32_metric(val.to_f32().unwrap())
     }
 }
 
+/// Find the neighbours of each observation, excluding the observation itself
+fn neighbours<F: Float, D: Data<Elem = F>>(
+    dataset: &ArrayBase<D, Ix2>,
+    neighbourhood: &Neighbourhood,
+) -> Vec<Vec<usize>> {
+    let n_points = dataset.len_of(Axis(0));
+    let metric = neighbourhood.metric;
+
+    match (neighbourhood.search, neighbourhood.graph) {
+        (NeighbourSearch::BruteForce, graph) => (0..n_points)
+            .map(|i| {
+                let mut distances = (0..n_points)
+                    .filter(|j| *j != i)
+                    .map(|j| (metric.distance(dataset.row(i), dataset.row(j)), j))
+                    .collect::<Vec<_>>();
+
+                match graph {
+                    Graph::Knn(k) | Graph::MutualKnn(k) => {
+                        distances.sort_by(|a, b| a.partial_cmp(b).unwrap());
+                        distances.truncate(k);
+                    }
+                    Graph::Radius(eps) => {
+                        let eps = F::from(eps).unwrap();
+                        distances.retain(|x| x.0 <= eps);
+                    }
+                }
+
+                distances.into_iter().map(|x| x.1).collect()
+            })
+            .collect(),
+        (NeighbourSearch::KdTree, graph) => {
+            let tree = KdTree::new(dataset.view(), metric);
+
+            match graph {
+                Graph::Knn(k) | Graph::MutualKnn(k) => {
+                    (0..n_points).map(|i| tree.nearest(i, k)).collect()
+                }
+                Graph::Radius(eps) => {
+                    let eps = F::from(eps).unwrap();
+                    (0..n_points).map(|i| tree.within(i, eps)).collect()
+                }
+            }
+        }
+        (NeighbourSearch::Hnsw, Graph::Knn(k)) | (NeighbourSearch::Hnsw, Graph::MutualKnn(k)) => {
+            let params = Params::new().ef_construction(k);
+
+            let mut searcher = Searcher::default();
+            let mut hnsw: HNSW<Point<F>> = HNSW::new_params(params);
+
+            // insert all rows as data points into HNSW graph
+            for features in dataset.genrows().into_iter() {
+                hnsw.insert(Point { features, metric }, &mut searcher);
+            }
+
+            // allocate buffer for k neighbours (plus the points itself)
+            let mut buffer = vec![Neighbor::invalid(); k + 1];
+
+            dataset
+                .genrows()
+                .into_iter()
+                .enumerate()
+                .map(|(i, features)| {
+                    let found = hnsw.nearest(
+                        &Point { features, metric },
+                        3 * k,
+                        &mut searcher,
+                        &mut buffer,
+                    );
+
+                    found
+                        .iter()
+                        .map(|x| x.index)
+                        .filter(|x| *x != i)
+                        .take(k)
+                        .collect()
+                })
+                .collect()
+        }
+        (NeighbourSearch::Hnsw, Graph::Radius(_)) => {
+            unreachable!("radius queries are rejected by the validation")
+        }
+    }
+}
+
 /// Create sparse adjacency matrix from dense dataset
+///
+/// The matrix is symmetric, has ones on the diagonal and at the position of connected
+/// observations.
 pub fn adjacency_matrix<F: Float, D: Data<Elem = F>>(
     dataset: &ArrayBase<D, Ix2>,
-    k: usize,
-) -> CsMat<F> {
+    neighbourhood: &Neighbourhood,
+) -> Result<CsMat<F>> {
     let n_points = dataset.len_of(Axis(0));
+    neighbourhood.validate(n_points)?;
 
-    // ensure that the number of neighbours is at least one and less than the total number of
-    // points
-    assert!(k < n_points);
-    assert!(k > 0);
+    let mut neighbours = neighbours(dataset, neighbourhood);
+    for list in &mut neighbours {
+        list.sort_unstable();
+    }
 
-    let params = Params::new().ef_construction(k);
+    // connect i and j if either (union) or both (intersection) contain each other
+    let mutual = matches!(neighbourhood.graph, Graph::MutualKnn(_));
+    let mut rows = vec![Vec::new(); n_points];
+    for (i, list) in neighbours.iter().enumerate() {
+        for &j in list {
+            let reverse = neighbours[j].binary_search(&i).is_ok();
 
-    let mut searcher = Searcher::default();
-    let mut hnsw: HNSW<Euclidean<F>> = HNSW::new_params(params);
+            if mutual && !reverse {
+                continue;
+            }
 
-    // insert all rows as data points into HNSW graph
-    for feature in dataset.genrows().into_iter() {
-        hnsw.insert(Euclidean(feature), &mut searcher);
+            rows[i].push(j);
+            // the reverse edge is added when visiting row j
+            if !reverse {
+                rows[j].push(i);
+            }
+        }
     }
 
-    // allocate buffer for k neighbours (plus the points itself)
-    let mut neighbours = vec![Neighbor::invalid(); k + 1];
-
-    // allocate buffer to initialize the sparse matrix later on
-    //  * data: we have exact #points * k positive entries
-    //  * indptr: has structure [0,k,2k,...,#points*k]
-    //  * indices: filled with the nearest indices
-    let mut data = Vec::with_capacity(n_points * (k + 1));
     let mut indptr = Vec::with_capacity(n_points + 1);
-    //let indptr = (0..n_points+1).map(|x| x * (k+1)).collect::<Vec<_>>();
-    let mut indices = Vec::with_capacity(n_points * (k + 1));
+    let mut indices = Vec::new();
     indptr.push(0);
 
-    // find neighbours for each data point
-    let mut added = 0;
-    for (m, feature) in dataset.genrows().into_iter().enumerate() {
-        hnsw.nearest(&Euclidean(feature), 3 * k, &mut searcher, &mut neighbours);
+    for (i, mut row) in rows.into_iter().enumerate() {
+        // add self loops
+        row.push(i);
+        row.sort_unstable();
+        row.dedup();
+
+        indices.extend(row);
+        indptr.push(indices.len());
+    }
+
+    let data = vec![F::one(); indices.len()];
+
+    Ok(CsMatBase::new((n_points, n_points), indptr, indices, data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{adjacency_matrix, Metric, NeighbourSearch, Neighbourhood};
+    use ndarray::Array2;
 
-        //dbg!(&neighbours);
+    fn data() -> Array2<f64> {
+        // pseudo-random points in the unit square, more than a single k-d tree leaf
+        Array2::from_shape_fn((60, 2), |(i, j)| {
+            ((i * 37 + j * 61 + i * i * 7) % 101) as f64 / 101.0
+        })
+    }
 
-        // sort by indices
-        neighbours.sort_unstable();
+    fn is_symmetric(neighbourhood: &Neighbourhood) {
+        let data = data();
+        let adj = adjacency_matrix(&data, neighbourhood).unwrap().to_dense();
 
-        indices.push(m);
-        data.push(F::one());
-        added += 1;
+        assert_eq!(adj, adj.t());
+        assert!(adj.diag().iter().all(|x| *x == 1.0));
+    }
 
-        // push each index into the indices array
-        for n in &neighbours {
-            if m != n.index {
-                indices.push(n.index);
-                data.push(F::one());
-                added += 1;
+    #[test]
+    fn test_kdtree_matches_brute_force() {
+        let data = data();
+
+        for metric in &[Metric::Euclidean, Metric::Manhattan, Metric::Chebyshev] {
+            for neighbourhood in &[
+                Neighbourhood::knn(5),
+                Neighbourhood::mutual_knn(5),
+                Neighbourhood::radius(0.2),
+            ] {
+                let neighbourhood = neighbourhood.clone().metric(*metric);
+
+                let exact = adjacency_matrix(
+                    &data,
+                    &neighbourhood.clone().search(NeighbourSearch::BruteForce),
+                )
+                .unwrap();
+                let tree = adjacency_matrix(&data, &neighbourhood.search(NeighbourSearch::KdTree))
+                    .unwrap();
+
+                assert_eq!(exact.to_dense(), tree.to_dense());
             }
         }
+    }
+
+    #[test]
+    fn test_mutual_subset_of_symmetric() {
+        let data = data();
+
+        for search in &[NeighbourSearch::KdTree, NeighbourSearch::Hnsw] {
+            let neighbourhood = Neighbourhood::knn(4).search(*search);
+            is_symmetric(&neighbourhood);
+            is_symmetric(&Neighbourhood::mutual_knn(4).search(*search));
+
+            let symmetric = adjacency_matrix(&data, &neighbourhood).unwrap().to_dense();
+            let mutual = adjacency_matrix(&data, &Neighbourhood::mutual_knn(4).search(*search))
+                .unwrap()
+                .to_dense();
+
+            // each row of the symmetric graph has at least k neighbours plus the self loop
+            assert!(symmetric.genrows().into_iter().all(|x| x.sum() >= 5.0));
+            assert!(mutual.iter().zip(symmetric.iter()).all(|(m, s)| m <= s));
+            assert!(mutual.sum() < symmetric.sum());
+        }
+    }
+
+    #[test]
+    fn test_radius_graph() {
+        let data = ndarray::array![[0.0], [0.5], [1.0], [3.0]];
+        let adj = adjacency_matrix(&data, &Neighbourhood::radius(0.6))
+            .unwrap()
+            .to_dense();
 
-        indptr.push(added);
+        assert_eq!(
+            adj,
+            ndarray::array![
+                [1., 1., 0., 0.],
+                [1., 1., 1., 0.],
+                [0., 1., 1., 0.],
+                [0., 0., 0., 1.]
+            ]
+        );
+        is_symmetric(&Neighbourhood::radius(0.1).search(NeighbourSearch::BruteForce));
     }
 
-    // create CSR matrix from data, indptr and indices
-    let mat = CsMatBase::new((n_points, n_points), indptr, indices, data);
-    let mut mat = &mat + &mat.transpose_view();
+    #[test]
+    fn test_hnsw_close_to_exact() {
+        let data = data();
 
-    // ensure that all values are one
-    let val: F = F::one();
-    mat.map_inplace(|_| val);
+        let exact = adjacency_matrix(
+            &data,
+            &Neighbourhood::knn(5).search(NeighbourSearch::KdTree),
+        )
+        .unwrap()
+        .to_dense();
+        let approx = adjacency_matrix(&data, &Neighbourhood::knn(5))
+            .unwrap()
+            .to_dense();
 
-    mat
+        // most edges agree on such a small dataset
+        let common = exact
+            .iter()
+            .zip(approx.iter())
+            .filter(|(a, b)| **a == 1.0 && **b == 1.0)
+            .count();
+        assert!(common as f64 > 0.9 * exact.sum());
+    }
+
+    #[test]
+    fn test_invalid_neighbourhood() {
+        let data = data();
+
+        assert!(adjacency_matrix(&data, &Neighbourhood::knn(0)).is_err());
+        assert!(adjacency_matrix(&data, &Neighbourhood::knn(60)).is_err());
+        assert!(adjacency_matrix(&data, &Neighbourhood::mutual_knn(100)).is_err());
+        assert!(adjacency_matrix(&data, &Neighbourhood::radius(0.0)).is_err());
+        assert!(adjacency_matrix(
+            &data,
+            &Neighbourhood::radius(0.5).search(NeighbourSearch::Hnsw)
+        )
+        .is_err());
+    }
 }
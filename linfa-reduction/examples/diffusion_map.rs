@@ -1,5 +1,5 @@
 use linfa::traits::Transformer;
-use linfa_kernel::{Kernel, KernelMethod, KernelType};
+use linfa_kernel::{Kernel, KernelMethod, KernelType, Neighbourhood};
 use linfa_reduction::utils::generate_convoluted_rings2d;
 use linfa_reduction::DiffusionMap;
 use ndarray_npy::write_npy;
@@ -20,7 +20,7 @@ fn main() {
     // generate sparse polynomial kernel with k = 14, c = 5 and d = 2
     let kernel = Kernel::params()
         //.method(KernelMethod::Polynomial(5.0, 2.0))
-        .kind(KernelType::Sparse(15))
+        .kind(KernelType::Sparse(Neighbourhood::knn(15)))
         .method(KernelMethod::Gaussian(2.0))
         //.kind(KernelType::Dense)
         .transform(&dataset);
@@ -0,0 +1,69 @@
+use std::error::Error;
+use std::fs::File;
+
+use csv::ReaderBuilder;
+use flate2::read::GzDecoder;
+use ndarray::{Array2, Axis};
+use ndarray_csv::Array2Reader;
+
+use linfa::dataset::Dataset;
+use linfa::dataset::Records;
+use linfa::metrics::ToConfusionMatrix;
+use linfa::traits::*;
+use linfa_kernel::{Kernel, KernelMethod};
+use linfa_svm::Svm;
+
+/// Extract a gziped CSV file and return as dataset
+fn read_array(path: &str) -> Result<Array2<f64>, Box<dyn Error>> {
+    // unzip file
+    let file = GzDecoder::new(File::open(path)?);
+    // create a CSV reader with headers and `;` as delimiter
+    let mut reader = ReaderBuilder::new()
+        .has_headers(true)
+        .delimiter(b';')
+        .from_reader(file);
+    // extract ndarray
+    let array = reader.deserialize_array2_dynamic()?;
+    Ok(array)
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    // Read in the wine-quality dataset from dataset path
+    let dataset = read_array("../datasets/winequality-red.csv.gz")?;
+    // The first 11 columns are features used in training and the last columns are targets
+    let (data, targets) = dataset.view().split_at(Axis(1), 11);
+
+    // keep the quality grades from 3 to 8 as separate classes
+    let targets = targets.iter().map(|x| *x as usize).collect::<Vec<_>>();
+    let dataset = Dataset::new(data, targets);
+
+    // split into training and validation dataset
+    let (train, valid) = dataset.split_with_ratio(0.9);
+
+    // transform with RBF kernel, the kernel matrix is shared by all binary classifiers
+    let train_kernel = Kernel::params()
+        .method(KernelMethod::Gaussian(30.0))
+        .transform(&train);
+
+    println!(
+        "Fit multi-class SVM classifier with #{} training points",
+        train.observations()
+    );
+
+    // fit one classifier for each pair of grades
+    let model = Svm::params()
+        .pos_neg_weights(10., 10.)
+        .multi_class()
+        .fit(&train_kernel);
+
+    // predict the grades of the validation dataset
+    let pred = model.predict(&valid);
+
+    // create a confusion matrix with one row and column per grade
+    let cm = pred.confusion_matrix(&valid);
+    println!("{:?}", cm);
+
+    println!("accuracy {}, MCC {}", cm.accuracy(), cm.mcc());
+
+    Ok(())
+}
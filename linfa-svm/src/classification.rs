@@ -3,7 +3,8 @@ use ndarray::{Array1, Array2, ArrayBase, ArrayView2, Data, Ix2};
 use std::cmp::Ordering;
 
 use super::permutable_kernel::{Kernel, PermutableKernel, PermutableKernelOneClass};
-use super::solver_smo::SolverState;
+use super::platt::{cross_validated_platt, sigmoid_predict};
+use super::solver_smo::{scale_to_bounds, SolverState};
 use super::SolverParams;
 use super::{Float, Svm, SvmParams};
 
@@ -30,17 +31,52 @@ pub fn fit_c<'a, A: Float>(
     cpos: A,
     cneg: A,
 ) -> Svm<'a, A, Pr> {
+    let indices = (0..targets.len()).collect::<Vec<_>>();
     let bounds = targets
         .iter()
         .map(|x| if *x { cpos } else { cneg })
         .collect::<Vec<_>>();
 
-    let kernel = PermutableKernel::new(kernel, targets.to_vec());
+    fit_c_subset(params, kernel, targets, &indices, &bounds, None)
+}
+
+/// Support Vector Classification with C-penalizing parameter on a subset of the observations
+///
+/// The targets are indexed like the kernel matrix, and only the observations in `indices` take
+/// part in the optimization. All other coefficients of the resulting model are zero. Each
+/// observation has its own C value in `bounds`, indexed like the kernel matrix. The solver
+/// starts from the signed coefficients `warm_start` of a previous model, if given.
+pub(crate) fn fit_c_subset<'a, A: Float>(
+    params: SolverParams<A>,
+    kernel: &'a Kernel<'a, A>,
+    targets: &[bool],
+    indices: &[usize],
+    bounds: &[A],
+    warm_start: Option<&[A]>,
+) -> Svm<'a, A, Pr> {
+    let local_targets = indices.iter().map(|i| targets[*i]).collect::<Vec<_>>();
+    let bounds = indices.iter().map(|i| bounds[*i]).collect::<Vec<_>>();
+
+    let init_alpha = match warm_start {
+        Some(alpha) => {
+            assert_eq!(
+                alpha.len(),
+                targets.len(),
+                "warm start coefficients do not match the number of observations"
+            );
+
+            let alpha = indices.iter().map(|i| alpha[*i].abs()).collect();
+            scale_to_bounds(alpha, &bounds)
+        }
+        None => vec![A::zero(); indices.len()],
+    };
+
+    let kernel = PermutableKernel::with_indices(kernel, targets.to_vec(), indices.to_vec());
 
     let solver = SolverState::new(
-        vec![A::zero(); targets.len()],
-        vec![-A::one(); targets.len()],
-        targets.to_vec(),
+        init_alpha,
+        vec![-A::one(); indices.len()],
+        local_targets.clone(),
         kernel,
         bounds,
         params,
@@ -49,14 +85,15 @@ pub fn fit_c<'a, A: Float>(
 
     let mut res = solver.solve();
 
-    res.alpha = res
+    let alpha = res
         .alpha
         .into_iter()
-        .zip(targets.iter())
-        .map(|(a, b)| if *b { a } else { -a })
-        .collect();
+        .zip(local_targets.iter())
+        .map(|(a, b)| if *b { a } else { -a });
+    res.alpha = expand_coefficients(alpha, indices, targets.len());
+    res.bounded = expand_coefficients(res.bounded.into_iter(), indices, targets.len());
 
-    res.with_phantom()
+    res.with_linear_decision().with_phantom()
 }
 
 /// Support Vector Classification with Nu-penalizing term
@@ -80,9 +117,27 @@ pub fn fit_nu<'a, A: Float>(
     targets: &'a [bool],
     nu: A,
 ) -> Svm<'a, A, Pr> {
-    let mut sum_pos = nu * A::from(targets.len()).unwrap() / A::from(2.0).unwrap();
-    let mut sum_neg = nu * A::from(targets.len()).unwrap() / A::from(2.0).unwrap();
-    let init_alpha = targets
+    let indices = (0..targets.len()).collect::<Vec<_>>();
+
+    fit_nu_subset(params, kernel, targets, &indices, nu)
+}
+
+/// Support Vector Classification with Nu-penalizing term on a subset of the observations
+///
+/// The targets are indexed like the kernel matrix, and only the observations in `indices` take
+/// part in the optimization. All other coefficients of the resulting model are zero.
+pub(crate) fn fit_nu_subset<'a, A: Float>(
+    params: SolverParams<A>,
+    kernel: &'a Kernel<'a, A>,
+    targets: &[bool],
+    indices: &[usize],
+    nu: A,
+) -> Svm<'a, A, Pr> {
+    let local_targets = indices.iter().map(|i| targets[*i]).collect::<Vec<_>>();
+
+    let mut sum_pos = nu * A::from(indices.len()).unwrap() / A::from(2.0).unwrap();
+    let mut sum_neg = nu * A::from(indices.len()).unwrap() / A::from(2.0).unwrap();
+    let init_alpha = local_targets
         .iter()
         .map(|x| {
             if *x {
@@ -97,14 +152,14 @@ pub fn fit_nu<'a, A: Float>(
         })
         .collect::<Vec<_>>();
 
-    let kernel = PermutableKernel::new(kernel, targets.to_vec());
+    let kernel = PermutableKernel::with_indices(kernel, targets.to_vec(), indices.to_vec());
 
     let solver = SolverState::new(
         init_alpha,
-        vec![A::zero(); targets.len()],
-        targets.to_vec(),
+        vec![A::zero(); indices.len()],
+        local_targets.clone(),
         kernel,
-        vec![A::one(); targets.len()],
+        vec![A::one(); indices.len()],
         params,
         true,
     );
@@ -113,17 +168,32 @@ pub fn fit_nu<'a, A: Float>(
 
     let r = res.r.unwrap();
 
-    res.alpha = res
+    let alpha = res
         .alpha
         .into_iter()
-        .zip(targets.iter())
+        .zip(local_targets.iter())
         .map(|(a, b)| if *b { a } else { -a })
-        .map(|x| x / r)
-        .collect();
+        .map(|x| x / r);
+    res.alpha = expand_coefficients(alpha, indices, targets.len());
+    res.bounded = expand_coefficients(res.bounded.into_iter(), indices, targets.len());
     res.rho /= r;
     res.obj /= r * r;
 
-    res.with_phantom()
+    res.with_linear_decision().with_phantom()
+}
+
+/// Place the coefficients of a subset at their position in the kernel matrix
+pub(crate) fn expand_coefficients<A: Default + Clone, I: Iterator<Item = A>>(
+    alpha: I,
+    indices: &[usize],
+    size: usize,
+) -> Vec<A> {
+    let mut expanded = vec![A::default(); size];
+    for (a, idx) in alpha.zip(indices.iter()) {
+        expanded[*idx] = a;
+    }
+
+    expanded
 }
 
 /// Support Vector Classification for one-class problems
@@ -166,52 +236,155 @@ pub fn fit_one_class<'a, A: Float + num_traits::ToPrimitive>(
 
     let res = solver.solve();
 
-    res.with_phantom()
+    res.with_linear_decision().with_phantom()
 }
 
-impl<'a, F: Float> Fit<'a, Kernel<'a, F>, Vec<bool>> for SvmParams<F, Pr> {
-    type Object = Svm<'a, F, Pr>;
+impl<F: Float> SvmParams<F, Pr> {
+    /// C value of each observation
+    ///
+    /// The C value of its class is scaled by the weight of the observation.
+    pub(crate) fn c_bounds(&self, targets: &[bool], weights: Option<&[F]>) -> Option<Vec<F>> {
+        let (c_p, c_n) = self.c?;
+
+        let bounds = targets
+            .iter()
+            .enumerate()
+            .map(|(i, x)| {
+                let c = if *x { c_p } else { c_n };
+                match weights {
+                    Some(weights) => c * weights[i],
+                    None => c,
+                }
+            })
+            .collect();
 
-    fn fit(&self, dataset: &'a Dataset<Kernel<'a, F>, Vec<bool>>) -> Self::Object {
-        match (self.c, self.nu) {
-            (Some((c_p, c_n)), _) => fit_c(
-                self.solver_params.clone(),
-                &dataset.records,
-                dataset.targets(),
-                c_p,
-                c_n,
-            ),
-            (None, Some((nu, _))) => fit_nu(
+        Some(bounds)
+    }
+
+    /// Weight of each observation from the dataset and, if balanced, the frequency of its class
+    pub(crate) fn binary_weights(
+        &self,
+        targets: &[bool],
+        weights: Option<&[f32]>,
+    ) -> Option<Vec<F>> {
+        let mut weights =
+            weights.map(|w| w.iter().map(|x| F::from(*x).unwrap()).collect::<Vec<_>>());
+
+        if self.balanced {
+            let n = F::from(targets.len()).unwrap();
+            let n_pos = F::from(targets.iter().filter(|x| **x).count()).unwrap();
+            let two = F::from(2.0).unwrap();
+
+            // classes which are not present are never scaled
+            let scale = |x: &bool| {
+                if *x {
+                    n / (two * n_pos)
+                } else {
+                    n / (two * (n - n_pos))
+                }
+            };
+
+            let weights = weights.get_or_insert_with(|| vec![F::one(); targets.len()]);
+            for (w, x) in weights.iter_mut().zip(targets.iter()) {
+                *w *= scale(x);
+            }
+        }
+
+        weights
+    }
+
+    /// Solve the binary problem on a subset of the observations with either C or Nu penalty
+    ///
+    /// The weights of the observations scale their C value and are ignored by the Nu formulation.
+    pub(crate) fn solve_subset<'a>(
+        &self,
+        kernel: &'a Kernel<'a, F>,
+        targets: &[bool],
+        indices: &[usize],
+        weights: Option<&[F]>,
+        warm_start: Option<&[F]>,
+    ) -> Svm<'a, F, Pr> {
+        let bounds = self.c_bounds(targets, weights);
+
+        if let Some(ref bounds) = bounds {
+            if let Some(model) =
+                self.solve_linear_subset(kernel, targets, indices, bounds, warm_start)
+            {
+                return model;
+            }
+        }
+
+        match (bounds, self.nu) {
+            (Some(bounds), _) => fit_c_subset(
                 self.solver_params.clone(),
-                &dataset.records,
-                dataset.targets(),
-                nu,
+                kernel,
+                targets,
+                indices,
+                &bounds,
+                warm_start,
             ),
+            (None, Some((nu, _))) => {
+                fit_nu_subset(self.solver_params.clone(), kernel, targets, indices, nu)
+            }
             _ => panic!("Set either C value or Nu value"),
         }
     }
+
+    /// Solve the binary problem and calibrate probabilities, if requested
+    ///
+    /// The warm start is only used for the final model, the cross-validation folds of the
+    /// calibration are solved from scratch.
+    pub(crate) fn fit_subset<'a>(
+        &self,
+        kernel: &'a Kernel<'a, F>,
+        targets: &[bool],
+        indices: &[usize],
+        weights: Option<&[F]>,
+        warm_start: Option<&[F]>,
+    ) -> Svm<'a, F, Pr> {
+        let mut model = self.solve_subset(kernel, targets, indices, weights, warm_start);
+
+        if self.probability {
+            model.platt = Some(cross_validated_platt(
+                self, kernel, targets, indices, weights,
+            ));
+        }
+
+        model
+    }
+
+    fn fit_binary<'a>(
+        &self,
+        kernel: &'a Kernel<'a, F>,
+        targets: &[bool],
+        weights: Option<&[f32]>,
+    ) -> Svm<'a, F, Pr> {
+        let indices = (0..targets.len()).collect::<Vec<_>>();
+        let weights = self.binary_weights(targets, weights);
+
+        self.fit_subset(
+            kernel,
+            targets,
+            &indices,
+            weights.as_deref(),
+            self.warm_start.as_deref(),
+        )
+    }
+}
+
+impl<'a, F: Float> Fit<'a, Kernel<'a, F>, Vec<bool>> for SvmParams<F, Pr> {
+    type Object = Svm<'a, F, Pr>;
+
+    fn fit(&self, dataset: &'a Dataset<Kernel<'a, F>, Vec<bool>>) -> Self::Object {
+        self.fit_binary(&dataset.records, dataset.targets(), dataset.weights())
+    }
 }
 
 impl<'a, F: Float> Fit<'a, Kernel<'a, F>, &Vec<bool>> for SvmParams<F, Pr> {
     type Object = Svm<'a, F, Pr>;
 
     fn fit(&self, dataset: &'a Dataset<Kernel<'a, F>, &Vec<bool>>) -> Self::Object {
-        match (self.c, self.nu) {
-            (Some((c_p, c_n)), _) => fit_c(
-                self.solver_params.clone(),
-                &dataset.records,
-                dataset.targets(),
-                c_p,
-                c_n,
-            ),
-            (None, Some((nu, _))) => fit_nu(
-                self.solver_params.clone(),
-                &dataset.records,
-                dataset.targets(),
-                nu,
-            ),
-            _ => panic!("Set either C value or Nu value"),
-        }
+        self.fit_binary(&dataset.records, dataset.targets(), dataset.weights())
     }
 }
 
@@ -219,24 +392,10 @@ impl<'a, F: Float> Fit<'a, Kernel<'a, F>, &[bool]> for SvmParams<F, Pr> {
     type Object = Svm<'a, F, Pr>;
 
     fn fit(&self, dataset: &'a Dataset<Kernel<'a, F>, &[bool]>) -> Self::Object {
-        match (self.c, self.nu) {
-            (Some((c_p, c_n)), _) => fit_c(
-                self.solver_params.clone(),
-                &dataset.records,
-                dataset.targets(),
-                c_p,
-                c_n,
-            ),
-            (None, Some((nu, _))) => fit_nu(
-                self.solver_params.clone(),
-                &dataset.records,
-                dataset.targets(),
-                nu,
-            ),
-            _ => panic!("Set either C value or Nu value"),
-        }
+        self.fit_binary(&dataset.records, dataset.targets(), dataset.weights())
     }
 }
+
 impl<'a, F: Float> Fit<'a, Kernel<'a, F>, &()> for SvmParams<F, Pr> {
     type Object = Svm<'a, F, Pr>;
 
@@ -248,6 +407,19 @@ impl<'a, F: Float> Fit<'a, Kernel<'a, F>, &()> for SvmParams<F, Pr> {
     }
 }
 
+impl<'a, F: Float> Svm<'a, F, Pr> {
+    /// Map a decision value to a calibrated probability, if available
+    fn to_pr(&self, val: F) -> Pr {
+        let val = match self.platt {
+            Some(coefs) => sigmoid_predict(val, coefs),
+            None => val,
+        };
+
+        // this is safe because `F` is only implemented for `f32` and `f64`
+        Pr(val.to_f32().unwrap())
+    }
+}
+
 /// Predict a probability with a feature vector
 impl<'a, F: Float> Predict<Array1<F>, Pr> for Svm<'a, F, Pr> {
     fn predict(&self, data: Array1<F>) -> Pr {
@@ -256,24 +428,21 @@ impl<'a, F: Float> Predict<Array1<F>, Pr> for Svm<'a, F, Pr> {
             None => self.kernel.weighted_sum(&self.alpha, data.view()) - self.rho,
         };
 
-        // this is safe because `F` is only implemented for `f32` and `f64`
-        Pr(val.to_f32().unwrap())
+        self.to_pr(val)
     }
 }
 
 /// Predict a probability with a set of observations
 impl<'a, F: Float, D: Data<Elem = F>> Predict<ArrayBase<D, Ix2>, Vec<Pr>> for Svm<'a, F, Pr> {
     fn predict(&self, data: ArrayBase<D, Ix2>) -> Vec<Pr> {
-        data.outer_iter()
-            .map(|data| {
-                let val = match self.linear_decision {
-                    Some(ref x) => x.dot(&data) - self.rho,
-                    None => self.kernel.weighted_sum(&self.alpha, data.view()) - self.rho,
-                };
-
-                // this is safe because `F` is only implemented for `f32` and `f64`
-                Pr(val.to_f32().unwrap())
-            })
+        let values = match self.linear_decision {
+            Some(ref x) => data.dot(x),
+            None => self.kernel.weighted_sums(&self.alpha, &data),
+        };
+
+        values
+            .iter()
+            .map(|val| self.to_pr(*val - self.rho))
             .collect()
     }
 }
@@ -304,14 +473,17 @@ impl<'a, F: Float, T: Targets, D: Data<Elem = F>>
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::Svm;
+    use crate::ExitReason;
     use linfa::dataset::Dataset;
+    use linfa::dataset::Pr;
     use linfa::metrics::ToConfusionMatrix;
     use linfa::traits::{Fit, Predict, Transformer};
-    use linfa_kernel::{Kernel, KernelMethod};
+    use linfa_kernel::{Kernel, KernelMethod, KernelType};
+    use std::sync::{Arc, Mutex};
 
-    use ndarray::{Array, Array2, Axis};
+    use ndarray::{Array, Array2, ArrayView1, Axis};
     use ndarray_rand::rand::SeedableRng;
     use ndarray_rand::rand_distr::Uniform;
     use ndarray_rand::RandomExt;
@@ -372,6 +544,212 @@ mod tests {
         assert_eq!(cm.accuracy(), 1.0);
     }
 
+    #[test]
+    fn test_support_vector_inspection() {
+        let mut rng = Isaac64Rng::seed_from_u64(42);
+        let records = ndarray::stack(
+            Axis(0),
+            &[
+                Array::random_using((30, 2), Uniform::new(-1f64, 0.3), &mut rng).view(),
+                Array::random_using((30, 2), Uniform::new(-0.3f64, 1.), &mut rng).view(),
+            ],
+        )
+        .unwrap();
+        let targets = (0..60).map(|x| x < 30).collect::<Vec<_>>();
+        let dataset = Dataset::new(records.clone(), targets.clone());
+
+        for method in [KernelMethod::Gaussian(1.0), KernelMethod::Linear] {
+            let kernel = Kernel::params().method(method.clone()).transform(&dataset);
+            let model = Svm::params().pos_neg_weights(1.0, 1.0).fit(&kernel);
+
+            // support vectors are either bounded or free
+            let support = model.support_indices();
+            let mut split = model.bounded_support_indices();
+            split.extend(model.free_support_indices());
+            split.sort_unstable();
+            assert_eq!(support, split);
+            assert_eq!(model.nsupport(), support.len());
+            assert_eq!(model.to_model().nsupport(), support.len());
+            assert!(!model.bounded_support_indices().is_empty());
+
+            let alpha = model.dual_coefficients();
+            let decision = model.decision_function(&records);
+            for i in model.bounded_support_indices() {
+                assert!((alpha[i].abs() - 1.0).abs() < 1e-6);
+            }
+            // free support vectors lie on the margin
+            for i in model.free_support_indices() {
+                assert!(alpha[i].abs() < 1.0);
+                let y = if targets[i] { 1.0 } else { -1.0 };
+                assert!((y * decision[i] - 1.0).abs() < 1e-2);
+            }
+
+            // the batch decision function agrees with the predictions
+            let pred: Vec<Pr> = model.predict(records.view());
+            for (p, d) in pred.iter().zip(decision.iter()) {
+                assert!((**p as f64 - d).abs() < 1e-5);
+            }
+
+            match model.primal_weights() {
+                Some(w) => {
+                    let expected = records.dot(w) - model.rho;
+                    for (d, e) in decision.iter().zip(expected.iter()) {
+                        assert!((d - e).abs() < 1e-10);
+                    }
+                }
+                None => assert!(!kernel.records.is_linear()),
+            }
+        }
+    }
+
+    #[test]
+    fn test_probability_classification() {
+        let mut rng = Isaac64Rng::seed_from_u64(42);
+        let records = Array::random_using((60, 2), Uniform::new(-2f64, 2.), &mut rng);
+        let targets = records
+            .outer_iter()
+            .map(|x| x[0] * x[0] + x[1] * x[1] < 1.5)
+            .collect::<Vec<_>>();
+        let dataset = Dataset::new(records.clone(), targets);
+
+        let dataset = Kernel::params()
+            .method(KernelMethod::Gaussian(1.0))
+            .transform(&dataset);
+
+        // the sigmoid is fitted on cross-validated decision values
+        let model = Svm::params()
+            .pos_neg_weights(1.0, 1.0)
+            .probability(true)
+            .fit(&dataset);
+        assert!(model.platt_coefficients().is_some());
+
+        let probabilities = model.predict(records.view());
+        assert!(probabilities.iter().all(|x| x.0 >= 0.0 && x.0 <= 1.0));
+
+        let valid = model
+            .predict(Dataset::from(records))
+            .map_targets(|x| **x > 0.5);
+
+        let cm = valid.confusion_matrix(&dataset);
+        assert!(cm.accuracy() > 0.9);
+
+        // without calibration raw decision values are returned
+        let model = Svm::params().pos_neg_weights(1.0, 1.0).fit(&dataset);
+        assert!(model.platt_coefficients().is_none());
+    }
+
+    #[test]
+    fn test_solver_controls() {
+        let records = generate_convoluted_rings(20);
+        let targets = (0..40).map(|x| x < 20).collect::<Vec<_>>();
+        let dataset = Dataset::new(records.clone(), targets);
+
+        let dataset = Kernel::params()
+            .method(KernelMethod::Gaussian(50.0))
+            .transform(&dataset);
+
+        let model = Svm::params().pos_neg_weights(1.0, 1.0).fit(&dataset);
+        assert_eq!(model.exit_reason(), ExitReason::ReachedThreshold);
+        assert!(model.iterations() > 5);
+
+        // stop early after five iterations
+        let stopped = Svm::params()
+            .pos_neg_weights(1.0, 1.0)
+            .max_iterations(5)
+            .fit(&dataset);
+        assert_eq!(stopped.exit_reason(), ExitReason::ReachedIterations);
+        assert_eq!(stopped.iterations(), 5);
+
+        // record the progress every other iteration
+        let progress = Arc::new(Mutex::new(Vec::new()));
+        let recorder = progress.clone();
+        let recorded = Svm::params()
+            .pos_neg_weights(1.0, 1.0)
+            .progress(2, move |x| recorder.lock().unwrap().push(x.clone()))
+            .fit(&dataset);
+
+        let progress = progress.lock().unwrap();
+        assert_eq!(progress.len(), recorded.iterations() / 2);
+        for (i, step) in progress.iter().enumerate() {
+            assert_eq!(step.iteration, 2 * (i + 1));
+            assert!(step.kkt_gap >= 0.0);
+        }
+        // the dual objective decreases in every step
+        for pair in progress.windows(2) {
+            assert!(pair[1].objective <= pair[0].objective + 1e-10);
+        }
+        assert!(progress.last().unwrap().objective >= recorded.objective() - 1e-10);
+
+        // warm starting from the solution converges immediately to the same model
+        let warm = Svm::params()
+            .pos_neg_weights(1.0, 1.0)
+            .warm_start(&model)
+            .fit(&dataset);
+        assert!(warm.iterations() < model.iterations());
+        assert!((warm.objective() - model.objective()).abs() < 1e-6);
+
+        // and with a larger C value to the new optimum
+        let expected = Svm::params().pos_neg_weights(10.0, 10.0).fit(&dataset);
+        let warm = Svm::params()
+            .pos_neg_weights(10.0, 10.0)
+            .warm_start(&model)
+            .fit(&dataset);
+        assert!((warm.objective() - expected.objective()).abs() < 1e-6);
+
+        let predicted = warm.predict(records.view());
+        let expected = expected.predict(records.view());
+        for (a, b) in predicted.iter().zip(expected.iter()) {
+            assert!((a.0 - b.0).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_weighted_classification() {
+        let mut rng = Isaac64Rng::seed_from_u64(42);
+        // overlapping groups with 40 negative and 8 positive observations
+        let records = ndarray::stack(
+            Axis(0),
+            &[
+                Array::random_using((40, 2), Uniform::new(-1f64, 0.3), &mut rng).view(),
+                Array::random_using((8, 2), Uniform::new(-0.3, 1.), &mut rng).view(),
+            ],
+        )
+        .unwrap();
+        let targets = (0..48).map(|x| x >= 40).collect::<Vec<_>>();
+
+        for method in [KernelMethod::Gaussian(1.0), KernelMethod::Linear] {
+            // a uniform weight of two is the same as doubling C
+            let dataset = Dataset::new(records.clone(), targets.clone());
+            let kernel = Kernel::params().method(method.clone()).transform(&dataset);
+            let expected = Svm::params().pos_neg_weights(2.0, 2.0).fit(&kernel);
+
+            let weighted =
+                Dataset::new(records.clone(), targets.clone()).with_weights(vec![2.0; 48]);
+            let kernel = Kernel::params().method(method.clone()).transform(&weighted);
+            let model = Svm::params().pos_neg_weights(1.0, 1.0).fit(&kernel);
+            assert!((model.objective() - expected.objective()).abs() < 1e-6);
+
+            // balancing the classes finds more of the rare positive observations
+            let kernel = Kernel::params().method(method).transform(&dataset);
+            let count_positive = |model: &Svm<f64, Pr>| {
+                model
+                    .predict(records.view())
+                    .iter()
+                    .zip(targets.iter())
+                    .filter(|(p, t)| p.0 > 0.0 && **t)
+                    .count()
+            };
+
+            let plain = Svm::params().pos_neg_weights(1.0, 1.0).fit(&kernel);
+            let balanced = Svm::params()
+                .pos_neg_weights(1.0, 1.0)
+                .balanced(true)
+                .fit(&kernel);
+
+            assert!(count_positive(&balanced) > count_positive(&plain));
+        }
+    }
+
     #[test]
     fn test_polynomial_classification() {
         let mut rng = Isaac64Rng::seed_from_u64(42);
@@ -426,6 +804,53 @@ mod tests {
         assert!(cm.accuracy() > 0.9);
     }
 
+    #[test]
+    fn test_laplacian_and_custom_kernel_classification() {
+        let records = generate_convoluted_rings(10);
+        let targets = (0..20).map(|x| x < 10).collect::<Vec<_>>();
+        let dataset = Dataset::new(records.clone(), targets);
+
+        // a custom kernel with the same definition as the Gaussian kernel
+        let gaussian = |a: ArrayView1<f64>, b: ArrayView1<f64>| {
+            (-(&a - &b).mapv(|x| x * x).sum() / 50.0).exp()
+        };
+
+        for method in [KernelMethod::Laplacian(5.0), KernelMethod::custom(gaussian)] {
+            let dataset = Kernel::params().method(method).transform(&dataset);
+
+            let model = Svm::params().pos_neg_weights(1.0, 1.0).fit(&dataset);
+
+            let valid = model
+                .predict(Dataset::from(records.clone()))
+                .map_targets(|x| **x > 0.0);
+
+            let cm = valid.confusion_matrix(&dataset);
+            assert!(cm.accuracy() > 0.9);
+        }
+    }
+
+    #[test]
+    fn test_lazy_kernel_classification() {
+        let records = generate_convoluted_rings(10);
+        let targets = (0..20).map(|x| x < 10).collect::<Vec<_>>();
+        let dataset = Dataset::new(records.clone(), targets);
+
+        // the cache is smaller than the kernel matrix
+        let dataset = Kernel::params()
+            .method(KernelMethod::Gaussian(50.0))
+            .kind(KernelType::Lazy(0))
+            .transform(&dataset);
+
+        let model = Svm::params().pos_neg_weights(1.0, 1.0).fit(&dataset);
+
+        let valid = model
+            .predict(Dataset::from(records))
+            .map_targets(|x| **x > 0.0);
+
+        let cm = valid.confusion_matrix(&dataset);
+        assert!(cm.accuracy() > 0.9);
+    }
+
     #[test]
     fn test_reject_classification() {
         // generate two clusters with 100 samples each
@@ -17,13 +17,43 @@
 //! C value controls the penalty given to missclassification and should be in the interval (0, inf). In
 //! [fit_nu](SVClassify/fn.fit_nu.html) the Nu value controls the number of support vectors and should be in the interval (0, 1].
 //!
+//! The C value of each observation is scaled by its weight in the dataset. Imbalanced classes can
+//! be [balanced](struct.SvmParams.html#method.balanced) by their frequencies, and multi-class
+//! classifiers accept a weight for each [class](enum.ClassWeights.html).
+//!
 //! For supervised classification with just one class of data a special classifier is available in
 //! [fit_one_class](SVClassify/fn.fit_one_class.html). It also accepts a Nu value.
 //!
 //! For support vector regression two flavors are available. With
 //! [fit_epsilon](SVRegress/fn.fit_epsilon.html) a regression task is learned while minimizing deviation
 //! larger than epsilon. In [fit_nu](SVRegress/fn.fit_nu.html) the parameter epsilon is replaced with Nu
-//! again and should be in the interval (0, 1]
+//! again and should be in the interval (0, 1]. Regressions predict `f32` or `f64` values, also from
+//! a dataset into a dataset, and with [fit_multi_target](struct.SvmParams.html#method.fit_multi_target)
+//! a separate regression is fitted for each column of a two-dimensional target.
+//!
+//! With a linear kernel and a C value a dedicated solver based on dual coordinate descent can be
+//! selected with [linear_solver](struct.SvmParams.html#method.linear_solver), which never evaluates
+//! the kernel matrix. Once selected, it is also available for dense and sparse records
+//! without a kernel with [fit_linear](struct.SvmParams.html#method.fit_linear) or
+//! [fit_linear_regression](struct.SvmParams.html#method.fit_linear_regression) and returns a
+//! [`LinearSvm`](struct.LinearSvm.html) with the weight vector of the discriminant. Nu values and
+//! probability calibration are not supported by this solver and result in an error.
+//!
+//! With [novelty](struct.SvmParams.html#method.novelty) a one-class SVM is wrapped into a
+//! [`NoveltyDetector`](struct.NoveltyDetector.html), which returns signed decision scores and
+//! labels observations as inliers or outliers. The threshold is either set directly or chosen from
+//! the expected contamination of the training data.
+//!
+//! For more than two classes, [multi_class](struct.SvmParams.html#method.multi_class) combines
+//! binary classifiers either one-vs-one or one-vs-rest and accepts any label type.
+//!
+//! With [probability](struct.SvmParams.html#method.probability) a sigmoid is fitted on
+//! cross-validated decision values (Platt scaling) and classifiers predict probabilities instead
+//! of decision values. Multi-class probabilities are combined by pairwise coupling.
+//!
+//! A fitted [`Svm`](struct.Svm.html) exposes the indices of its support vectors, split into
+//! bounded and free ones, the signed dual coefficients and, for linear kernels, the weight vector
+//! of the discriminant. The decision function can be evaluated on a batch of observations.
 //!
 //! ## Kernel Methods
 //! Normally the resulting discriminant is linear, but with [Kernel Methods](https://en.wikipedia.org/wiki/Kernel_method) non-linear relations between the input features
@@ -37,6 +67,12 @@
 //! let kernel = Kernel::gaussian_sparse(&dataset, 10);
 //! ```
 //!
+//! ## Persisting models
+//! A fitted [`Svm`](struct.Svm.html) borrows the kernel matrix and therefore the training data.
+//! With [`Svm::to_model`](struct.Svm.html#method.to_model) only the support vectors are copied
+//! into a [`SvmModel`](struct.SvmModel.html), which can be returned from functions and, with the
+//! `serde` feature, saved and reloaded.
+//!
 //! # The solver
 //! This implementation uses Sequential Minimal Optimization, a widely used optimization tool for
 //! convex problems. It selects in each optimization step two variables and updates the variables.
@@ -48,6 +84,12 @@
 //!
 //! After a couple of iterations the solution may be optimal.
 //!
+//! The number of iterations can be limited with
+//! [max_iterations](struct.SvmParams.html#method.max_iterations), and a
+//! [progress](struct.SvmParams.html#method.progress) callback reports the objective and KKT gap
+//! while solving. When a sequence of similar problems is solved, for example while searching C,
+//! the solver can [warm start](struct.SvmParams.html#method.warm_start) from a previous model.
+//!
 //! # Example
 //! The wine quality data consists of 11 features, like "acid", "sugar", "sulfur dioxide", and
 //! groups the quality into worst 3 to best 8. These are unified to good 8-7 and bad 3-6 to get a
@@ -67,24 +109,42 @@
 //! accuracy 0.98818624, MCC 0.9523008
 //! ```
 use linfa::{dataset::Pr, Float};
-use ndarray::Array1;
+use ndarray::{Array1, ArrayBase, Data, Ix2};
 use std::fmt;
 use std::marker::PhantomData;
+use std::sync::Arc;
 
 #[cfg(feature = "serde")]
 use serde_crate::{Deserialize, Serialize};
 
 mod classification;
+mod linear;
+mod model;
+mod multiclass;
+mod novelty;
 mod permutable_kernel;
+mod platt;
 mod regression;
 pub mod solver_smo;
 
+pub use linear::{LinearLoss, LinearRecords, LinearSvm};
+pub use model::SvmModel;
+pub use multiclass::{ClassWeights, MultiClassParams, MultiClassStrategy, MultiClassSvm};
+pub use novelty::{NoveltyDetector, NoveltyParams};
 use permutable_kernel::Kernel;
-pub use solver_smo::SolverParams;
+pub use regression::MultiTargetSvm;
+pub use solver_smo::{ProgressCallback, SolverParams, SolverProgress};
+
+/// Observations with a dual coefficient of at most this magnitude are no support vectors
+const SUPPORT_THRESHOLD: f64 = 1e-5;
 
 pub struct SvmParams<F: Float, T> {
     c: Option<(F, F)>,
     nu: Option<(F, F)>,
+    probability: bool,
+    balanced: bool,
+    warm_start: Option<Vec<F>>,
+    linear_loss: Option<LinearLoss>,
     solver_params: SolverParams<F>,
     phantom: PhantomData<T>,
 }
@@ -100,6 +160,50 @@ impl<F: Float, T> SvmParams<F, T> {
 
         self
     }
+
+    /// Stop the solver after a maximal number of iterations
+    ///
+    /// The [exit reason](struct.Svm.html#method.exit_reason) of the model reports whether the
+    /// threshold was reached before.
+    pub fn max_iterations(mut self, max_iterations: usize) -> Self {
+        self.solver_params.max_iterations = Some(max_iterations);
+
+        self
+    }
+
+    /// Call `callback` every `every` iterations with the current objective and KKT gap
+    pub fn progress<C>(mut self, every: usize, callback: C) -> Self
+    where
+        C: Fn(&SolverProgress<F>) + Send + Sync + 'static,
+    {
+        self.solver_params.progress = Some((usize::max(every, 1), Arc::new(callback)));
+
+        self
+    }
+
+    /// Start the solver from the coefficients of a previous model
+    ///
+    /// The model should be trained on the same observations, for example with a different C
+    /// value. The coefficients are scaled down uniformly to satisfy the new bounds. Warm starts are
+    /// used by C-SVC and epsilon-SVR, the Nu formulations always start from their default
+    /// initialization.
+    pub fn warm_start<S>(mut self, model: &Svm<F, S>) -> Self {
+        self.warm_start = Some(model.alpha.clone());
+
+        self
+    }
+
+    /// Loss of the dual coordinate descent solver for linear kernels
+    ///
+    /// With `Some(loss)` problems with a C value and a linear kernel are solved without the kernel
+    /// matrix by dual coordinate descent, which also learns the offset as regularized weight of a
+    /// constant feature. Defaults to `None`, which solves linear kernels with SMO like any other
+    /// kernel. Fitting records without a kernel, for example with `fit_linear`, requires a loss.
+    pub fn linear_solver(mut self, loss: Option<LinearLoss>) -> Self {
+        self.linear_loss = loss;
+
+        self
+    }
 }
 
 impl<F: Float> SvmParams<F, Pr> {
@@ -116,6 +220,27 @@ impl<F: Float> SvmParams<F, Pr> {
 
         self
     }
+
+    /// Scale the C value of each class inversely proportional to its frequency
+    ///
+    /// The C values are multiplied with `n / (2 n_class)`, which balances imbalanced datasets.
+    /// Multi-class classifiers use `n / (k n_class)` for `k` classes.
+    pub fn balanced(mut self, balanced: bool) -> Self {
+        self.balanced = balanced;
+
+        self
+    }
+
+    /// Calibrate probabilities with Platt scaling
+    ///
+    /// A sigmoid is fitted on decision values obtained with 5-fold cross-validation, which
+    /// increases the training time accordingly. The predicted `Pr` are then probabilities of the
+    /// positive class instead of decision values.
+    pub fn probability(mut self, probability: bool) -> Self {
+        self.probability = probability;
+
+        self
+    }
 }
 
 impl<F: Float> SvmParams<F, F> {
@@ -152,7 +277,7 @@ pub mod SVRegress {
     derive(Serialize, Deserialize),
     serde(crate = "serde_crate")
 )]
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ExitReason {
     ReachedThreshold,
     ReachedIterations,
@@ -171,6 +296,8 @@ pub struct Svm<'a, A: Float, T> {
     exit_reason: ExitReason,
     iterations: usize,
     obj: A,
+    /// Whether each coefficient reached its upper bound
+    bounded: Vec<bool>,
     #[cfg_attr(
         feature = "serde",
         serde(bound(
@@ -180,6 +307,7 @@ pub struct Svm<'a, A: Float, T> {
     )]
     kernel: &'a Kernel<'a, A>,
     linear_decision: Option<Array1<A>>,
+    platt: Option<(A, A)>,
     phantom: PhantomData<T>,
 }
 
@@ -188,21 +316,115 @@ impl<'a, A: Float, T> Svm<'a, A, T> {
         SvmParams {
             c: Some((A::one(), A::one())),
             nu: None,
-            solver_params: SolverParams {
-                eps: A::from(1e-7).unwrap(),
-                shrinking: false,
-            },
+            probability: false,
+            balanced: false,
+            warm_start: None,
+            linear_loss: None,
+            solver_params: SolverParams::default(),
             phantom: PhantomData,
         }
     }
 
     /// Returns the number of support vectors
     pub fn nsupport(&self) -> usize {
+        self.alpha.iter().filter(|x| is_support(**x)).count()
+    }
+
+    /// Indices of the support vectors among the training observations
+    ///
+    /// These are the observations copied into an [`SvmModel`](struct.SvmModel.html).
+    pub fn support_indices(&self) -> Vec<usize> {
         self.alpha
             .iter()
-            .filter(|x| x.abs() > A::from(1e-5).unwrap())
-            .count()
+            .enumerate()
+            .filter(|(_, a)| is_support(**a))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Indices of the support vectors whose coefficient reached the upper bound
+    ///
+    /// Bounded support vectors lie inside the margin or are misclassified.
+    pub fn bounded_support_indices(&self) -> Vec<usize> {
+        self.support_indices()
+            .into_iter()
+            .filter(|i| self.bounded[*i])
+            .collect()
+    }
+
+    /// Indices of the support vectors whose coefficient lies strictly between its bounds
+    ///
+    /// Free support vectors lie exactly on the margin.
+    pub fn free_support_indices(&self) -> Vec<usize> {
+        self.support_indices()
+            .into_iter()
+            .filter(|i| !self.bounded[*i])
+            .collect()
+    }
+
+    /// Signed dual coefficient of each training observation
+    ///
+    /// The coefficients are zero for observations which are no support vectors.
+    pub fn dual_coefficients(&self) -> &[A] {
+        &self.alpha
+    }
+
+    /// Weight vector of the discriminant in the feature space, only available for linear kernels
+    pub fn primal_weights(&self) -> Option<&Array1<A>> {
+        self.linear_decision.as_ref()
+    }
+
+    /// Returns whether the solver reached the threshold or the maximal number of iterations
+    pub fn exit_reason(&self) -> ExitReason {
+        self.exit_reason
+    }
+
+    /// Returns the number of iterations performed by the solver
+    pub fn iterations(&self) -> usize {
+        self.iterations
     }
+
+    /// Returns the final value of the dual objective
+    pub fn objective(&self) -> A {
+        self.obj
+    }
+
+    /// Coefficients `(A, B)` of the sigmoid `1 / (1 + exp(A f + B))`, if probabilities are
+    /// calibrated
+    pub fn platt_coefficients(&self) -> Option<(A, A)> {
+        self.platt
+    }
+
+    /// Evaluate the decision function `sum_i alpha_i K(x_i, x) - rho` for each observation
+    pub fn decision_function<D: Data<Elem = A>>(&self, data: &ArrayBase<D, Ix2>) -> Array1<A> {
+        let mut values = match self.linear_decision {
+            Some(ref x) => data.dot(x),
+            None => self.kernel.weighted_sums(&self.alpha, data),
+        };
+
+        values -= self.rho;
+
+        values
+    }
+
+    /// Pre-calculate the weight vector of a linear kernel from the final coefficients
+    ///
+    /// The coefficients are expected to be signed and indexed like the kernel matrix.
+    pub(crate) fn with_linear_decision(mut self) -> Self {
+        self.linear_decision = if self.kernel.is_linear() {
+            let mut tmp = Array1::zeros(self.kernel.dataset.ncols());
+            for (alpha, elm) in self.alpha.iter().zip(self.kernel.dataset.outer_iter()) {
+                tmp.scaled_add(*alpha, &elm);
+            }
+
+            Some(tmp)
+        } else {
+            None
+        };
+
+        self
+    }
+
     pub fn with_phantom<S>(self) -> Svm<'a, A, S> {
         Svm {
             alpha: self.alpha,
@@ -211,13 +433,20 @@ impl<'a, A: Float, T> Svm<'a, A, T> {
             exit_reason: self.exit_reason,
             obj: self.obj,
             iterations: self.iterations,
+            bounded: self.bounded,
             kernel: self.kernel,
             linear_decision: self.linear_decision,
+            platt: self.platt,
             phantom: PhantomData,
         }
     }
 }
 
+/// Whether an observation with this dual coefficient is a support vector
+fn is_support<A: Float>(alpha: A) -> bool {
+    alpha.abs() > A::from(SUPPORT_THRESHOLD).unwrap()
+}
+
 impl<'a, A: Float, T> fmt::Display for Svm<'a, A, T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self.exit_reason {
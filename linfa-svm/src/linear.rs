@@ -0,0 +1,987 @@
+//! Linear Support Vector Machines
+//!
+//! For linear kernels the decision function is a weight vector `w` and the kernel matrix is not
+//! necessary. The dual problem is solved by coordinate descent, which updates a single
+//! coefficient at a time and keeps `w` up to date. Each step then only needs a single observation,
+//! which makes the solver suitable for large and sparse records. The offset is learned as the
+//! weight of an additional constant feature, and therefore regularized as well.
+//!
+//! This follows the formulation of liblinear, see
+//!  * Hsieh et al.: "A Dual Coordinate Descent Method for Large-scale Linear SVM"
+//!  * Ho, Lin: "Large-scale Linear Support Vector Regression"
+use linfa::{
+    dataset::Dataset,
+    dataset::Pr,
+    error::{Error, Result},
+    traits::Fit,
+    traits::Predict,
+};
+use ndarray::{Array1, Array2, ArrayBase, Data, Ix2};
+use ndarray_rand::rand::{seq::SliceRandom, SeedableRng};
+use rand_isaac::Isaac64Rng;
+use sprs::{CsMatBase, SpIndex};
+use std::marker::PhantomData;
+use std::ops::Deref;
+
+#[cfg(feature = "serde")]
+use serde_crate::{Deserialize, Serialize};
+
+use super::classification::expand_coefficients;
+use super::permutable_kernel::Kernel;
+use super::{ExitReason, Float, SolverParams, SolverProgress, Svm, SvmParams};
+
+/// Default number of passes over the observations
+const MAX_ITERATIONS: usize = 1000;
+
+/// Loss function of the linear solver
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinearLoss {
+    /// Hinge loss for classification, epsilon-insensitive loss for regression
+    L1,
+    /// Squared hinge loss for classification, squared epsilon-insensitive loss for regression
+    L2,
+}
+
+/// Records which can be used by the linear solver
+///
+/// This is implemented for dense matrices and sparse matrices in CSR format. Sparse matrices in
+/// CSC format panic, because their outer dimension indexes the features.
+pub trait LinearRecords<F: Float> {
+    /// Number of observations
+    fn nobservations(&self) -> usize;
+    /// Number of features
+    fn nfeatures(&self) -> usize;
+    /// Inner product of the i-th observation with `w`
+    fn row_dot(&self, i: usize, w: &Array1<F>) -> F;
+    /// Add the i-th observation, scaled by `alpha`, to `w`
+    fn row_scaled_add(&self, i: usize, alpha: F, w: &mut Array1<F>);
+    /// Squared norm of the i-th observation
+    fn row_norm_sq(&self, i: usize) -> F;
+}
+
+impl<F: Float, D: Data<Elem = F>> LinearRecords<F> for ArrayBase<D, Ix2> {
+    fn nobservations(&self) -> usize {
+        self.nrows()
+    }
+
+    fn nfeatures(&self) -> usize {
+        self.ncols()
+    }
+
+    fn row_dot(&self, i: usize, w: &Array1<F>) -> F {
+        self.row(i).dot(w)
+    }
+
+    fn row_scaled_add(&self, i: usize, alpha: F, w: &mut Array1<F>) {
+        w.scaled_add(alpha, &self.row(i));
+    }
+
+    fn row_norm_sq(&self, i: usize) -> F {
+        self.row(i).dot(&self.row(i))
+    }
+}
+
+impl<F, I, IptrS, IndS, DataS> LinearRecords<F> for CsMatBase<F, I, IptrS, IndS, DataS>
+where
+    F: Float,
+    I: SpIndex,
+    IptrS: Deref<Target = [I]>,
+    IndS: Deref<Target = [I]>,
+    DataS: Deref<Target = [F]>,
+{
+    fn nobservations(&self) -> usize {
+        assert!(self.is_csr(), "sparse records should be in CSR format");
+        self.rows()
+    }
+
+    fn nfeatures(&self) -> usize {
+        assert!(self.is_csr(), "sparse records should be in CSR format");
+        self.cols()
+    }
+
+    fn row_dot(&self, i: usize, w: &Array1<F>) -> F {
+        self.outer_view(i)
+            .unwrap()
+            .iter()
+            .map(|(j, val)| *val * w[j])
+            .sum()
+    }
+
+    fn row_scaled_add(&self, i: usize, alpha: F, w: &mut Array1<F>) {
+        for (j, val) in self.outer_view(i).unwrap().iter() {
+            w[j] += alpha * *val;
+        }
+    }
+
+    fn row_norm_sq(&self, i: usize) -> F {
+        self.outer_view(i)
+            .unwrap()
+            .iter()
+            .map(|(_, val)| *val * *val)
+            .sum()
+    }
+}
+
+/// Fitted linear Support Vector Machine
+///
+/// In contrast to [`Svm`](struct.Svm.html) the model only keeps the weight vector and offset.
+/// Dense records can be predicted with `Predict`, sparse records with
+/// [decision_function](#method.decision_function).
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
+pub struct LinearSvm<F: Float, T> {
+    weights: Array1<F>,
+    bias: F,
+    alpha: Vec<F>,
+    exit_reason: ExitReason,
+    iterations: usize,
+    obj: F,
+    phantom: PhantomData<T>,
+}
+
+impl<F: Float, T> LinearSvm<F, T> {
+    /// Weight of each feature in the decision function
+    pub fn weights(&self) -> &Array1<F> {
+        &self.weights
+    }
+
+    /// Offset of the decision function
+    pub fn bias(&self) -> F {
+        self.bias
+    }
+
+    /// Signed dual coefficient of each training observation
+    pub fn dual_coefficients(&self) -> &[F] {
+        &self.alpha
+    }
+
+    /// Returns whether the solver reached the threshold or the maximal number of iterations
+    pub fn exit_reason(&self) -> ExitReason {
+        self.exit_reason
+    }
+
+    /// Returns the number of passes over the training observations
+    pub fn iterations(&self) -> usize {
+        self.iterations
+    }
+
+    /// Returns the final value of the dual objective
+    pub fn objective(&self) -> F {
+        self.obj
+    }
+
+    /// Evaluate the decision function `w^T x + b` for each observation
+    pub fn decision_function<R: LinearRecords<F>>(&self, records: &R) -> Array1<F> {
+        (0..records.nobservations())
+            .map(|i| records.row_dot(i, &self.weights) + self.bias)
+            .collect()
+    }
+}
+
+/// Solution of the dual problem
+struct DualSolution<F: Float> {
+    alpha: Vec<F>,
+    bounded: Vec<bool>,
+    weights: Array1<F>,
+    bias: F,
+    exit_reason: ExitReason,
+    iterations: usize,
+    obj: F,
+}
+
+impl<F: Float> DualSolution<F> {
+    fn into_model<T>(self) -> LinearSvm<F, T> {
+        LinearSvm {
+            weights: self.weights,
+            bias: self.bias,
+            alpha: self.alpha,
+            exit_reason: self.exit_reason,
+            iterations: self.iterations,
+            obj: self.obj,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Convert to a kernel model, the coefficients of the subset are placed at `indices`
+    fn into_svm<'a>(self, kernel: &'a Kernel<'a, F>, indices: &[usize]) -> Svm<'a, F, F> {
+        let alpha = expand_coefficients(self.alpha.into_iter(), indices, kernel.size());
+        let bounded = expand_coefficients(self.bounded.into_iter(), indices, kernel.size());
+
+        Svm {
+            alpha,
+            bounded,
+            rho: -self.bias,
+            r: None,
+            exit_reason: self.exit_reason,
+            iterations: self.iterations,
+            obj: self.obj,
+            kernel,
+            linear_decision: Some(self.weights),
+            platt: None,
+            phantom: PhantomData,
+        }
+    }
+}
+
+/// Upper bound and diagonal term of a coefficient with penalty `c`
+///
+/// The L2 loss has no upper bound, but a diagonal term.
+fn bound_and_diagonal<F: Float>(c: F, loss: LinearLoss) -> (F, F) {
+    match loss {
+        LinearLoss::L1 => (c, F::zero()),
+        LinearLoss::L2 => (F::infinity(), F::one() / (F::from(2.0).unwrap() * c)),
+    }
+}
+
+/// Dual problem optimized one coordinate at a time
+///
+/// Both problems keep the primal weights `w = sum_i alpha_i x_i` and the weight of the constant
+/// feature up to date.
+trait DualProblem<F: Float> {
+    /// Number of coordinates
+    fn size(&self) -> usize;
+    /// Optimize the k-th coordinate and return its violation of the optimality conditions
+    fn step(&mut self, k: usize) -> F;
+    /// Value of the dual objective
+    fn objective(&self) -> F;
+    /// Whether each coefficient reached its upper bound
+    fn bounded(&self) -> Vec<bool>;
+    /// Signed coefficients, the primal weights and the offset
+    fn into_solution(self) -> (Vec<F>, Array1<F>, F);
+}
+
+/// Iterate over the coordinates in random order until the violation is below `eps`
+fn coordinate_descent<F: Float, P: DualProblem<F>>(
+    mut problem: P,
+    params: &SolverParams<F>,
+) -> DualSolution<F> {
+    let max_iter = params.max_iterations.unwrap_or(MAX_ITERATIONS);
+    let mut rng = Isaac64Rng::seed_from_u64(42);
+    let mut order = (0..problem.size()).collect::<Vec<_>>();
+
+    let mut exit_reason = ExitReason::ReachedIterations;
+    let mut iterations = max_iter;
+    for iter in 0..max_iter {
+        order.shuffle(&mut rng);
+
+        let kkt_gap = order
+            .iter()
+            .map(|k| problem.step(*k))
+            .fold(F::zero(), F::max);
+
+        if let Some((every, ref callback)) = params.progress {
+            if (iter + 1) % usize::max(every, 1) == 0 {
+                callback(&SolverProgress {
+                    iteration: iter + 1,
+                    objective: problem.objective(),
+                    kkt_gap,
+                });
+            }
+        }
+
+        if kkt_gap < params.eps {
+            exit_reason = ExitReason::ReachedThreshold;
+            iterations = iter + 1;
+            break;
+        }
+    }
+
+    let obj = problem.objective();
+    let bounded = problem.bounded();
+    let (alpha, weights, bias) = problem.into_solution();
+
+    DualSolution {
+        alpha,
+        bounded,
+        weights,
+        bias,
+        exit_reason,
+        iterations,
+        obj,
+    }
+}
+
+/// Dual problem of a linear classifier on the observations in `indices`
+struct ClassificationProblem<'a, F: Float, R: LinearRecords<F>> {
+    records: &'a R,
+    indices: &'a [usize],
+    /// Targets as +1 and -1
+    y: Vec<F>,
+    /// Upper bound of each coefficient
+    upper: Vec<F>,
+    /// Diagonal term of the L2 loss
+    diag: Vec<F>,
+    /// Diagonal of the quadratic term, including the constant feature
+    qd: Vec<F>,
+    alpha: Vec<F>,
+    weights: Array1<F>,
+    bias: F,
+}
+
+impl<'a, F: Float, R: LinearRecords<F>> ClassificationProblem<'a, F, R> {
+    /// Initialize the problem, the targets, C values and the signed coefficients of the warm
+    /// start are indexed like the records
+    fn new(
+        records: &'a R,
+        targets: &[bool],
+        indices: &'a [usize],
+        bounds: &[F],
+        loss: LinearLoss,
+        warm_start: Option<&[F]>,
+    ) -> Self {
+        let y = indices
+            .iter()
+            .map(|i| if targets[*i] { F::one() } else { -F::one() })
+            .collect::<Vec<_>>();
+
+        // the L2 loss has no upper bound, but a diagonal term
+        let (upper, diag): (Vec<_>, Vec<_>) = indices
+            .iter()
+            .map(|i| bound_and_diagonal(bounds[*i], loss))
+            .unzip();
+
+        let alpha = match warm_start {
+            Some(alpha) => {
+                assert_eq!(
+                    alpha.len(),
+                    targets.len(),
+                    "warm start coefficients do not match the number of observations"
+                );
+
+                indices
+                    .iter()
+                    .zip(upper.iter())
+                    .map(|(i, u)| alpha[*i].abs().min(*u))
+                    .collect()
+            }
+            None => vec![F::zero(); indices.len()],
+        };
+
+        let mut weights = Array1::zeros(records.nfeatures());
+        let mut bias = F::zero();
+        for (k, i) in indices.iter().enumerate() {
+            if alpha[k] != F::zero() {
+                records.row_scaled_add(*i, alpha[k] * y[k], &mut weights);
+                bias += alpha[k] * y[k];
+            }
+        }
+
+        let qd = indices
+            .iter()
+            .zip(diag.iter())
+            .map(|(i, d)| records.row_norm_sq(*i) + F::one() + *d)
+            .collect();
+
+        ClassificationProblem {
+            records,
+            indices,
+            y,
+            upper,
+            diag,
+            qd,
+            alpha,
+            weights,
+            bias,
+        }
+    }
+}
+
+impl<'a, F: Float, R: LinearRecords<F>> DualProblem<F> for ClassificationProblem<'a, F, R> {
+    fn size(&self) -> usize {
+        self.indices.len()
+    }
+
+    fn step(&mut self, k: usize) -> F {
+        let i = self.indices[k];
+        let g = self.y[k] * (self.records.row_dot(i, &self.weights) + self.bias) - F::one()
+            + self.diag[k] * self.alpha[k];
+
+        // projected gradient
+        let pg = if self.alpha[k] == F::zero() {
+            g.min(F::zero())
+        } else if self.alpha[k] >= self.upper[k] {
+            g.max(F::zero())
+        } else {
+            g
+        };
+
+        if pg.abs() > F::from(1e-12).unwrap() {
+            let old = self.alpha[k];
+            self.alpha[k] = (old - g / self.qd[k]).max(F::zero()).min(self.upper[k]);
+
+            let diff = (self.alpha[k] - old) * self.y[k];
+            self.records.row_scaled_add(i, diff, &mut self.weights);
+            self.bias += diff;
+        }
+
+        pg.abs()
+    }
+
+    fn objective(&self) -> F {
+        let reg = self
+            .alpha
+            .iter()
+            .zip(self.diag.iter())
+            .map(|(a, d)| *a * *a * *d - F::from(2.0).unwrap() * *a)
+            .sum::<F>();
+
+        (self.weights.dot(&self.weights) + self.bias * self.bias + reg) / F::from(2.0).unwrap()
+    }
+
+    fn bounded(&self) -> Vec<bool> {
+        self.alpha
+            .iter()
+            .zip(self.upper.iter())
+            .map(|(a, u)| *a >= *u)
+            .collect()
+    }
+
+    fn into_solution(self) -> (Vec<F>, Array1<F>, F) {
+        let alpha = self
+            .alpha
+            .iter()
+            .zip(self.y.iter())
+            .map(|(a, y)| *a * *y)
+            .collect();
+
+        (alpha, self.weights, self.bias)
+    }
+}
+
+/// Dual problem of a linear regression with tolerance `p`
+struct RegressionProblem<'a, F: Float, R: LinearRecords<F>> {
+    records: &'a R,
+    targets: &'a [F],
+    p: F,
+    /// Upper bound of the absolute value of each coefficient
+    upper: Vec<F>,
+    /// Diagonal term of the L2 loss
+    lambda: Vec<F>,
+    /// Diagonal of the quadratic term, including the constant feature
+    qd: Vec<F>,
+    beta: Vec<F>,
+    weights: Array1<F>,
+    bias: F,
+}
+
+impl<'a, F: Float, R: LinearRecords<F>> RegressionProblem<'a, F, R> {
+    fn new(
+        records: &'a R,
+        targets: &'a [F],
+        bounds: &[F],
+        p: F,
+        loss: LinearLoss,
+        warm_start: Option<&[F]>,
+    ) -> Self {
+        let n = targets.len();
+
+        let (upper, lambda): (Vec<_>, Vec<_>) =
+            bounds.iter().map(|c| bound_and_diagonal(*c, loss)).unzip();
+
+        let beta = match warm_start {
+            Some(beta) => {
+                assert_eq!(
+                    beta.len(),
+                    n,
+                    "warm start coefficients do not match the number of observations"
+                );
+
+                beta.iter()
+                    .zip(upper.iter())
+                    .map(|(x, u)| x.max(-*u).min(*u))
+                    .collect()
+            }
+            None => vec![F::zero(); n],
+        };
+
+        let mut weights = Array1::zeros(records.nfeatures());
+        let mut bias = F::zero();
+        for (i, b) in beta.iter().enumerate() {
+            if *b != F::zero() {
+                records.row_scaled_add(i, *b, &mut weights);
+                bias += *b;
+            }
+        }
+
+        let qd = (0..n)
+            .map(|i| records.row_norm_sq(i) + F::one() + lambda[i])
+            .collect();
+
+        RegressionProblem {
+            records,
+            targets,
+            p,
+            upper,
+            lambda,
+            qd,
+            beta,
+            weights,
+            bias,
+        }
+    }
+}
+
+impl<'a, F: Float, R: LinearRecords<F>> DualProblem<F> for RegressionProblem<'a, F, R> {
+    fn size(&self) -> usize {
+        self.targets.len()
+    }
+
+    fn step(&mut self, i: usize) -> F {
+        let beta = self.beta[i];
+        let g = self.records.row_dot(i, &self.weights) + self.bias - self.targets[i]
+            + self.lambda[i] * beta;
+        let (gp, gn) = (g + self.p, g - self.p);
+
+        // violation of the optimality conditions
+        let violation = if beta == F::zero() {
+            (-gp).max(gn).max(F::zero())
+        } else if beta >= self.upper[i] {
+            gp.max(F::zero())
+        } else if beta <= -self.upper[i] {
+            (-gn).max(F::zero())
+        } else if beta > F::zero() {
+            gp.abs()
+        } else {
+            gn.abs()
+        };
+
+        // minimize the one-dimensional problem with soft thresholding
+        let h = self.qd[i];
+        let d = if gp < h * beta {
+            -gp / h
+        } else if gn > h * beta {
+            -gn / h
+        } else {
+            -beta
+        };
+
+        if d.abs() > F::from(1e-12).unwrap() {
+            self.beta[i] = (beta + d).max(-self.upper[i]).min(self.upper[i]);
+
+            let diff = self.beta[i] - beta;
+            self.records.row_scaled_add(i, diff, &mut self.weights);
+            self.bias += diff;
+        }
+
+        violation
+    }
+
+    fn objective(&self) -> F {
+        let linear = self
+            .beta
+            .iter()
+            .zip(self.targets.iter())
+            .zip(self.lambda.iter())
+            .map(|((b, y), l)| self.p * b.abs() - *y * *b + *l * *b * *b / F::from(2.0).unwrap())
+            .sum::<F>();
+
+        (self.weights.dot(&self.weights) + self.bias * self.bias) / F::from(2.0).unwrap() + linear
+    }
+
+    fn bounded(&self) -> Vec<bool> {
+        self.beta
+            .iter()
+            .zip(self.upper.iter())
+            .map(|(b, u)| b.abs() >= *u)
+            .collect()
+    }
+
+    fn into_solution(self) -> (Vec<F>, Array1<F>, F) {
+        (self.beta, self.weights, self.bias)
+    }
+}
+
+impl<F: Float, T> SvmParams<F, T> {
+    /// Check the parameters of a linear model fitted without a kernel and return its loss
+    ///
+    /// The solver has to be selected with `linear_solver` and supports neither Nu values nor
+    /// probability calibration.
+    fn linear_records_loss(&self) -> Result<LinearLoss> {
+        let loss = self.linear_loss.ok_or_else(|| {
+            Error::Parameters(
+                "Fitting records without a kernel needs a linear solver, selected with `linear_solver`"
+                    .to_string(),
+            )
+        })?;
+
+        if self.c.is_none() {
+            return Err(Error::Parameters(
+                "The linear solver needs a C value, Nu values are not supported".to_string(),
+            ));
+        }
+
+        if self.probability {
+            return Err(Error::Parameters(
+                "The linear solver does not calibrate probabilities".to_string(),
+            ));
+        }
+
+        Ok(loss)
+    }
+}
+
+impl<F: Float> SvmParams<F, Pr> {
+    /// Fit a linear classifier on dense or sparse records with dual coordinate descent
+    ///
+    /// Sparse records are expected in CSR format. Returns an error if the linear solver is not
+    /// selected with [linear_solver](struct.SvmParams.html#method.linear_solver), or a Nu value
+    /// or probability calibration is set.
+    pub fn fit_linear<R: LinearRecords<F>>(
+        &self,
+        records: &R,
+        targets: &[bool],
+    ) -> Result<LinearSvm<F, Pr>> {
+        self.fit_linear_weighted(records, targets, None)
+    }
+
+    fn fit_linear_weighted<R: LinearRecords<F>>(
+        &self,
+        records: &R,
+        targets: &[bool],
+        weights: Option<&[f32]>,
+    ) -> Result<LinearSvm<F, Pr>> {
+        let loss = self.linear_records_loss()?;
+        let weights = self.binary_weights(targets, weights);
+        let bounds = self.c_bounds(targets, weights.as_deref()).unwrap();
+        let indices = (0..targets.len()).collect::<Vec<_>>();
+
+        let problem = ClassificationProblem::new(
+            records,
+            targets,
+            &indices,
+            &bounds,
+            loss,
+            self.warm_start.as_deref(),
+        );
+
+        Ok(coordinate_descent(problem, &self.solver_params).into_model())
+    }
+
+    /// Solve a linear kernel on a subset of the observations with dual coordinate descent
+    ///
+    /// Returns `None` if the kernel is not linear or the linear solver is disabled.
+    pub(crate) fn solve_linear_subset<'a>(
+        &self,
+        kernel: &'a Kernel<'a, F>,
+        targets: &[bool],
+        indices: &[usize],
+        bounds: &[F],
+        warm_start: Option<&[F]>,
+    ) -> Option<Svm<'a, F, Pr>> {
+        let loss = self.linear_loss?;
+        if !kernel.is_linear() {
+            return None;
+        }
+
+        let problem =
+            ClassificationProblem::new(&kernel.dataset, targets, indices, bounds, loss, warm_start);
+        let solution = coordinate_descent(problem, &self.solver_params);
+
+        Some(solution.into_svm(kernel, indices).with_phantom())
+    }
+}
+
+impl<F: Float> SvmParams<F, F> {
+    /// Fit a linear regression on dense or sparse records with dual coordinate descent
+    ///
+    /// Sparse records are expected in CSR format. Returns an error if the linear solver is not
+    /// selected with [linear_solver](struct.SvmParams.html#method.linear_solver) or a Nu value is
+    /// set.
+    pub fn fit_linear_regression<R: LinearRecords<F>>(
+        &self,
+        records: &R,
+        targets: &[F],
+    ) -> Result<LinearSvm<F, F>> {
+        self.fit_linear_regression_weighted(records, targets, None)
+    }
+
+    fn fit_linear_regression_weighted<R: LinearRecords<F>>(
+        &self,
+        records: &R,
+        targets: &[F],
+        weights: Option<&[f32]>,
+    ) -> Result<LinearSvm<F, F>> {
+        let loss = self.linear_records_loss()?;
+        let (_, eps) = self.c.unwrap();
+        let bounds = self.regression_bounds(targets.len(), weights).unwrap();
+
+        let problem = RegressionProblem::new(
+            records,
+            targets,
+            &bounds,
+            eps,
+            loss,
+            self.warm_start.as_deref(),
+        );
+
+        Ok(coordinate_descent(problem, &self.solver_params).into_model())
+    }
+
+    /// Solve a linear kernel with dual coordinate descent
+    ///
+    /// Returns `None` if the kernel is not linear or the linear solver is disabled.
+    pub(crate) fn solve_linear<'a>(
+        &self,
+        kernel: &'a Kernel<'a, F>,
+        targets: &[F],
+        bounds: &[F],
+    ) -> Option<Svm<'a, F, F>> {
+        let (_, eps) = self.c?;
+        let loss = self.linear_loss?;
+        if !kernel.is_linear() {
+            return None;
+        }
+
+        let indices = (0..targets.len()).collect::<Vec<_>>();
+        let problem = RegressionProblem::new(
+            &kernel.dataset,
+            targets,
+            bounds,
+            eps,
+            loss,
+            self.warm_start.as_deref(),
+        );
+        let solution = coordinate_descent(problem, &self.solver_params);
+
+        Some(solution.into_svm(kernel, &indices).with_phantom())
+    }
+}
+
+/// Fit a linear classifier on records without a kernel, see [fit_linear](struct.SvmParams.html#method.fit_linear)
+impl<'a, F: Float> Fit<'a, Array2<F>, Vec<bool>> for SvmParams<F, Pr> {
+    type Object = Result<LinearSvm<F, Pr>>;
+
+    fn fit(&self, dataset: &'a Dataset<Array2<F>, Vec<bool>>) -> Self::Object {
+        self.fit_linear_weighted(&dataset.records, dataset.targets(), dataset.weights())
+    }
+}
+
+/// Fit a linear regression on records without a kernel, see
+/// [fit_linear_regression](struct.SvmParams.html#method.fit_linear_regression)
+impl<'a, F: Float> Fit<'a, Array2<F>, Vec<F>> for SvmParams<F, F> {
+    type Object = Result<LinearSvm<F, F>>;
+
+    fn fit(&self, dataset: &'a Dataset<Array2<F>, Vec<F>>) -> Self::Object {
+        self.fit_linear_regression_weighted(&dataset.records, dataset.targets(), dataset.weights())
+    }
+}
+
+/// Predict a decision value with a set of observations
+impl<F: Float, D: Data<Elem = F>> Predict<ArrayBase<D, Ix2>, Vec<Pr>> for LinearSvm<F, Pr> {
+    fn predict(&self, data: ArrayBase<D, Ix2>) -> Vec<Pr> {
+        self.decision_function(&data)
+            .iter()
+            // this is safe because `F` is only implemented for `f32` and `f64`
+            .map(|val| Pr(val.to_f32().unwrap()))
+            .collect()
+    }
+}
+
+/// Predict a continuous target with a set of observations
+impl<D: Data<Elem = f64>> Predict<ArrayBase<D, Ix2>, Vec<f64>> for LinearSvm<f64, f64> {
+    fn predict(&self, data: ArrayBase<D, Ix2>) -> Vec<f64> {
+        self.decision_function(&data).to_vec()
+    }
+}
+
+/// Predict a continuous target with a set of observations
+impl<D: Data<Elem = f32>> Predict<ArrayBase<D, Ix2>, Vec<f32>> for LinearSvm<f32, f32> {
+    fn predict(&self, data: ArrayBase<D, Ix2>) -> Vec<f32> {
+        self.decision_function(&data).to_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LinearLoss;
+    use crate::{ExitReason, Svm};
+    use linfa::dataset::Dataset;
+    use linfa::traits::{Fit, Predict, Transformer};
+    use linfa_kernel::{Kernel, KernelMethod, KernelType};
+    use ndarray::{Array, Array2, Axis};
+    use ndarray_rand::rand::SeedableRng;
+    use ndarray_rand::rand_distr::Uniform;
+    use ndarray_rand::RandomExt;
+    use rand_isaac::Isaac64Rng;
+    use sprs::CsMat;
+
+    /// Two separable groups with five features, of which only the first two are non-zero
+    fn separable() -> (Array2<f64>, Vec<bool>) {
+        let mut rng = Isaac64Rng::seed_from_u64(42);
+        let mut records = Array2::zeros((40, 5));
+        let group = Array::random_using((40, 2), Uniform::new(0.5, 1.5), &mut rng);
+        records.slice_mut(ndarray::s![.., ..2]).assign(&group);
+        records
+            .slice_mut(ndarray::s![20.., ..2])
+            .mapv_inplace(|x| -x);
+
+        let targets = (0..40).map(|x| x < 20).collect();
+
+        (records, targets)
+    }
+
+    #[test]
+    fn test_linear_classification() {
+        let (records, targets) = separable();
+        let sparse = CsMat::csr_from_dense(records.view(), 0.0);
+
+        for loss in [LinearLoss::L1, LinearLoss::L2] {
+            let params = Svm::params()
+                .pos_neg_weights(1.0, 1.0)
+                .eps(1e-5)
+                .linear_solver(Some(loss));
+
+            let model = params.fit_linear(&records, &targets).unwrap();
+            assert_eq!(model.exit_reason(), ExitReason::ReachedThreshold);
+            assert_eq!(model.dual_coefficients().len(), 40);
+
+            let pred = model.predict(records.view());
+            for (p, t) in pred.iter().zip(targets.iter()) {
+                assert_eq!(p.0 > 0.0, *t);
+            }
+
+            // the sparse records result in the same model
+            let sparse_model = params.fit_linear(&sparse, &targets).unwrap();
+            assert!((model.weights() - sparse_model.weights())
+                .iter()
+                .all(|x| x.abs() < 1e-10));
+            assert!((model.bias() - sparse_model.bias()).abs() < 1e-10);
+
+            // features which are always zero have no weight
+            assert!(model.weights().iter().skip(2).all(|x| *x == 0.0));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "sparse records should be in CSR format")]
+    fn test_linear_classification_csc() {
+        let (records, targets) = separable();
+        let sparse = CsMat::csc_from_dense(records.view(), 0.0);
+
+        let _ = Svm::params()
+            .pos_neg_weights(1.0, 1.0)
+            .linear_solver(Some(LinearLoss::L1))
+            .fit_linear(&sparse, &targets);
+    }
+
+    #[test]
+    fn test_linear_invalid_params() {
+        let (records, targets) = separable();
+        let dataset = Dataset::new(records.clone(), targets.clone());
+
+        // the linear solver has to be selected
+        assert!(Svm::params()
+            .pos_neg_weights(1.0, 1.0)
+            .fit(&dataset)
+            .is_err());
+
+        // Nu values and probabilities are not supported
+        assert!(Svm::params()
+            .nu_weight(0.1)
+            .linear_solver(Some(LinearLoss::L1))
+            .fit(&dataset)
+            .is_err());
+        assert!(Svm::params()
+            .pos_neg_weights(1.0, 1.0)
+            .linear_solver(Some(LinearLoss::L1))
+            .probability(true)
+            .fit_linear(&records, &targets)
+            .is_err());
+
+        let targets = (0..40).map(|x| x as f64).collect::<Vec<_>>();
+        let dataset = Dataset::new(records, targets);
+        assert!(Svm::params()
+            .nu_eps(0.5, 0.1)
+            .linear_solver(Some(LinearLoss::L1))
+            .fit(&dataset)
+            .is_err());
+    }
+
+    #[test]
+    fn test_linear_kernel_solver() {
+        let (records, targets) = separable();
+        let dataset = Dataset::new(records.clone(), targets);
+
+        // the kernel matrix is never evaluated with the linear solver
+        let kernel = Kernel::params()
+            .method(KernelMethod::Linear)
+            .kind(KernelType::Lazy(10))
+            .transform(&dataset);
+
+        let model = Svm::params()
+            .pos_neg_weights(1.0, 1.0)
+            .linear_solver(Some(LinearLoss::L1))
+            .fit(&kernel);
+        let linear = Svm::params()
+            .pos_neg_weights(1.0, 1.0)
+            .linear_solver(Some(LinearLoss::L1))
+            .fit_linear(&records, dataset.targets())
+            .unwrap();
+
+        assert_eq!(model.iterations(), linear.iterations());
+        assert!((model.rho + linear.bias()).abs() < 1e-10);
+
+        // the SMO solver, used by default, agrees on the separation
+        let smo = Svm::params().pos_neg_weights(1.0, 1.0).fit(&kernel);
+
+        let pred = model.predict(records.view());
+        let pred_smo = smo.predict(records.view());
+        for (a, b) in pred.iter().zip(pred_smo.iter()) {
+            assert_eq!(a.0 > 0.0, b.0 > 0.0);
+        }
+    }
+
+    #[test]
+    fn test_linear_regression() {
+        let mut rng = Isaac64Rng::seed_from_u64(42);
+        let records = Array::random_using((50, 2), Uniform::new(-1., 1.), &mut rng);
+        let targets = records
+            .map_axis(Axis(1), |x| 2.0 * x[0] - x[1] + 0.5)
+            .to_vec();
+        let dataset = Dataset::new(records.clone(), targets.clone());
+
+        for loss in [LinearLoss::L1, LinearLoss::L2] {
+            let model = Svm::params()
+                .c_eps(100.0, 0.01)
+                .eps(1e-6)
+                .linear_solver(Some(loss))
+                .fit(&dataset)
+                .unwrap();
+
+            let pred: Vec<f64> = model.predict(records.view());
+            for (p, t) in pred.iter().zip(targets.iter()) {
+                assert!((p - t).abs() < 0.05);
+            }
+
+            // sparse records with the same model
+            let sparse = CsMat::csr_from_dense(records.view(), 0.0);
+            let decision = model.decision_function(&sparse);
+            for (p, d) in pred.iter().zip(decision.iter()) {
+                assert!((p - d).abs() < 1e-10);
+            }
+        }
+
+        // the kernel interface uses the same solver, if selected
+        let kernel = Kernel::params()
+            .method(KernelMethod::Linear)
+            .transform(&records);
+        let kernel = Dataset::new(kernel, &targets);
+        let svm = Svm::params()
+            .c_eps(100.0, 0.01)
+            .eps(1e-6)
+            .linear_solver(Some(LinearLoss::L1))
+            .fit(&kernel);
+        let pred: Vec<f64> = svm.predict(records.view());
+        for (p, t) in pred.iter().zip(targets.iter()) {
+            assert!((p - t).abs() < 0.05);
+        }
+    }
+}
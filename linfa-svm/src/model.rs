@@ -0,0 +1,283 @@
+//! Fitted SVM model owning its support vectors
+use linfa::{dataset::Dataset, dataset::Pr, dataset::Targets, traits::Predict};
+use linfa_kernel::KernelMethod;
+use ndarray::{Array1, Array2, ArrayBase, ArrayView2, Axis, Data, Ix2};
+use std::marker::PhantomData;
+
+#[cfg(feature = "serde")]
+use serde_crate::{Deserialize, Serialize};
+
+use super::platt::sigmoid_predict;
+use super::{Float, Svm};
+
+/// Number of observations for which the kernel is evaluated at once
+const BATCH_SIZE: usize = 1024;
+
+/// Fitted SVM model, independent of the training data
+///
+/// In contrast to [`Svm`](struct.Svm.html) the model does not borrow the kernel matrix, but only
+/// keeps the support vectors with their coefficients. It can therefore outlive the training
+/// data and be serialized. Kernel methods constructed with `KernelMethod::custom` cannot be
+/// serialized.
+///
+/// ```ignore
+/// let model = Svm::params().pos_neg_weights(1.0, 1.0).fit(&dataset).to_model();
+///
+/// let writer = std::fs::File::create("model.json")?;
+/// serde_json::to_writer(writer, &model)?;
+/// ```
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
+pub struct SvmModel<F: Float, T> {
+    support_vectors: Array2<F>,
+    coefficients: Array1<F>,
+    rho: F,
+    method: KernelMethod<F>,
+    linear_decision: Option<Array1<F>>,
+    platt: Option<(F, F)>,
+    phantom: PhantomData<T>,
+}
+
+impl<F: Float, T> SvmModel<F, T> {
+    /// Observations with non-zero coefficient, one per row
+    pub fn support_vectors(&self) -> &Array2<F> {
+        &self.support_vectors
+    }
+
+    /// Signed coefficient of each support vector
+    pub fn coefficients(&self) -> &Array1<F> {
+        &self.coefficients
+    }
+
+    /// Offset of the decision function
+    pub fn rho(&self) -> F {
+        self.rho
+    }
+
+    /// Kernel method used to compare observations with the support vectors
+    pub fn method(&self) -> &KernelMethod<F> {
+        &self.method
+    }
+
+    /// Coefficients `(A, B)` of the sigmoid calibrating the probabilities, if any
+    pub fn platt_coefficients(&self) -> Option<(F, F)> {
+        self.platt
+    }
+
+    /// Weight vector of the discriminant in the feature space, only available for linear kernels
+    pub fn primal_weights(&self) -> Option<&Array1<F>> {
+        self.linear_decision.as_ref()
+    }
+
+    /// Returns the number of support vectors
+    pub fn nsupport(&self) -> usize {
+        self.support_vectors.nrows()
+    }
+
+    /// Evaluate the decision function for each observation
+    pub fn decision_function<D: Data<Elem = F>>(&self, data: &ArrayBase<D, Ix2>) -> Array1<F> {
+        let mut values = match self.linear_decision {
+            Some(ref x) => data.dot(x),
+            None => {
+                let mut values = Array1::zeros(data.nrows());
+                for (batch, mut values) in data
+                    .axis_chunks_iter(Axis(0), BATCH_SIZE)
+                    .zip(values.axis_chunks_iter_mut(Axis(0), BATCH_SIZE))
+                {
+                    let kernel = self.method.cross(&batch, &self.support_vectors);
+                    values.assign(&kernel.dot(&self.coefficients));
+                }
+
+                values
+            }
+        };
+
+        values -= self.rho;
+
+        values
+    }
+}
+
+impl<F: Float> SvmModel<F, Pr> {
+    /// Map a decision value to a probability if the model is calibrated
+    fn to_pr(&self, val: F) -> Pr {
+        let val = match self.platt {
+            Some(coefs) => sigmoid_predict(val, coefs),
+            None => val,
+        };
+
+        // this is safe because `F` is only implemented for `f32` and `f64`
+        Pr(val.to_f32().unwrap())
+    }
+}
+
+impl<'a, F: Float, T> Svm<'a, F, T> {
+    /// Copy the support vectors into a model, which does not borrow the training data
+    pub fn to_model(&self) -> SvmModel<F, T> {
+        let support = self.support_indices();
+
+        SvmModel {
+            support_vectors: self.kernel.dataset.select(Axis(0), &support),
+            coefficients: support.iter().map(|i| self.alpha[*i]).collect(),
+            rho: self.rho,
+            method: self.kernel.method.clone(),
+            linear_decision: self.linear_decision.clone(),
+            platt: self.platt,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<'a, F: Float, T> From<Svm<'a, F, T>> for SvmModel<F, T> {
+    fn from(svm: Svm<'a, F, T>) -> SvmModel<F, T> {
+        svm.to_model()
+    }
+}
+
+/// Predict a probability with a feature vector
+impl<F: Float> Predict<Array1<F>, Pr> for SvmModel<F, Pr> {
+    fn predict(&self, data: Array1<F>) -> Pr {
+        let data = data.insert_axis(Axis(0));
+
+        self.to_pr(self.decision_function(&data)[0])
+    }
+}
+
+/// Predict a probability with a set of observations
+impl<F: Float, D: Data<Elem = F>> Predict<ArrayBase<D, Ix2>, Vec<Pr>> for SvmModel<F, Pr> {
+    fn predict(&self, data: ArrayBase<D, Ix2>) -> Vec<Pr> {
+        self.decision_function(&data)
+            .iter()
+            .map(|val| self.to_pr(*val))
+            .collect()
+    }
+}
+
+impl<F: Float, T: Targets> Predict<Dataset<Array2<F>, T>, Dataset<Array2<F>, Vec<Pr>>>
+    for SvmModel<F, Pr>
+{
+    fn predict(&self, data: Dataset<Array2<F>, T>) -> Dataset<Array2<F>, Vec<Pr>> {
+        let Dataset { records, .. } = data;
+        let predicted = self.predict(records.view());
+
+        Dataset::new(records, predicted)
+    }
+}
+
+impl<'a, F: Float, T: Targets, D: Data<Elem = F>>
+    Predict<&'a Dataset<ArrayBase<D, Ix2>, T>, Dataset<ArrayView2<'a, F>, Vec<Pr>>>
+    for SvmModel<F, Pr>
+{
+    fn predict(
+        &self,
+        data: &'a Dataset<ArrayBase<D, Ix2>, T>,
+    ) -> Dataset<ArrayView2<'a, F>, Vec<Pr>> {
+        let predicted = self.predict(data.records.view());
+
+        Dataset::new(data.records.view(), predicted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SvmModel;
+    use crate::Svm;
+    use linfa::dataset::{Dataset, Pr};
+    use linfa::traits::{Fit, Predict, Transformer};
+    use linfa_kernel::{Kernel, KernelMethod};
+    use ndarray::{Array, Array2};
+    use ndarray_rand::rand::SeedableRng;
+    use ndarray_rand::rand_distr::Uniform;
+    use ndarray_rand::RandomExt;
+    use rand_isaac::Isaac64Rng;
+
+    fn train_model(records: &Array2<f64>, method: KernelMethod<f64>) -> SvmModel<f64, Pr> {
+        let targets = records
+            .outer_iter()
+            .map(|x| x[0] * x[0] + x[1] * x[1] < 1.0)
+            .collect::<Vec<_>>();
+        let dataset = Dataset::new(records.clone(), targets);
+        let dataset = Kernel::params().method(method).transform(&dataset);
+
+        // the model is returned, while the kernel matrix is dropped
+        let svm = Svm::params().pos_neg_weights(1.0, 1.0).fit(&dataset);
+        let model = svm.to_model();
+
+        let expected = svm.predict(records.view());
+        let predicted = model.predict(records.view());
+        for (a, b) in expected.iter().zip(predicted.iter()) {
+            assert!((a.0 - b.0).abs() < 1e-5);
+        }
+        assert_eq!(model.nsupport(), model.coefficients().len());
+        assert!(model.nsupport() <= records.nrows());
+
+        model
+    }
+
+    #[test]
+    fn test_owned_model() {
+        let mut rng = Isaac64Rng::seed_from_u64(42);
+        let records = Array::random_using((50, 2), Uniform::new(-2., 2.), &mut rng);
+
+        for method in [KernelMethod::Gaussian(1.0), KernelMethod::Linear] {
+            let model = train_model(&records, method);
+
+            let valid = Array::random_using((20, 2), Uniform::new(-2., 2.), &mut rng);
+            assert_eq!(model.predict(valid).len(), 20);
+        }
+    }
+
+    #[test]
+    fn test_owned_regression_model() {
+        let target = Array::linspace(0f64, 10., 100).to_vec();
+        let records = Array::linspace(0f64, 10., 100)
+            .into_shape((100, 1))
+            .unwrap();
+
+        let kernel = Kernel::params()
+            .method(KernelMethod::Gaussian(50.))
+            .transform(&records);
+        let dataset = Dataset::new(kernel, &target);
+
+        let svm = Svm::params().nu_eps(2., 0.01).fit(&dataset);
+        let model = svm.to_model();
+
+        let expected: Vec<f64> = svm.predict(records.view());
+        let predicted: Vec<f64> = model.predict(records.view());
+        for (a, b) in expected.iter().zip(predicted.iter()) {
+            assert!((a - b).abs() < 1e-8);
+        }
+
+        // datasets are predicted into datasets, like with the borrowing model
+        let valid = Dataset::new(records.clone(), target.clone());
+        let predicted = model.predict(&valid);
+        assert_eq!(predicted.records(), &records);
+        assert_eq!(predicted.targets(), &model.predict(records.view()));
+
+        let predicted = model.predict(valid);
+        for (a, b) in expected.iter().zip(predicted.targets().iter()) {
+            assert!((a - b).abs() < 1e-8);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialized_model() {
+        let mut rng = Isaac64Rng::seed_from_u64(42);
+        let records = Array::random_using((50, 2), Uniform::new(-2., 2.), &mut rng);
+        let model = train_model(&records, KernelMethod::Gaussian(1.0));
+
+        let serialized = serde_json::to_string(&model).unwrap();
+        let loaded: SvmModel<f64, Pr> = serde_json::from_str(&serialized).unwrap();
+
+        // the reloaded model reproduces the predictions exactly
+        let expected = model.predict(records.view());
+        let predicted = loaded.predict(records.view());
+        for (a, b) in expected.iter().zip(predicted.iter()) {
+            assert_eq!(a.0, b.0);
+        }
+    }
+}
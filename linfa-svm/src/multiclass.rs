@@ -0,0 +1,624 @@
+//! Multi-class classification with binary SVMs
+//!
+//! The binary classifiers are combined either one-vs-one, which trains a classifier for each pair
+//! of classes and predicts the class with the most votes, or one-vs-rest, which trains a
+//! classifier for each class against all others and predicts the class with the largest decision
+//! value. All classifiers share the kernel matrix of the training data.
+use linfa::dataset::{Dataset, Label, Labels, Pr, Targets};
+use linfa::traits::{Fit, Predict};
+use ndarray::{Array1, Array2, ArrayBase, ArrayView2, Axis, Data, Ix2};
+use std::collections::HashMap;
+
+use super::permutable_kernel::Kernel;
+use super::platt::{pairwise_coupling, sigmoid_predict};
+use super::{Float, Svm, SvmParams};
+
+/// Number of observations for which the kernel is evaluated at once
+const BATCH_SIZE: usize = 1024;
+
+/// Strategy to combine binary classifiers
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MultiClassStrategy {
+    /// One classifier for each pair of classes, predictions by majority vote
+    OneVsOne,
+    /// One classifier for each class against all others, predictions by largest decision value
+    OneVsRest,
+}
+
+/// Weights scaling the C value of each class
+#[derive(Clone, Debug, PartialEq)]
+pub enum ClassWeights<F, L: Label> {
+    /// Weights inversely proportional to the class frequencies, `n / (k * n_class)`
+    Balanced,
+    /// Weight of each class, classes without a weight keep their C value
+    Custom(HashMap<L, F>),
+}
+
+/// Parameters of a multi-class SVM
+///
+/// The C or Nu values of the binary classifiers are taken from the underlying
+/// [`SvmParams`](struct.SvmParams.html). For C classification the positive weight is used for
+/// the first class of a pair, or the single class in one-vs-rest. The C value of each
+/// observation is further scaled by the weight of its class and its weight in the dataset.
+pub struct MultiClassParams<F: Float, L: Label> {
+    params: SvmParams<F, Pr>,
+    strategy: MultiClassStrategy,
+    class_weights: Option<ClassWeights<F, L>>,
+}
+
+impl<F: Float> SvmParams<F, Pr> {
+    /// Train a classifier for an arbitrary number of classes
+    ///
+    /// Defaults to the one-vs-one strategy.
+    pub fn multi_class<L: Label>(self) -> MultiClassParams<F, L> {
+        // balancing the binary problems extends to all classes
+        let class_weights = if self.balanced {
+            Some(ClassWeights::Balanced)
+        } else {
+            None
+        };
+
+        MultiClassParams {
+            params: self,
+            strategy: MultiClassStrategy::OneVsOne,
+            class_weights,
+        }
+    }
+}
+
+impl<F: Float, L: Label> MultiClassParams<F, L> {
+    pub fn strategy(mut self, strategy: MultiClassStrategy) -> Self {
+        self.strategy = strategy;
+
+        self
+    }
+
+    /// Scale the C value of each class, useful for imbalanced datasets
+    pub fn class_weights(mut self, class_weights: ClassWeights<F, L>) -> Self {
+        self.class_weights = Some(class_weights);
+
+        self
+    }
+
+    /// Weight of each observation from its class and the dataset
+    fn sample_weights(
+        &self,
+        classes: &[L],
+        class_indices: &[usize],
+        weights: Option<&[f32]>,
+    ) -> Option<Vec<F>> {
+        let class_weights = match self.class_weights {
+            Some(ClassWeights::Balanced) => {
+                let mut counts = vec![0usize; classes.len()];
+                for idx in class_indices {
+                    counts[*idx] += 1;
+                }
+
+                let n = F::from(class_indices.len()).unwrap();
+                let k = F::from(classes.len()).unwrap();
+                let class_weights = counts
+                    .into_iter()
+                    .map(|count| n / (k * F::from(count).unwrap()))
+                    .collect::<Vec<_>>();
+
+                Some(class_weights)
+            }
+            Some(ClassWeights::Custom(ref map)) => Some(
+                classes
+                    .iter()
+                    .map(|c| map.get(c).cloned().unwrap_or_else(F::one))
+                    .collect(),
+            ),
+            None => None,
+        };
+
+        if class_weights.is_none() && weights.is_none() {
+            return None;
+        }
+
+        let sample_weights = class_indices
+            .iter()
+            .enumerate()
+            .map(|(i, idx)| {
+                let class_weight = class_weights
+                    .as_ref()
+                    .map(|w| w[*idx])
+                    .unwrap_or_else(F::one);
+                let weight = weights
+                    .map(|w| F::from(w[i]).unwrap())
+                    .unwrap_or_else(F::one);
+
+                class_weight * weight
+            })
+            .collect();
+
+        Some(sample_weights)
+    }
+}
+
+/// Fitted multi-class SVM
+pub struct MultiClassSvm<'a, F: Float, L> {
+    classes: Vec<L>,
+    strategy: MultiClassStrategy,
+    /// binary classifiers with the indices of their positive and negative class, the negative
+    /// class is `None` for one-vs-rest
+    models: Vec<(usize, Option<usize>, Svm<'a, F, Pr>)>,
+}
+
+impl<'a, F: Float, L: 'a + Label, T: Labels<Elem = L>> Fit<'a, Kernel<'a, F>, T>
+    for MultiClassParams<F, L>
+{
+    type Object = MultiClassSvm<'a, F, L>;
+
+    fn fit(&self, dataset: &'a Dataset<Kernel<'a, F>, T>) -> Self::Object {
+        let targets = dataset.targets().as_slice();
+
+        // classes in order of their first appearance
+        let mut classes: Vec<L> = Vec::new();
+        for target in targets {
+            if !classes.contains(target) {
+                classes.push(target.clone());
+            }
+        }
+
+        let class_indices = targets
+            .iter()
+            .map(|x| classes.iter().position(|c| c == x).unwrap())
+            .collect::<Vec<_>>();
+
+        let weights = self.sample_weights(&classes, &class_indices, dataset.weights());
+        let weights = weights.as_deref();
+
+        let mut models = Vec::new();
+        match self.strategy {
+            MultiClassStrategy::OneVsOne => {
+                for i in 0..classes.len() {
+                    for j in i + 1..classes.len() {
+                        let indices = (0..targets.len())
+                            .filter(|k| class_indices[*k] == i || class_indices[*k] == j)
+                            .collect::<Vec<_>>();
+                        let positive = class_indices.iter().map(|x| *x == i).collect::<Vec<_>>();
+
+                        models.push((
+                            i,
+                            Some(j),
+                            self.params.fit_subset(
+                                &dataset.records,
+                                &positive,
+                                &indices,
+                                weights,
+                                None,
+                            ),
+                        ));
+                    }
+                }
+            }
+            MultiClassStrategy::OneVsRest => {
+                let indices = (0..targets.len()).collect::<Vec<_>>();
+                for i in 0..classes.len() {
+                    let positive = class_indices.iter().map(|x| *x == i).collect::<Vec<_>>();
+
+                    models.push((
+                        i,
+                        None,
+                        self.params.fit_subset(
+                            &dataset.records,
+                            &positive,
+                            &indices,
+                            weights,
+                            None,
+                        ),
+                    ));
+                }
+            }
+        }
+
+        MultiClassSvm {
+            classes,
+            strategy: self.strategy,
+            models,
+        }
+    }
+}
+
+impl<'a, F: Float, L: Label> MultiClassSvm<'a, F, L> {
+    /// Classes in the order of the columns of the decision values
+    pub fn classes(&self) -> &[L] {
+        &self.classes
+    }
+
+    pub fn strategy(&self) -> MultiClassStrategy {
+        self.strategy
+    }
+
+    /// Binary classifiers with the indices of their positive and negative class
+    ///
+    /// The negative class is `None` for the one-vs-rest strategy.
+    pub fn models(&self) -> impl Iterator<Item = (usize, Option<usize>, &Svm<'a, F, Pr>)> {
+        self.models.iter().map(|(i, j, model)| (*i, *j, model))
+    }
+
+    /// Decision values of each binary classifier, one column per classifier
+    pub fn binary_decision_values<D: Data<Elem = F>>(&self, x: &ArrayBase<D, Ix2>) -> Array2<F> {
+        let mut values = Array2::zeros((x.nrows(), self.models.len()));
+
+        for (batch, mut values) in x
+            .axis_chunks_iter(Axis(0), BATCH_SIZE)
+            .zip(values.axis_chunks_iter_mut(Axis(0), BATCH_SIZE))
+        {
+            // all classifiers share the training data, evaluate the kernel only once
+            let kernel = match self.models.first() {
+                Some((_, _, model)) if model.linear_decision.is_none() => {
+                    Some(model.kernel.cross(&batch))
+                }
+                _ => None,
+            };
+
+            for (mut column, (_, _, model)) in values.axis_iter_mut(Axis(1)).zip(&self.models) {
+                let decision = match (&model.linear_decision, &kernel) {
+                    (Some(w), _) => batch.dot(w),
+                    (None, Some(kernel)) => kernel.dot(&Array1::from(model.alpha.clone())),
+                    (None, None) => unreachable!(),
+                };
+
+                column.assign(&(decision - model.rho));
+            }
+        }
+
+        values
+    }
+
+    /// Decision values of each class, one column per class
+    ///
+    /// For one-vs-rest these are the decision values of the binary classifiers. For one-vs-one
+    /// each class gets the number of won votes, plus the sum of its decision values mapped
+    /// to `(-1/3, 1/3)`. This breaks ties between votes without changing the majority.
+    pub fn decision_function<D: Data<Elem = F>>(&self, x: &ArrayBase<D, Ix2>) -> Array2<F> {
+        let binary = self.binary_decision_values(x);
+
+        if self.strategy == MultiClassStrategy::OneVsRest {
+            return binary;
+        }
+
+        let mut votes = Array2::zeros((x.nrows(), self.classes.len()));
+        let mut confidences = Array2::<F>::zeros((x.nrows(), self.classes.len()));
+
+        for (column, (i, j, _)) in binary.axis_iter(Axis(1)).zip(&self.models) {
+            let j = j.unwrap();
+
+            for (k, val) in column.iter().enumerate() {
+                if *val > F::zero() {
+                    votes[(k, *i)] += F::one();
+                } else {
+                    votes[(k, j)] += F::one();
+                }
+
+                confidences[(k, *i)] += *val;
+                confidences[(k, j)] -= *val;
+            }
+        }
+
+        let three = F::from(3.0).unwrap();
+        votes + confidences.mapv(|x| x / (three * (x.abs() + F::one())))
+    }
+}
+
+impl<'a, F: Float, L: Label> MultiClassSvm<'a, F, L> {
+    /// Class probabilities, one column per class
+    ///
+    /// The probabilities of the pairwise classifiers are combined by pairwise coupling for
+    /// one-vs-one, and normalized to sum one for one-vs-rest. Returns `None` if the model was
+    /// trained without probability calibration.
+    pub fn predict_proba<D: Data<Elem = F>>(&self, x: &ArrayBase<D, Ix2>) -> Option<Array2<F>> {
+        let coefs = self
+            .models
+            .iter()
+            .map(|(_, _, model)| model.platt)
+            .collect::<Option<Vec<_>>>()?;
+
+        let binary = self.binary_decision_values(x);
+        let n_classes = self.classes.len();
+        let mut probabilities = Array2::zeros((x.nrows(), n_classes));
+
+        // avoid probabilities of exactly zero or one
+        let min_prob = F::from(1e-7).unwrap();
+
+        for (decisions, mut probs) in binary.outer_iter().zip(probabilities.outer_iter_mut()) {
+            match self.strategy {
+                MultiClassStrategy::OneVsOne => {
+                    let mut pairwise = Array2::zeros((n_classes, n_classes));
+                    for ((dec, coefs), (i, j, _)) in
+                        decisions.iter().zip(coefs.iter()).zip(&self.models)
+                    {
+                        let j = j.unwrap();
+                        let prob = sigmoid_predict(*dec, *coefs)
+                            .max(min_prob)
+                            .min(F::one() - min_prob);
+
+                        pairwise[(*i, j)] = prob;
+                        pairwise[(j, *i)] = F::one() - prob;
+                    }
+
+                    probs.assign(&pairwise_coupling(&pairwise));
+                }
+                MultiClassStrategy::OneVsRest => {
+                    for ((dec, coefs), (i, _, _)) in
+                        decisions.iter().zip(coefs.iter()).zip(&self.models)
+                    {
+                        probs[*i] = sigmoid_predict(*dec, *coefs).max(min_prob);
+                    }
+
+                    let sum = probs.sum();
+                    probs.mapv_inplace(|x| x / sum);
+                }
+            }
+        }
+
+        Some(probabilities)
+    }
+}
+
+/// Predict the class with the largest decision value
+impl<'a, F: Float, L: Label, D: Data<Elem = F>> Predict<ArrayBase<D, Ix2>, Vec<L>>
+    for MultiClassSvm<'a, F, L>
+{
+    fn predict(&self, x: ArrayBase<D, Ix2>) -> Vec<L> {
+        self.decision_function(&x)
+            .outer_iter()
+            .map(|row| {
+                let best = row
+                    .iter()
+                    .enumerate()
+                    .fold((0, F::neg_infinity()), |best, (i, val)| {
+                        if *val > best.1 {
+                            (i, *val)
+                        } else {
+                            best
+                        }
+                    })
+                    .0;
+
+                self.classes[best].clone()
+            })
+            .collect()
+    }
+}
+
+impl<'a, F: Float, L: Label, T: Targets> Predict<Dataset<Array2<F>, T>, Dataset<Array2<F>, Vec<L>>>
+    for MultiClassSvm<'a, F, L>
+{
+    fn predict(&self, data: Dataset<Array2<F>, T>) -> Dataset<Array2<F>, Vec<L>> {
+        let Dataset { records, .. } = data;
+        let predicted = self.predict(records.view());
+
+        Dataset::new(records, predicted)
+    }
+}
+
+impl<'a, 'b, F: Float, L: Label, T: Targets, D: Data<Elem = F>>
+    Predict<&'b Dataset<ArrayBase<D, Ix2>, T>, Dataset<ArrayView2<'b, F>, Vec<L>>>
+    for MultiClassSvm<'a, F, L>
+{
+    fn predict(
+        &self,
+        data: &'b Dataset<ArrayBase<D, Ix2>, T>,
+    ) -> Dataset<ArrayView2<'b, F>, Vec<L>> {
+        let predicted = self.predict(data.records.view());
+
+        Dataset::new(data.records.view(), predicted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ClassWeights, MultiClassStrategy};
+    use crate::Svm;
+    use linfa::dataset::Dataset;
+    use linfa::metrics::ToConfusionMatrix;
+    use linfa::traits::{Fit, Predict, Transformer};
+    use linfa_kernel::{Kernel, KernelMethod};
+    use ndarray::{Array, Array2};
+    use ndarray_rand::rand::SeedableRng;
+    use ndarray_rand::rand_distr::Uniform;
+    use ndarray_rand::RandomExt;
+    use rand_isaac::Isaac64Rng;
+    use std::collections::HashMap;
+
+    /// Three clusters with 20 observations each, labeled by name
+    fn clusters() -> (Array2<f64>, Vec<String>) {
+        let mut rng = Isaac64Rng::seed_from_u64(42);
+        let centers = [(0.0, 0.0), (4.0, 0.0), (2.0, 4.0)];
+        let names = ["left", "right", "top"];
+
+        let mut records = Array::random_using((60, 2), Uniform::new(-1., 1.), &mut rng);
+        let mut targets = Vec::new();
+        for (i, mut row) in records.outer_iter_mut().enumerate() {
+            row[0] += centers[i % 3].0;
+            row[1] += centers[i % 3].1;
+            targets.push(names[i % 3].to_string());
+        }
+
+        (records, targets)
+    }
+
+    #[test]
+    fn test_multi_class() {
+        let (records, targets) = clusters();
+        let dataset = Dataset::new(records.clone(), targets);
+
+        for method in [KernelMethod::Gaussian(2.0), KernelMethod::Linear] {
+            let kernel = Kernel::params().method(method).transform(&dataset);
+
+            for strategy in [MultiClassStrategy::OneVsOne, MultiClassStrategy::OneVsRest] {
+                let model = Svm::params()
+                    .pos_neg_weights(10.0, 10.0)
+                    .multi_class()
+                    .strategy(strategy)
+                    .fit(&kernel);
+
+                assert_eq!(model.classes(), &["left", "right", "top"]);
+                let n_models = model.models().count();
+                assert_eq!(n_models, 3);
+
+                let decisions = model.decision_function(&records);
+                assert_eq!(decisions.dim(), (60, 3));
+
+                let pred = model.predict(&dataset);
+                let cm = pred.confusion_matrix(&dataset);
+                assert_eq!(cm.accuracy(), 1.0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_one_vs_one_votes() {
+        let (records, targets) = clusters();
+        let dataset = Dataset::new(records.clone(), targets);
+        let kernel = Kernel::params()
+            .method(KernelMethod::Gaussian(2.0))
+            .transform(&dataset);
+
+        let model = Svm::params().nu_weight(0.1).multi_class().fit(&kernel);
+
+        // the winner of two pairwise comparisons has between two and three votes
+        let decisions = model.decision_function(&records);
+        for row in decisions.outer_iter() {
+            let max = row.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+            assert!(max > 5.0 / 3.0 && max < 7.0 / 3.0);
+            assert!((row.sum() - 3.0).abs() < 1.0);
+        }
+
+        // the binary classifiers of each pair only use observations of their classes
+        for (i, j, svm) in model.models() {
+            let j = j.unwrap();
+            for (k, alpha) in svm.alpha.iter().enumerate() {
+                if k % 3 != i && k % 3 != j {
+                    assert_eq!(*alpha, 0.0);
+                }
+            }
+        }
+
+        let pred = model.predict(records.view());
+        let accuracy = pred
+            .iter()
+            .zip(dataset.targets())
+            .filter(|(a, b)| a == b)
+            .count();
+        assert_eq!(accuracy, 60);
+
+        // with integer labels
+        let dataset = Dataset::new(
+            records.clone(),
+            (0..60).map(|x| x % 3).collect::<Vec<usize>>(),
+        );
+        let kernel = Kernel::params()
+            .method(KernelMethod::Gaussian(2.0))
+            .transform(&dataset);
+        let model = Svm::params()
+            .pos_neg_weights(1.0, 1.0)
+            .multi_class()
+            .fit(&kernel);
+        let pred = model.predict(records.view());
+        assert_eq!(pred, dataset.targets().to_vec());
+    }
+
+    #[test]
+    fn test_predict_proba() {
+        let (records, targets) = clusters();
+        let dataset = Dataset::new(records.clone(), targets);
+        let kernel = Kernel::params()
+            .method(KernelMethod::Gaussian(2.0))
+            .transform(&dataset);
+
+        // without calibration no probabilities are available
+        let model = Svm::params()
+            .pos_neg_weights(10.0, 10.0)
+            .multi_class()
+            .fit(&kernel);
+        assert!(model.predict_proba(&records).is_none());
+
+        for strategy in [MultiClassStrategy::OneVsOne, MultiClassStrategy::OneVsRest] {
+            let model = Svm::params()
+                .pos_neg_weights(10.0, 10.0)
+                .probability(true)
+                .multi_class()
+                .strategy(strategy)
+                .fit(&kernel);
+
+            let probabilities = model.predict_proba(&records).unwrap();
+            assert_eq!(probabilities.dim(), (60, 3));
+
+            for (i, row) in probabilities.outer_iter().enumerate() {
+                assert!((row.sum() - 1.0).abs() < 1e-6);
+                assert!(row.iter().all(|x| *x >= 0.0 && *x <= 1.0));
+
+                // the most probable class is the true class
+                let argmax = (0..3).max_by(|a, b| row[*a].partial_cmp(&row[*b]).unwrap());
+                assert_eq!(argmax, Some(i % 3));
+            }
+        }
+    }
+
+    #[test]
+    fn test_class_weights() {
+        let (records, targets) = clusters();
+        let classes = ["left", "right", "top"]
+            .iter()
+            .map(|x| x.to_string())
+            .collect::<Vec<_>>();
+
+        // drop most observations of the top cluster
+        let keep = (0..60).filter(|x| x % 3 != 2 || *x < 9).collect::<Vec<_>>();
+        let records = records.select(ndarray::Axis(0), &keep);
+        let targets = keep.iter().map(|x| targets[*x].clone()).collect::<Vec<_>>();
+        let dataset = Dataset::new(records, targets);
+
+        let params = Svm::<f64, _>::params()
+            .pos_neg_weights(1.0, 1.0)
+            .balanced(true)
+            .multi_class::<String>();
+
+        // 20 left, 20 right and 3 top observations
+        let class_indices = dataset
+            .targets()
+            .iter()
+            .map(|x| classes.iter().position(|c| c == x).unwrap())
+            .collect::<Vec<_>>();
+        let weights = params
+            .sample_weights(&classes, &class_indices, None)
+            .unwrap();
+        for (w, idx) in weights.iter().zip(class_indices.iter()) {
+            let count = [20.0, 20.0, 3.0][*idx];
+            assert!((w - 43.0 / (3.0 * count)).abs() < 1e-10);
+        }
+
+        // custom weights are combined with the weights of the dataset
+        let mut map = HashMap::new();
+        map.insert("top".to_string(), 4.0);
+        let params = params.class_weights(ClassWeights::Custom(map));
+        let sample = vec![0.5; class_indices.len()];
+        let weights = params
+            .sample_weights(&classes, &class_indices, Some(&sample))
+            .unwrap();
+        for (w, idx) in weights.iter().zip(class_indices.iter()) {
+            assert_eq!(*w, if *idx == 2 { 2.0 } else { 0.5 });
+        }
+
+        let kernel = Kernel::params()
+            .method(KernelMethod::Gaussian(2.0))
+            .transform(&dataset);
+
+        for strategy in [MultiClassStrategy::OneVsOne, MultiClassStrategy::OneVsRest] {
+            let model = Svm::params()
+                .pos_neg_weights(1.0, 1.0)
+                .multi_class()
+                .strategy(strategy)
+                .class_weights(ClassWeights::Balanced)
+                .fit(&kernel);
+
+            let pred = model.predict(&dataset);
+            let cm = pred.confusion_matrix(&dataset);
+            assert_eq!(cm.accuracy(), 1.0);
+        }
+    }
+}
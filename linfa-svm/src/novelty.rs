@@ -0,0 +1,273 @@
+//! Novelty detection with one-class SVMs
+//!
+//! A one-class SVM learns the support of the training distribution. Observations with a
+//! negative decision score lie outside of this support and are labeled as outliers. The score
+//! is shifted by a threshold, which is either given directly or selected such that a fraction of
+//! the training observations is labeled as outliers.
+use linfa::dataset::{Dataset, Pr, Targets};
+use linfa::traits::{Fit, Predict};
+use ndarray::{Array1, Array2, ArrayBase, ArrayView2, Data, Ix2};
+
+use super::classification::fit_one_class;
+use super::permutable_kernel::Kernel;
+use super::{Float, Svm, SvmParams};
+
+/// Parameters of a novelty detector
+///
+/// The Nu value of the underlying [`SvmParams`](struct.SvmParams.html) is an upper bound on
+/// the fraction of training errors and a lower bound on the fraction of support vectors.
+pub struct NoveltyParams<F: Float> {
+    params: SvmParams<F, Pr>,
+    threshold: F,
+    contamination: Option<F>,
+}
+
+impl<F: Float> SvmParams<F, Pr> {
+    /// Detect novel observations with a one-class SVM
+    ///
+    /// Requires a Nu value, for example set with [nu_weight](struct.SvmParams.html#method.nu_weight).
+    pub fn novelty(self) -> NoveltyParams<F> {
+        NoveltyParams {
+            params: self,
+            threshold: F::zero(),
+            contamination: None,
+        }
+    }
+}
+
+impl<F: Float> NoveltyParams<F> {
+    /// Decision value below which an observation is labeled as outlier
+    ///
+    /// Defaults to zero, the boundary learned by the one-class SVM.
+    pub fn threshold(mut self, threshold: F) -> Self {
+        self.threshold = threshold;
+        self.contamination = None;
+
+        self
+    }
+
+    /// Select the threshold such that this fraction of training observations are outliers
+    ///
+    /// Observations with a NaN score are ignored. Without any valid score the threshold is left
+    /// at its previous value.
+    ///
+    /// # Panics
+    ///
+    /// If the contamination is not in the interval [0, 1)
+    pub fn contamination(mut self, contamination: F) -> Self {
+        assert!(
+            contamination >= F::zero() && contamination < F::one(),
+            "contamination should be in the interval [0, 1)"
+        );
+        self.contamination = Some(contamination);
+
+        self
+    }
+}
+
+impl<'a, F: Float, T: Targets> Fit<'a, Kernel<'a, F>, T> for NoveltyParams<F> {
+    type Object = NoveltyDetector<'a, F>;
+
+    fn fit(&self, dataset: &'a Dataset<Kernel<'a, F>, T>) -> Self::Object {
+        let svm = match self.params.nu {
+            Some((nu, _)) => fit_one_class(self.params.solver_params.clone(), &dataset.records, nu),
+            None => panic!("One class needs Nu value"),
+        };
+
+        let threshold = match self.contamination {
+            Some(contamination) => {
+                let mut scores = svm
+                    .decision_function(&dataset.records.dataset)
+                    .iter()
+                    .copied()
+                    .filter(|x| !x.is_nan())
+                    .collect::<Vec<_>>();
+                // scores without NaN are totally ordered
+                scores.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+                // the observations with a score below the threshold are outliers
+                match scores.len() {
+                    0 => self.threshold,
+                    len => {
+                        let n = F::from(len).unwrap();
+                        let idx = (contamination * n).round().to_usize().unwrap();
+                        scores[usize::min(idx, len - 1)]
+                    }
+                }
+            }
+            None => self.threshold,
+        };
+
+        NoveltyDetector { svm, threshold }
+    }
+}
+
+/// Fitted novelty detector
+///
+/// Predicts `true` for inliers and `false` for outliers.
+pub struct NoveltyDetector<'a, F: Float> {
+    svm: Svm<'a, F, Pr>,
+    threshold: F,
+}
+
+impl<'a, F: Float> NoveltyDetector<'a, F> {
+    /// Underlying one-class SVM
+    pub fn svm(&self) -> &Svm<'a, F, Pr> {
+        &self.svm
+    }
+
+    /// Decision value below which an observation is labeled as outlier
+    pub fn threshold(&self) -> F {
+        self.threshold
+    }
+
+    /// Replace the threshold without fitting again
+    pub fn with_threshold(mut self, threshold: F) -> Self {
+        self.threshold = threshold;
+
+        self
+    }
+
+    /// Signed decision score of each observation
+    ///
+    /// The score is positive for inliers and negative for outliers, its magnitude grows with
+    /// the distance to the threshold.
+    pub fn decision_function<D: Data<Elem = F>>(&self, data: &ArrayBase<D, Ix2>) -> Array1<F> {
+        let mut scores = self.svm.decision_function(data);
+        scores -= self.threshold;
+
+        scores
+    }
+}
+
+/// Label each observation as inlier (`true`) or outlier (`false`)
+impl<'a, F: Float, D: Data<Elem = F>> Predict<ArrayBase<D, Ix2>, Vec<bool>>
+    for NoveltyDetector<'a, F>
+{
+    fn predict(&self, data: ArrayBase<D, Ix2>) -> Vec<bool> {
+        self.decision_function(&data)
+            .iter()
+            .map(|score| *score >= F::zero())
+            .collect()
+    }
+}
+
+impl<'a, F: Float, T: Targets> Predict<Dataset<Array2<F>, T>, Dataset<Array2<F>, Vec<bool>>>
+    for NoveltyDetector<'a, F>
+{
+    fn predict(&self, data: Dataset<Array2<F>, T>) -> Dataset<Array2<F>, Vec<bool>> {
+        let Dataset { records, .. } = data;
+        let predicted = self.predict(records.view());
+
+        Dataset::new(records, predicted)
+    }
+}
+
+impl<'a, 'b, F: Float, T: Targets, D: Data<Elem = F>>
+    Predict<&'b Dataset<ArrayBase<D, Ix2>, T>, Dataset<ArrayView2<'b, F>, Vec<bool>>>
+    for NoveltyDetector<'a, F>
+{
+    fn predict(
+        &self,
+        data: &'b Dataset<ArrayBase<D, Ix2>, T>,
+    ) -> Dataset<ArrayView2<'b, F>, Vec<bool>> {
+        let predicted = self.predict(data.records.view());
+
+        Dataset::new(data.records.view(), predicted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::classification::tests::generate_convoluted_rings;
+    use crate::Svm;
+    use linfa::dataset::Dataset;
+    use linfa::traits::{Fit, Predict, Transformer};
+    use linfa_kernel::{Kernel, KernelMethod};
+    use ndarray::{s, Array};
+    use ndarray_rand::rand_distr::Uniform;
+    use ndarray_rand::RandomExt;
+
+    #[test]
+    fn test_novelty_convoluted_rings() {
+        // train on the inner ring only
+        let rings = generate_convoluted_rings(100);
+        let inner = rings.slice(s![..100, ..]).to_owned();
+        let outer = rings.slice(s![100.., ..]).to_owned();
+
+        let kernel = Kernel::params()
+            .method(KernelMethod::Gaussian(1.0))
+            .transform(inner.view());
+        let dataset = Dataset::new(kernel, ());
+
+        let detector = Svm::params().nu_weight(0.1).novelty().fit(&dataset);
+
+        // the outer ring is novel
+        let outliers = detector
+            .predict(outer.view())
+            .into_iter()
+            .filter(|x| !x)
+            .count();
+        assert!(outliers > 95);
+
+        // scores and labels agree and most of the inner ring are inliers
+        let scores = detector.decision_function(&inner);
+        let inner = Dataset::new(inner.clone(), ());
+        let labels = detector.predict(&inner);
+        for (score, label) in scores.iter().zip(labels.targets()) {
+            assert_eq!(*score >= 0.0, *label);
+        }
+        assert!(labels.targets().iter().filter(|x| **x).count() > 80);
+
+        // shifting the threshold below all scores accepts everything
+        let min = scores.iter().cloned().fold(f64::INFINITY, f64::min);
+        let threshold = detector.threshold() + min - 1.0;
+        let detector = detector.with_threshold(threshold);
+        assert!(detector
+            .predict(inner.records().view())
+            .into_iter()
+            .all(|x| x));
+    }
+
+    #[test]
+    fn test_novelty_contamination() {
+        let records = Array::random((200, 2), Uniform::new(-1f64, 1.));
+        let kernel = Kernel::params()
+            .method(KernelMethod::Gaussian(1.0))
+            .transform(records.view());
+        let dataset = Dataset::new(kernel, ());
+
+        for &contamination in &[0.05, 0.1, 0.25] {
+            let detector = Svm::params()
+                .nu_weight(0.5)
+                .novelty()
+                .contamination(contamination)
+                .fit(&dataset);
+
+            let outliers = detector
+                .predict(records.view())
+                .into_iter()
+                .filter(|x| !x)
+                .count();
+            let expected = (contamination * 200.0) as usize;
+            assert!(outliers <= expected && outliers + 2 >= expected);
+        }
+    }
+
+    #[test]
+    fn test_novelty_contamination_empty() {
+        let records = Array::<f64, _>::zeros((0, 2));
+        let kernel = Kernel::params()
+            .method(KernelMethod::Gaussian(1.0))
+            .transform(records.view());
+        let dataset = Dataset::new(kernel, ());
+
+        let detector = Svm::params()
+            .nu_weight(0.5)
+            .novelty()
+            .contamination(0.1)
+            .fit(&dataset);
+
+        assert_eq!(detector.threshold(), 0.0);
+    }
+}
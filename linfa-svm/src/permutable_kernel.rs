@@ -23,9 +23,15 @@ pub struct PermutableKernel<'a, A: Float> {
 }
 
 impl<'a, A: Float> PermutableKernel<'a, A> {
-    pub fn new(kernel: &'a Kernel<'a, A>, targets: Vec<bool>) -> PermutableKernel<'a, A> {
+    /// Create a kernel matrix restricted to a subset of the observations
+    ///
+    /// The targets are indexed by the position in the full kernel matrix.
+    pub fn with_indices(
+        kernel: &'a Kernel<'a, A>,
+        targets: Vec<bool>,
+        kernel_indices: Vec<usize>,
+    ) -> PermutableKernel<'a, A> {
         let kernel_diag = kernel.diagonal();
-        let kernel_indices = (0..kernel.size()).collect::<Vec<_>>();
 
         PermutableKernel {
             kernel,
@@ -216,7 +222,7 @@ mod tests {
             linear: false,
         };
 
-        let mut kernel = PermutableKernel::new(&dist, targets);
+        let mut kernel = PermutableKernel::with_indices(&dist, targets, vec![0, 1, 2]);
 
         assert_eq!(kernel.distances(0, 3), &[1.0, 0.3, 0.1]);
         assert_eq!(kernel.distances(1, 3), &[0.3, 1.0, 0.5]);
@@ -0,0 +1,268 @@
+//! Probability estimates for SVM classification
+//!
+//! Decision values are mapped to probabilities with a sigmoid `1 / (1 + exp(A f + B))`, fitted
+//! on decision values obtained by cross-validation (Platt scaling). The probabilities of
+//! pairwise classifiers are combined into multi-class probabilities by pairwise coupling.
+//!
+//! Both follow the formulation of libsvm, see
+//!  * Lin, Lin, Weng: "A note on Platt's probabilistic outputs for support vector machines"
+//!  * Wu, Lin, Weng: "Probability estimates for multi-class classification by pairwise coupling"
+use linfa::dataset::Pr;
+use ndarray::{Array1, Array2};
+
+use super::permutable_kernel::Kernel;
+use super::{Float, SvmParams};
+
+/// Number of folds used to obtain unbiased decision values
+const PLATT_FOLDS: usize = 5;
+
+/// Fit the sigmoid on cross-validated decision values of the observations in `indices`
+pub(crate) fn cross_validated_platt<'a, F: Float>(
+    params: &SvmParams<F, Pr>,
+    kernel: &'a Kernel<'a, F>,
+    targets: &[bool],
+    indices: &[usize],
+    weights: Option<&[F]>,
+) -> (F, F) {
+    let n_folds = usize::min(PLATT_FOLDS, indices.len());
+    let mut decisions = vec![F::zero(); indices.len()];
+
+    for fold in 0..n_folds {
+        let (valid, train): (Vec<_>, Vec<_>) =
+            (0..indices.len()).partition(|i| i % n_folds == fold);
+        let train = train.into_iter().map(|i| indices[i]).collect::<Vec<_>>();
+
+        let n_positive = train.iter().filter(|i| targets[**i]).count();
+
+        if n_positive == 0 || n_positive == train.len() {
+            // only a single class is left for training, predict it with full confidence
+            let val = if n_positive > 0 { F::one() } else { -F::one() };
+            for i in valid {
+                decisions[i] = val;
+            }
+
+            continue;
+        }
+
+        let model = params.solve_subset(kernel, targets, &train, weights, None);
+        for i in valid {
+            let column = kernel.column(indices[i]);
+
+            decisions[i] = train
+                .iter()
+                .map(|j| model.alpha[*j] * column[*j])
+                .sum::<F>()
+                - model.rho;
+        }
+    }
+
+    let labels = indices.iter().map(|i| targets[*i]).collect::<Vec<_>>();
+
+    fit_sigmoid(&decisions, &labels)
+}
+
+/// Evaluate `A f + B` of the sigmoid and return `(p, 1 - p)` without overflow
+fn sigmoid_parts<F: Float>(f_ab: F) -> (F, F) {
+    if f_ab >= F::zero() {
+        let e = (-f_ab).exp();
+        (e / (F::one() + e), F::one() / (F::one() + e))
+    } else {
+        let e = f_ab.exp();
+        (F::one() / (F::one() + e), e / (F::one() + e))
+    }
+}
+
+/// Probability of the positive class for a decision value
+pub(crate) fn sigmoid_predict<F: Float>(decision: F, (a, b): (F, F)) -> F {
+    sigmoid_parts(decision * a + b).0
+}
+
+/// Fit the coefficients `(A, B)` of the sigmoid with a Newton method and backtracking line search
+pub(crate) fn fit_sigmoid<F: Float>(decisions: &[F], labels: &[bool]) -> (F, F) {
+    let max_iter = 100;
+    let min_step = F::from(1e-10).unwrap();
+    let sigma = F::from(1e-12).unwrap();
+    let eps = F::from(1e-5).unwrap();
+
+    let prior1 = F::from(labels.iter().filter(|x| **x).count()).unwrap();
+    let prior0 = F::from(labels.len()).unwrap() - prior1;
+
+    // regularized targets avoid overfitting to separable decision values
+    let hi_target = (prior1 + F::one()) / (prior1 + F::from(2.0).unwrap());
+    let lo_target = F::one() / (prior0 + F::from(2.0).unwrap());
+    let t = labels
+        .iter()
+        .map(|x| if *x { hi_target } else { lo_target })
+        .collect::<Vec<_>>();
+
+    let objective = |a: F, b: F| {
+        decisions
+            .iter()
+            .zip(t.iter())
+            .map(|(dec, t)| {
+                let f_ab = *dec * a + b;
+                if f_ab >= F::zero() {
+                    *t * f_ab + (F::one() + (-f_ab).exp()).ln()
+                } else {
+                    (*t - F::one()) * f_ab + (F::one() + f_ab.exp()).ln()
+                }
+            })
+            .sum::<F>()
+    };
+
+    let mut a = F::zero();
+    let mut b = ((prior0 + F::one()) / (prior1 + F::one())).ln();
+    let mut fval = objective(a, b);
+
+    for _ in 0..max_iter {
+        // gradient and Hessian
+        let (mut h11, mut h22, mut h21) = (sigma, sigma, F::zero());
+        let (mut g1, mut g2) = (F::zero(), F::zero());
+
+        for (dec, t) in decisions.iter().zip(t.iter()) {
+            let (p, q) = sigmoid_parts(*dec * a + b);
+            let d2 = p * q;
+            h11 += *dec * *dec * d2;
+            h22 += d2;
+            h21 += *dec * d2;
+
+            let d1 = *t - p;
+            g1 += *dec * d1;
+            g2 += d1;
+        }
+
+        if g1.abs() < eps && g2.abs() < eps {
+            break;
+        }
+
+        // Newton direction
+        let det = h11 * h22 - h21 * h21;
+        let da = -(h22 * g1 - h21 * g2) / det;
+        let db = -(-h21 * g1 + h11 * g2) / det;
+        let gd = g1 * da + g2 * db;
+
+        // backtracking line search
+        let mut step = F::one();
+        while step >= min_step {
+            let (new_a, new_b) = (a + step * da, b + step * db);
+            let new_f = objective(new_a, new_b);
+
+            if new_f < fval + F::from(1e-4).unwrap() * step * gd {
+                a = new_a;
+                b = new_b;
+                fval = new_f;
+                break;
+            }
+
+            step /= F::from(2.0).unwrap();
+        }
+
+        if step < min_step {
+            break;
+        }
+    }
+
+    (a, b)
+}
+
+/// Combine pairwise probabilities into class probabilities
+///
+/// The entry `(i, j)` of `pairwise` is the probability of class `i`, given that the observation
+/// belongs either to class `i` or `j`.
+pub(crate) fn pairwise_coupling<F: Float>(pairwise: &Array2<F>) -> Array1<F> {
+    let k = pairwise.nrows();
+    let kf = F::from(k).unwrap();
+
+    let mut q = Array2::zeros((k, k));
+    for t in 0..k {
+        for j in 0..k {
+            if j != t {
+                q[(t, t)] += pairwise[(j, t)] * pairwise[(j, t)];
+                q[(t, j)] = -pairwise[(j, t)] * pairwise[(t, j)];
+            }
+        }
+    }
+
+    let mut p = Array1::from_elem(k, F::one() / kf);
+    let max_iter = usize::max(100, k);
+    let eps = F::from(0.005).unwrap() / kf;
+
+    for _ in 0..max_iter {
+        let mut qp = q.dot(&p);
+        let mut pqp = p.dot(&qp);
+
+        let max_error = qp.iter().map(|x| (*x - pqp).abs()).fold(F::zero(), F::max);
+        if max_error < eps {
+            break;
+        }
+
+        for t in 0..k {
+            let diff = (-qp[t] + pqp) / q[(t, t)];
+            p[t] += diff;
+
+            let scale = F::one() + diff;
+            pqp =
+                (pqp + diff * (diff * q[(t, t)] + F::from(2.0).unwrap() * qp[t])) / (scale * scale);
+            for j in 0..k {
+                qp[j] = (qp[j] + diff * q[(t, j)]) / scale;
+                p[j] /= scale;
+            }
+        }
+    }
+
+    p
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fit_sigmoid, pairwise_coupling, sigmoid_predict};
+    use ndarray::{array, Array2};
+
+    #[test]
+    fn test_fit_sigmoid() {
+        // overlapping decision values, positive labels are more likely for larger values
+        let decisions = (0..100)
+            .map(|x| (x as f64 - 50.0) / 10.0)
+            .collect::<Vec<_>>();
+        let labels = (0..100)
+            .map(|x| (x * 37) % 100 < x + (x % 10))
+            .collect::<Vec<_>>();
+
+        let coefs = fit_sigmoid(&decisions, &labels);
+
+        // the sigmoid is increasing in the decision value
+        assert!(coefs.0 < 0.0);
+        assert!(sigmoid_predict(-5.0, coefs) < sigmoid_predict(0.0, coefs));
+        assert!(sigmoid_predict(0.0, coefs) < sigmoid_predict(5.0, coefs));
+
+        // symmetric labels result in a probability of 0.5 at zero
+        let decisions = [-2.0f64, -1.0, 1.0, 2.0];
+        let labels = [false, true, false, true];
+        let coefs = fit_sigmoid(&decisions, &labels);
+        assert!((sigmoid_predict(0.0, coefs) - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_pairwise_coupling() {
+        // consistent pairwise probabilities of the distribution (0.5, 0.3, 0.2)
+        let p = [0.5f64, 0.3, 0.2];
+        let mut pairwise = Array2::zeros((3, 3));
+        for i in 0..3 {
+            for j in 0..3 {
+                if i != j {
+                    pairwise[(i, j)] = p[i] / (p[i] + p[j]);
+                }
+            }
+        }
+
+        let coupled = pairwise_coupling(&pairwise);
+        assert!((coupled.sum() - 1.0).abs() < 1e-6);
+        for (a, b) in coupled.iter().zip(p.iter()) {
+            assert!((a - b).abs() < 1e-3);
+        }
+
+        // two classes reproduce the pairwise probability
+        let coupled = pairwise_coupling(&array![[0.0f64, 0.8], [0.2, 0.0]]);
+        assert!((coupled[0] - 0.8).abs() < 1e-3);
+    }
+}
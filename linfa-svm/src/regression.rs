@@ -1,11 +1,11 @@
 //! Support Vector Regression
-use linfa::{dataset::Dataset, traits::Fit, traits::Predict};
-use ndarray::{ArrayBase, Data, Ix2};
+use linfa::{dataset::Dataset, dataset::Targets, traits::Fit, traits::Predict};
+use ndarray::{Array2, ArrayBase, ArrayView2, Data, Ix2};
 
 use super::permutable_kernel::{Kernel, PermutableKernelRegression};
-use super::solver_smo::SolverState;
+use super::solver_smo::{scale_to_bounds, SolverState};
 use super::SolverParams;
-use super::{Float, Svm, SvmParams};
+use super::{Float, Svm, SvmModel, SvmParams};
 
 /// Support Vector Regression with epsilon tolerance
 ///
@@ -21,10 +21,30 @@ use super::{Float, Svm, SvmParams};
 pub fn fit_epsilon<'a, A: Float>(
     params: SolverParams<A>,
     kernel: &'a Kernel<'a, A>,
-    target: &'a [A],
+    target: &[A],
     c: A,
     p: A,
 ) -> Svm<'a, A, A> {
+    solve_epsilon(params, kernel, target, &vec![c; target.len()], p, None)
+}
+
+/// Support Vector Regression with epsilon tolerance, with a C value for each observation in
+/// `bounds` and starting from the coefficients `warm_start` of a previous model
+fn solve_epsilon<'a, A: Float>(
+    params: SolverParams<A>,
+    kernel: &'a Kernel<'a, A>,
+    target: &[A],
+    bounds: &[A],
+    p: A,
+    warm_start: Option<&[A]>,
+) -> Svm<'a, A, A> {
+    // both coefficients of an observation share its bound
+    let bounds = bounds
+        .iter()
+        .chain(bounds.iter())
+        .cloned()
+        .collect::<Vec<_>>();
+
     let mut linear_term = vec![A::zero(); 2 * target.len()];
     let mut targets = vec![true; 2 * target.len()];
 
@@ -36,13 +56,32 @@ pub fn fit_epsilon<'a, A: Float>(
         targets[i + target.len()] = false;
     }
 
+    // split the coefficients into the positive and negative part
+    let init_alpha = match warm_start {
+        Some(beta) => {
+            assert_eq!(
+                beta.len(),
+                target.len(),
+                "warm start coefficients do not match the number of observations"
+            );
+
+            let alpha = beta
+                .iter()
+                .map(|x| x.max(A::zero()))
+                .chain(beta.iter().map(|x| (-*x).max(A::zero())))
+                .collect();
+            scale_to_bounds(alpha, &bounds)
+        }
+        None => vec![A::zero(); 2 * target.len()],
+    };
+
     let kernel = PermutableKernelRegression::new(kernel);
     let solver = SolverState::new(
-        vec![A::zero(); 2 * target.len()],
+        init_alpha,
         linear_term,
         targets.to_vec(),
         kernel,
-        vec![c; 2 * target.len()],
+        bounds,
         params,
         false,
     );
@@ -52,10 +91,12 @@ pub fn fit_epsilon<'a, A: Float>(
     for i in 0..target.len() {
         let tmp = res.alpha[i + target.len()];
         res.alpha[i] -= tmp;
+        res.bounded[i] |= res.bounded[i + target.len()];
     }
     res.alpha.truncate(target.len());
+    res.bounded.truncate(target.len());
 
-    res.with_phantom()
+    res.with_linear_decision().with_phantom()
 }
 
 /// Support Vector Regression with nu parameter
@@ -72,7 +113,7 @@ pub fn fit_epsilon<'a, A: Float>(
 pub fn fit_nu<'a, A: Float>(
     params: SolverParams<A>,
     kernel: &'a Kernel<'a, A>,
-    target: &'a [A],
+    target: &[A],
     c: A,
     nu: A,
 ) -> Svm<'a, A, A> {
@@ -109,76 +150,165 @@ pub fn fit_nu<'a, A: Float>(
     for i in 0..target.len() {
         let tmp = res.alpha[i + target.len()];
         res.alpha[i] -= tmp;
+        res.bounded[i] |= res.bounded[i + target.len()];
     }
     res.alpha.truncate(target.len());
+    res.bounded.truncate(target.len());
 
-    res.with_phantom()
+    res.with_linear_decision().with_phantom()
 }
 
-impl<'a, F: Float> Fit<'a, Kernel<'a, F>, Vec<F>> for SvmParams<F, F> {
-    type Object = Svm<'a, F, F>;
+impl<F: Float> SvmParams<F, F> {
+    /// C value of each observation, scaled by the weight of the observation
+    pub(crate) fn regression_bounds(&self, n: usize, weights: Option<&[f32]>) -> Option<Vec<F>> {
+        let (c, _) = self.c?;
 
-    fn fit(&self, dataset: &'a Dataset<Kernel<'a, F>, Vec<F>>) -> Self::Object {
-        match (self.c, self.nu) {
-            (Some((c, eps)), _) => fit_epsilon(
-                self.solver_params.clone(),
-                &dataset.records,
-                dataset.targets(),
-                c,
-                eps,
-            ),
-            (None, Some((nu, eps))) => fit_nu(
+        let bounds = match weights {
+            Some(weights) => weights.iter().map(|w| c * F::from(*w).unwrap()).collect(),
+            None => vec![c; n],
+        };
+
+        Some(bounds)
+    }
+
+    /// Fit a regression, the weights of the observations scale their C value and are ignored
+    /// by the Nu formulation
+    fn fit_regression<'a>(
+        &self,
+        kernel: &'a Kernel<'a, F>,
+        targets: &[F],
+        weights: Option<&[f32]>,
+    ) -> Svm<'a, F, F> {
+        let bounds = self.regression_bounds(targets.len(), weights);
+
+        if let Some(ref bounds) = bounds {
+            if let Some(model) = self.solve_linear(kernel, targets, bounds) {
+                return model;
+            }
+        }
+
+        match (bounds, self.nu) {
+            (Some(bounds), _) => solve_epsilon(
                 self.solver_params.clone(),
-                &dataset.records,
-                dataset.targets(),
-                nu,
-                eps,
+                kernel,
+                targets,
+                &bounds,
+                self.c.unwrap().1,
+                self.warm_start.as_deref(),
             ),
+            (None, Some((nu, eps))) => fit_nu(self.solver_params.clone(), kernel, targets, nu, eps),
             _ => panic!("Set either C value or Nu value"),
         }
     }
 }
 
+impl<'a, F: Float> Fit<'a, Kernel<'a, F>, Vec<F>> for SvmParams<F, F> {
+    type Object = Svm<'a, F, F>;
+
+    fn fit(&self, dataset: &'a Dataset<Kernel<'a, F>, Vec<F>>) -> Self::Object {
+        self.fit_regression(&dataset.records, dataset.targets(), dataset.weights())
+    }
+}
+
 impl<'a, F: Float> Fit<'a, Kernel<'a, F>, &Vec<F>> for SvmParams<F, F> {
     type Object = Svm<'a, F, F>;
 
     fn fit(&self, dataset: &'a Dataset<Kernel<'a, F>, &Vec<F>>) -> Self::Object {
-        match (self.c, self.nu) {
-            (Some((c, eps)), _) => fit_epsilon(
-                self.solver_params.clone(),
-                &dataset.records,
-                dataset.targets(),
-                c,
-                eps,
-            ),
-            (None, Some((nu, eps))) => fit_nu(
-                self.solver_params.clone(),
-                &dataset.records,
-                dataset.targets(),
-                nu,
-                eps,
-            ),
-            _ => panic!("Set either C value or Nu value"),
-        }
+        self.fit_regression(&dataset.records, dataset.targets(), dataset.weights())
     }
 }
 
-/// Predict a probability with a set of observations
-impl<'a, D: Data<Elem = f64>> Predict<ArrayBase<D, Ix2>, Vec<f64>> for Svm<'a, f64, f64> {
-    fn predict(&self, data: ArrayBase<D, Ix2>) -> Vec<f64> {
-        data.outer_iter()
-            .map(|data| {
-                let val = match self.linear_decision {
-                    Some(ref x) => x.dot(&data) - self.rho,
-                    None => self.kernel.weighted_sum(&self.alpha, data.view()) - self.rho,
-                };
-
-                // this is safe because `F` is only implemented for `f32` and `f64`
-                val
-            })
-            .collect()
+impl<F: Float> SvmParams<F, F> {
+    /// Fit one regression for each column of `targets`
+    ///
+    /// All regressions share the kernel matrix and the parameters.
+    pub fn fit_multi_target<'a, D: Data<Elem = F>>(
+        &self,
+        kernel: &'a Kernel<'a, F>,
+        targets: &ArrayBase<D, Ix2>,
+    ) -> MultiTargetSvm<'a, F> {
+        let models = targets
+            .gencolumns()
+            .into_iter()
+            .map(|column| self.fit_regression(kernel, &column.to_vec(), None))
+            .collect();
+
+        MultiTargetSvm { models }
     }
 }
+
+/// Fitted regression with multiple targets, one model for each target
+pub struct MultiTargetSvm<'a, F: Float> {
+    models: Vec<Svm<'a, F, F>>,
+}
+
+impl<'a, F: Float> MultiTargetSvm<'a, F> {
+    /// Regression of each target
+    pub fn models(&self) -> &[Svm<'a, F, F>] {
+        &self.models
+    }
+}
+
+/// Predict all targets with a set of observations, one column per target
+impl<'a, F: Float, D: Data<Elem = F>> Predict<ArrayBase<D, Ix2>, Array2<F>>
+    for MultiTargetSvm<'a, F>
+{
+    fn predict(&self, data: ArrayBase<D, Ix2>) -> Array2<F> {
+        let mut values = Array2::zeros((data.nrows(), self.models.len()));
+        for (model, mut column) in self.models.iter().zip(values.gencolumns_mut()) {
+            column.assign(&model.decision_function(&data));
+        }
+
+        values
+    }
+}
+
+/// Implement the regression predictions of a model for a float type
+///
+/// A generic implementation over `F: Float` would conflict with the predictions of `Pr` for
+/// classification, because `Pr` could implement `Float` in the future. The lifetime of the
+/// model, if any, is passed as last argument.
+macro_rules! impl_regression_predict {
+    ($t:ty, $model:ty $(, $lt:lifetime)?) => {
+        /// Predict a continuous target with a set of observations
+        impl<$($lt,)? D: Data<Elem = $t>> Predict<ArrayBase<D, Ix2>, Vec<$t>> for $model {
+            fn predict(&self, data: ArrayBase<D, Ix2>) -> Vec<$t> {
+                self.decision_function(&data).to_vec()
+            }
+        }
+
+        impl<$($lt,)? T: Targets> Predict<Dataset<Array2<$t>, T>, Dataset<Array2<$t>, Vec<$t>>>
+            for $model
+        {
+            fn predict(&self, data: Dataset<Array2<$t>, T>) -> Dataset<Array2<$t>, Vec<$t>> {
+                let Dataset { records, .. } = data;
+                let predicted = self.predict(records.view());
+
+                Dataset::new(records, predicted)
+            }
+        }
+
+        impl<$($lt,)? 'b, T: Targets, D: Data<Elem = $t>>
+            Predict<&'b Dataset<ArrayBase<D, Ix2>, T>, Dataset<ArrayView2<'b, $t>, Vec<$t>>>
+            for $model
+        {
+            fn predict(
+                &self,
+                data: &'b Dataset<ArrayBase<D, Ix2>, T>,
+            ) -> Dataset<ArrayView2<'b, $t>, Vec<$t>> {
+                let predicted = self.predict(data.records.view());
+
+                Dataset::new(data.records.view(), predicted)
+            }
+        }
+    };
+}
+
+impl_regression_predict!(f32, Svm<'a, f32, f32>, 'a);
+impl_regression_predict!(f64, Svm<'a, f64, f64>, 'a);
+impl_regression_predict!(f32, SvmModel<f32, f32>);
+impl_regression_predict!(f64, SvmModel<f64, f64>);
+
 #[cfg(test)]
 pub mod tests {
     use super::Svm;
@@ -187,7 +317,7 @@ pub mod tests {
     use linfa::metrics::Regression;
     use linfa::traits::{Fit, Predict, Transformer};
     use linfa_kernel::{Kernel, KernelMethod};
-    use ndarray::{Array, Array1};
+    use ndarray::{Array, Array1, Array2};
 
     #[test]
     fn test_linear_epsilon_regression() {
@@ -232,4 +362,105 @@ pub mod tests {
         let predicted = Array1::from(model.predict(sin_curve.clone()));
         assert!(predicted.mean_squared_error(&target) < 1e-2);
     }
+
+    #[test]
+    fn test_warm_start_regression() {
+        let target = Array::linspace(0f64, 10., 100).to_vec();
+        let records = Array::linspace(0f64, 10., 100)
+            .into_shape((100, 1))
+            .unwrap();
+
+        let kernel = Kernel::params()
+            .method(KernelMethod::Gaussian(50.))
+            .transform(&records);
+        let dataset = Dataset::new(kernel, &target);
+
+        let model = Svm::params().c_eps(10., 0.1).fit(&dataset);
+
+        // the coefficients are split into the positive and negative part and scaled to C = 1
+        let expected = Svm::params().c_eps(1., 0.1).fit(&dataset);
+        let warm = Svm::params()
+            .c_eps(1., 0.1)
+            .warm_start(&model)
+            .fit(&dataset);
+        assert!((warm.objective() - expected.objective()).abs() < 1e-6);
+
+        let predicted = Array1::from(warm.predict(records.view()));
+        let expected = Array1::from(expected.predict(records.view()));
+        assert!(predicted.mean_squared_error(&expected) < 1e-4);
+    }
+
+    #[test]
+    fn test_weighted_regression() {
+        let target = Array::linspace(0f64, 10., 100).to_vec();
+        let records = Array::linspace(0f64, 10., 100)
+            .into_shape((100, 1))
+            .unwrap();
+
+        // a uniform weight of two is the same as doubling C
+        let dataset = Dataset::new(records.clone(), target.clone());
+        let kernel = Kernel::params()
+            .method(KernelMethod::Gaussian(50.))
+            .transform(&dataset);
+        let expected = Svm::params().c_eps(2., 0.1).fit(&kernel);
+
+        let dataset = Dataset::new(records, target).with_weights(vec![2.0; 100]);
+        let kernel = Kernel::params()
+            .method(KernelMethod::Gaussian(50.))
+            .transform(&dataset);
+        let model = Svm::params().c_eps(1., 0.1).fit(&kernel);
+
+        assert!((model.objective() - expected.objective()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_regression_f32() {
+        let target = Array::linspace(0f32, 10., 100).to_vec();
+        let records = Array::linspace(0f32, 10., 100)
+            .into_shape((100, 1))
+            .unwrap();
+
+        let dataset = Dataset::new(records.clone(), target.clone());
+        let kernel = Kernel::params()
+            .method(KernelMethod::Gaussian(50.))
+            .transform(&dataset);
+        let model = Svm::params().c_eps(10., 0.01).fit(&kernel);
+
+        let predicted = Array1::from(model.predict(records.view()));
+        assert!(predicted.mean_squared_error(&target) < 1e-2);
+
+        // predict a dataset into a dataset with the same records
+        let predicted = model.predict(&dataset);
+        assert_eq!(predicted.records(), &records.view());
+        assert_eq!(predicted.targets().len(), 100);
+
+        let predicted = model.predict(Dataset::new(records.clone(), target.clone()));
+        assert!(Array1::from(predicted.targets().clone()).mean_squared_error(&target) < 1e-2);
+    }
+
+    #[test]
+    fn test_multi_target_regression() {
+        let records = Array::linspace(0f64, 10., 100)
+            .into_shape((100, 1))
+            .unwrap();
+        let mut targets = Array2::zeros((100, 2));
+        targets.column_mut(0).assign(&records.column(0));
+        targets
+            .column_mut(1)
+            .assign(&records.column(0).mapv(|x| 2. * x - 1.));
+
+        let kernel = Kernel::params()
+            .method(KernelMethod::Gaussian(50.))
+            .transform(&records);
+        let model = Svm::params()
+            .c_eps(10., 0.01)
+            .fit_multi_target(&kernel, &targets);
+        assert_eq!(model.models().len(), 2);
+
+        let predicted: Array2<f64> = model.predict(records.view());
+        assert_eq!(predicted.dim(), (100, 2));
+        for (pred, target) in predicted.gencolumns().into_iter().zip(targets.gencolumns()) {
+            assert!(pred.to_owned().mean_squared_error(&target.to_owned()) < 1e-2);
+        }
+    }
 }
@@ -1,8 +1,11 @@
 use super::permutable_kernel::Permutable;
 use super::{ExitReason, Float, Svm};
 
-use ndarray::{Array1, Axis};
 use std::marker::PhantomData;
+use std::sync::Arc;
+
+/// Callback receiving the progress of the solver
+pub type ProgressCallback<A> = Arc<dyn Fn(&SolverProgress<A>) + Send + Sync>;
 
 /// Parameters of the solver routine
 #[derive(Clone)]
@@ -11,6 +14,46 @@ pub struct SolverParams<A: Float> {
     pub eps: A,
     /// Should we shrink, e.g. ignore bounded alphas
     pub shrinking: bool,
+    /// Maximal number of iterations, defaults to `max(10^7, 100 * l)`
+    pub max_iterations: Option<usize>,
+    /// Callback invoked every n-th iteration
+    pub progress: Option<(usize, ProgressCallback<A>)>,
+}
+
+impl<A: Float> Default for SolverParams<A> {
+    fn default() -> Self {
+        SolverParams {
+            eps: A::from(1e-7).unwrap(),
+            shrinking: false,
+            max_iterations: None,
+            progress: None,
+        }
+    }
+}
+
+/// Intermediate state of the solver passed to the progress callback
+#[derive(Debug, Clone)]
+pub struct SolverProgress<A: Float> {
+    /// Number of performed iterations
+    pub iteration: usize,
+    /// Current value of the dual objective
+    pub objective: A,
+    /// Maximal violation of the KKT conditions, the solver stops when this is smaller than `eps`
+    pub kkt_gap: A,
+}
+
+/// Scale initial coefficients uniformly until they are within their bounds
+///
+/// A uniform scaling keeps the equality constraint `y^T a = 0` of a previous solution satisfied.
+pub(crate) fn scale_to_bounds<A: Float>(alpha: Vec<A>, bounds: &[A]) -> Vec<A> {
+    let scale = alpha
+        .iter()
+        .zip(bounds.iter())
+        .filter(|(a, _)| **a > A::zero())
+        .map(|(a, bound)| *bound / *a)
+        .fold(A::one(), A::min);
+
+    alpha.into_iter().map(|a| a * scale).collect()
 }
 
 /// Status of alpha variables of the solver
@@ -760,15 +803,39 @@ impl<'a, A: Float, K: 'a + Permutable<'a, A>> SolverState<'a, A, K> {
         (r1 - r2) / A::from(2.0).unwrap()
     }
 
+    /// Maximal violation of the KKT conditions in the active set
+    pub fn kkt_gap(&self) -> A {
+        if self.nu_constraint {
+            let (gmaxp1, gmaxn1, gmaxp2, gmaxn2) = self.max_violating_pair_nu();
+
+            A::max(gmaxp1.0 + gmaxp2.0, gmaxn1.0 + gmaxn2.0)
+        } else {
+            let (gmax1, gmax2) = self.max_violating_pair();
+
+            gmax1.0 + gmax2.0
+        }
+    }
+
+    /// Value of the objective function
+    ///
+    /// The gradients of shrunk variables are not updated, hence this is only approximated while
+    /// the active set is reduced.
+    pub fn objective(&self) -> A {
+        let v = (0..self.ntotal())
+            .map(|i| self.alpha[i].val() * (self.gradient[i] + self.p[i]))
+            .sum::<A>();
+
+        v / A::from(2.0).unwrap()
+    }
+
     pub fn solve(mut self) -> Svm<'a, A, A> {
         let mut iter = 0;
-        let max_iter = if self.targets.len() > std::usize::MAX / 100 {
-            std::usize::MAX
-        } else {
-            100 * self.targets.len()
+        let max_iter = match self.params.max_iterations {
+            Some(max_iter) => max_iter,
+            None if self.targets.len() > usize::MAX / 100 => usize::MAX,
+            None => usize::max(10_000_000, 100 * self.targets.len()),
         };
 
-        let max_iter = usize::max(10_000_000, max_iter);
         let mut counter = usize::min(self.targets.len(), 1000) + 1;
         while iter < max_iter {
             counter -= 1;
@@ -797,6 +864,16 @@ impl<'a, A: Float, K: 'a + Permutable<'a, A>> SolverState<'a, A, K> {
 
             // update alpha[i] and alpha[j]
             self.update((i, j));
+
+            if let Some((every, ref callback)) = self.params.progress {
+                if iter % usize::max(every, 1) == 0 {
+                    callback(&SolverProgress {
+                        iteration: iter,
+                        objective: self.objective(),
+                        kkt_gap: self.kkt_gap(),
+                    });
+                }
+            }
         }
 
         if iter >= max_iter && self.nactive() < self.targets.len() {
@@ -812,11 +889,7 @@ impl<'a, A: Float, K: 'a + Permutable<'a, A>> SolverState<'a, A, K> {
         };
 
         // calculate object function
-        let mut v = A::zero();
-        for i in 0..self.targets.len() {
-            v += self.alpha[i].val() * (self.gradient[i] + self.p[i]);
-        }
-        let obj = v / A::from(2.0).unwrap();
+        let obj = self.objective();
 
         let exit_reason = if max_iter == iter {
             ExitReason::ReachedIterations
@@ -828,18 +901,9 @@ impl<'a, A: Float, K: 'a + Permutable<'a, A>> SolverState<'a, A, K> {
         let alpha: Vec<A> = (0..self.ntotal())
             .map(|i| self.alpha[self.active_set[i]].val())
             .collect();
-
-        // if the kernel is linear, then we can pre-calculate the dot product
-        let linear_decision = if self.kernel.inner().is_linear() {
-            let mut tmp = Array1::zeros(self.kernel.inner().dataset.len_of(Axis(1)));
-            for (i, elm) in self.kernel.inner().dataset.outer_iter().enumerate() {
-                tmp.scaled_add(self.target(i) * alpha[i], &elm);
-            }
-
-            Some(tmp)
-        } else {
-            None
-        };
+        let bounded = (0..self.ntotal())
+            .map(|i| self.alpha[self.active_set[i]].reached_upper())
+            .collect();
 
         Svm {
             alpha,
@@ -848,8 +912,10 @@ impl<'a, A: Float, K: 'a + Permutable<'a, A>> SolverState<'a, A, K> {
             exit_reason,
             obj,
             iterations: iter,
+            bounded,
             kernel: self.kernel.inner(),
-            linear_decision,
+            linear_decision: None,
+            platt: None,
             phantom: PhantomData,
         }
     }
@@ -1,6 +1,6 @@
 use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
 use linfa::prelude::*;
-use linfa_trees::DecisionTree;
+use linfa_trees::{DecisionTree, SplitSearch};
 use ndarray::{stack, Array, Array2, Axis};
 use ndarray_rand::rand::SeedableRng;
 use ndarray_rand::rand_distr::{StandardNormal, Uniform};
@@ -54,5 +54,42 @@ fn decision_tree_bench(c: &mut Criterion) {
     group.finish();
 }
 
-criterion_group!(benches, decision_tree_bench);
+fn split_search_bench(c: &mut Criterion) {
+    let mut rng = Isaac64Rng::seed_from_u64(42);
+
+    // Controls how many samples for each class are generated
+    let training_set_sizes = &[1000, 10000, 100000];
+
+    let n_classes = 4;
+    let n_features = 4;
+
+    let searches = &[
+        ("exact", SplitSearch::Exact),
+        ("histogram", SplitSearch::Histogram(255)),
+    ];
+
+    // Compare the training time of both split searches for each training sample size
+    let mut group = c.benchmark_group("decision_tree_split_search");
+    group.sample_size(10);
+
+    for n in training_set_sizes.iter() {
+        let centroids =
+            Array2::random_using((n_classes, n_features), Uniform::new(-30., 30.), &mut rng);
+
+        let train_x = generate_blobs(&centroids, *n, &mut rng);
+        let train_y = Array::from_iter((0..n_classes * n).map(|i| i / n));
+        let dataset = Dataset::new(train_x, train_y);
+
+        for (name, split_search) in searches.iter() {
+            let hyperparams = DecisionTree::params().split_search(*split_search);
+            group.bench_with_input(BenchmarkId::new(*name, n), &dataset, |b, d| {
+                b.iter(|| hyperparams.fit(d))
+            });
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, decision_tree_bench, split_search_bench);
 criterion_main!(benches);
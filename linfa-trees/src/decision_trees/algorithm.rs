@@ -4,9 +4,10 @@ use std::collections::{HashMap, HashSet};
 use std::hash::{Hash, Hasher};
 use std::marker::PhantomData;
 
-use ndarray::{ArrayBase, Axis, Data, Ix1, Ix2};
+use ndarray::{Array2, ArrayBase, ArrayViewMut1, Axis, Data, Ix1, Ix2};
 
-use super::hyperparameters::{DecisionTreeParams, SplitQuality};
+use super::histogram::BinnedFeature;
+use super::hyperparameters::{DecisionTreeParams, SplitQuality, SplitSearch};
 use super::NodeIter;
 use super::Tikz;
 use linfa::{
@@ -23,40 +24,57 @@ use serde_crate::{Deserialize, Serialize};
 /// The decision tree algorithm splits observations at a certain split value for a specific feature. The
 /// left and right children can then only use a certain number of observations. In order to track
 /// that the observations are masked with a boolean vector, hiding all observations which are not
-/// applicable in a lower tree.
-struct RowMask {
+/// applicable in a lower tree. The indices of the marked observations are kept as well, so that
+/// a node can visit its observations without scanning the whole dataset.
+pub(crate) struct RowMask {
     mask: Vec<bool>,
-    nsamples: usize,
+    idxs: Vec<usize>,
 }
 
 impl RowMask {
-    fn all(nsamples: usize) -> Self {
+    pub(crate) fn all(nsamples: usize) -> Self {
         RowMask {
             mask: vec![true; nsamples as usize],
-            nsamples,
+            idxs: (0..nsamples).collect(),
         }
     }
 
-    fn none(nsamples: usize) -> Self {
+    pub(crate) fn none(nsamples: usize) -> Self {
         RowMask {
             mask: vec![false; nsamples as usize],
-            nsamples: 0,
+            idxs: Vec::new(),
         }
     }
 
-    fn mark(&mut self, idx: usize) {
+    pub(crate) fn mark(&mut self, idx: usize) {
         self.mask[idx] = true;
-        self.nsamples += 1;
+        self.idxs.push(idx);
+    }
+
+    pub(crate) fn is_marked(&self, idx: usize) -> bool {
+        self.mask[idx]
+    }
+
+    /// Indices of the marked observations, in the order they were marked
+    pub(crate) fn marked(&self) -> &[usize] {
+        &self.idxs
+    }
+
+    pub(crate) fn nsamples(&self) -> usize {
+        self.idxs.len()
     }
 }
 
 /// Sorted values of observations with indices (always for a particular feature)
-struct SortedIndex<F: Float> {
-    sorted_values: Vec<(usize, F)>,
+pub(crate) struct SortedIndex<F: Float> {
+    pub(crate) sorted_values: Vec<(usize, F)>,
 }
 
 impl<F: Float> SortedIndex<F> {
-    fn of_array_column(x: &ArrayBase<impl Data<Elem = F>, Ix2>, feature_idx: usize) -> Self {
+    pub(crate) fn of_array_column(
+        x: &ArrayBase<impl Data<Elem = F>, Ix2>,
+        feature_idx: usize,
+    ) -> Self {
         let sliced_column: Vec<F> = x.index_axis(Axis(1), feature_idx).to_vec();
         let mut pairs: Vec<(usize, F)> = sliced_column.into_iter().enumerate().collect();
         pairs.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Greater));
@@ -67,6 +85,30 @@ impl<F: Float> SortedIndex<F> {
     }
 }
 
+/// Preprocessed features for the split search, shared by all nodes of a tree
+pub(crate) enum FeatureIndex<F: Float> {
+    Sorted(Vec<SortedIndex<F>>),
+    Binned(Vec<BinnedFeature<F>>),
+}
+
+impl<F: Float> FeatureIndex<F> {
+    pub(crate) fn new(x: &ArrayBase<impl Data<Elem = F>, Ix2>, split_search: SplitSearch) -> Self {
+        let features = 0..x.ncols();
+        match split_search {
+            SplitSearch::Exact => FeatureIndex::Sorted(
+                features
+                    .map(|feature_idx| SortedIndex::of_array_column(x, feature_idx))
+                    .collect(),
+            ),
+            SplitSearch::Histogram(max_bins) => FeatureIndex::Binned(
+                features
+                    .map(|feature_idx| BinnedFeature::of_array_column(x, feature_idx, max_bins))
+                    .collect(),
+            ),
+        }
+    }
+}
+
 #[cfg_attr(
     feature = "serde",
     derive(Serialize, Deserialize),
@@ -77,11 +119,13 @@ impl<F: Float> SortedIndex<F> {
 pub struct TreeNode<F, L> {
     feature_idx: usize,
     split_value: F,
+    missing_left: bool,
     impurity_decrease: F,
     left_child: Option<Box<TreeNode<F, L>>>,
     right_child: Option<Box<TreeNode<F, L>>>,
     leaf_node: bool,
     prediction: L,
+    class_freq: Vec<(L, f32)>,
     depth: usize,
 }
 
@@ -104,15 +148,17 @@ impl<F, L> PartialEq for TreeNode<F, L> {
 }
 
 impl<F: Float, L: Label + std::fmt::Debug> TreeNode<F, L> {
-    fn empty_leaf(prediction: L, depth: usize) -> Self {
+    fn empty_leaf(prediction: L, class_freq: Vec<(L, f32)>, depth: usize) -> Self {
         TreeNode {
             feature_idx: 0,
             split_value: F::zero(),
+            missing_left: false,
             impurity_decrease: F::zero(),
             left_child: None,
             right_child: None,
             leaf_node: true,
             prediction,
+            class_freq,
             depth,
         }
     }
@@ -143,33 +189,66 @@ impl<F: Float, L: Label + std::fmt::Debug> TreeNode<F, L> {
         (self.feature_idx, self.split_value, self.impurity_decrease)
     }
 
+    /// Return whether samples with a missing value of the split feature go to the left child
+    pub fn missing_left(&self) -> bool {
+        self.missing_left
+    }
+
+    /// Return the weighted frequencies of the classes of the training samples in this node
+    pub fn class_frequencies(&self) -> &[(L, f32)] {
+        &self.class_freq
+    }
+
+    /// Fit a node on the samples in `mask`
+    ///
+    /// The candidate features of each split are drawn from `features`.
     fn fit<D: Data<Elem = F>, T: Labels<Elem = L>>(
         data: &Dataset<ArrayBase<D, Ix2>, T>,
         mask: &RowMask,
         hyperparameters: &DecisionTreeParams<F, L>,
-        sorted_indices: &[SortedIndex<F>],
+        index: &FeatureIndex<F>,
         depth: usize,
+        features: &mut dyn FnMut() -> Vec<usize>,
     ) -> Self {
         // compute weighted frequencies for target classes
         let parent_class_freq = data.frequencies_with_mask(&mask.mask);
         // set our prediction for this subset to the modal class
         let prediction = find_modal_class(&parent_class_freq);
+        let class_freq = parent_class_freq
+            .iter()
+            .map(|(class, freq)| ((*class).clone(), *freq))
+            .collect::<Vec<_>>();
 
         // return empty leaf when we don't have enough samples or the maximal depth is reached
-        if (mask.nsamples as f32) < hyperparameters.min_weight_split
+        if (mask.nsamples() as f32) < hyperparameters.min_weight_split
             || hyperparameters
                 .max_depth
                 .map(|max_depth| depth >= max_depth)
                 .unwrap_or(false)
         {
-            return Self::empty_leaf(prediction, depth);
+            return Self::empty_leaf(prediction, class_freq, depth);
         }
 
         // Find best split for current level
         let mut best = None;
 
         // Iterate over features
-        for (feature_idx, sorted_index) in sorted_indices.iter().enumerate() {
+        for feature_idx in features() {
+            let sorted_index = match index {
+                FeatureIndex::Sorted(sorted_indices) => &sorted_indices[feature_idx],
+                FeatureIndex::Binned(binned) => {
+                    if let Some((split_value, score, missing_left)) = best_binned_split(
+                        data,
+                        mask,
+                        &binned[feature_idx],
+                        &parent_class_freq,
+                        hyperparameters,
+                    ) {
+                        best = better_split(best, (feature_idx, split_value, score, missing_left));
+                    }
+                    continue;
+                }
+            };
             let mut left_class_freq = parent_class_freq.clone();
             let mut right_class_freq = HashMap::new();
 
@@ -179,13 +258,18 @@ impl<F: Float, L: Label + std::fmt::Debug> TreeNode<F, L> {
             let mut weight_on_left_side = total_weight;
             let mut weight_on_right_side = 0.0;
 
-            // Iterate over sorted values
-            for i in 0..mask.mask.len() - 1 {
-                let (presorted_index, mut split_value) = sorted_index.sorted_values[i];
-
-                if !mask.mask[presorted_index] {
-                    continue;
-                }
+            // Iterate over sorted values of the samples in this node
+            let mut sorted_values = sorted_index
+                .sorted_values
+                .iter()
+                .filter(|(idx, _)| mask.mask[*idx])
+                .peekable();
+            while let Some(&(presorted_index, mut split_value)) = sorted_values.next() {
+                // the last sample can not be moved to the right side
+                let next_value = match sorted_values.peek() {
+                    Some((_, value)) => *value,
+                    None => break,
+                };
 
                 let sample_class = data.target(presorted_index);
                 let sample_weight = data.weight_for(presorted_index);
@@ -201,9 +285,7 @@ impl<F: Float, L: Label + std::fmt::Debug> TreeNode<F, L> {
                 weight_on_right_side += sample_weight;
 
                 // Continue if the next values is equal
-                if (sorted_index.sorted_values[i].1 - sorted_index.sorted_values[i + 1].1).abs()
-                    < F::from(1e-5).unwrap()
-                {
+                if (split_value - next_value).abs() < F::from(1e-5).unwrap() {
                     continue;
                 }
 
@@ -215,37 +297,22 @@ impl<F: Float, L: Label + std::fmt::Debug> TreeNode<F, L> {
                     continue;
                 }
 
-                // Calculate the quality of each resulting subset of the dataset
-                let (left_score, right_score) = match hyperparameters.split_quality {
-                    SplitQuality::Gini => (
-                        gini_impurity(&left_class_freq),
-                        gini_impurity(&right_class_freq),
-                    ),
-                    SplitQuality::Entropy => {
-                        (entropy(&left_class_freq), entropy(&right_class_freq))
-                    }
-                };
-
-                // Weight the qualities based on the number of samples in each subset
-                let w = weight_on_left_side / total_weight;
-                let score = w * left_score + (1.0 - w) * right_score;
+                let score = split_score(
+                    hyperparameters.split_quality,
+                    &left_class_freq,
+                    &right_class_freq,
+                    weight_on_left_side / total_weight,
+                );
 
                 // Take the midpoint from this value and the next one as split_value
-                split_value =
-                    (split_value + sorted_index.sorted_values[i + 1].1) / F::from(2.0).unwrap();
+                split_value = (split_value + next_value) / F::from(2.0).unwrap();
 
                 // override best indices when score improved
-                best = match best.take() {
-                    None => Some((feature_idx, split_value, score)),
-                    Some((_, _, best_score)) if score < best_score => {
-                        Some((feature_idx, split_value, score))
-                    }
-                    x => x,
-                };
+                best = better_split(best, (feature_idx, split_value, score, false));
             }
         }
 
-        let impurity_decrease = if let Some((_, _, best_score)) = best {
+        let impurity_decrease = if let Some((_, _, best_score, _)) = best {
             let parent_score = match hyperparameters.split_quality {
                 SplitQuality::Gini => gini_impurity(&parent_class_freq),
                 SplitQuality::Entropy => entropy(&parent_class_freq),
@@ -260,45 +327,46 @@ impl<F: Float, L: Label + std::fmt::Debug> TreeNode<F, L> {
         };
 
         if impurity_decrease < hyperparameters.min_impurity_decrease {
-            return Self::empty_leaf(prediction, depth);
+            return Self::empty_leaf(prediction, class_freq, depth);
         }
 
-        let (best_feature_idx, best_split_value, _) = best.unwrap();
+        let (best_feature_idx, best_split_value, _, missing_left) = best.unwrap();
 
         // determine new masks for the left and right subtrees
         let mut left_mask = RowMask::none(data.observations());
         let mut right_mask = RowMask::none(data.observations());
 
-        for i in 0..data.observations() {
-            if mask.mask[i] {
-                if data.records()[(i, best_feature_idx)] <= best_split_value {
-                    left_mask.mark(i);
-                } else {
-                    right_mask.mark(i);
-                }
+        for &i in mask.marked() {
+            let value = data.records()[(i, best_feature_idx)];
+            if value <= best_split_value || (value.is_nan() && missing_left) {
+                left_mask.mark(i);
+            } else {
+                right_mask.mark(i);
             }
         }
 
         // Recurse and refit on left and right subtrees
-        let left_child = if left_mask.nsamples > 0 {
+        let left_child = if left_mask.nsamples() > 0 {
             Some(Box::new(TreeNode::fit(
                 data,
                 &left_mask,
                 &hyperparameters,
-                &sorted_indices,
+                index,
                 depth + 1,
+                features,
             )))
         } else {
             None
         };
 
-        let right_child = if right_mask.nsamples > 0 {
+        let right_child = if right_mask.nsamples() > 0 {
             Some(Box::new(TreeNode::fit(
                 data,
                 &right_mask,
                 &hyperparameters,
-                &sorted_indices,
+                index,
                 depth + 1,
+                features,
             )))
         } else {
             None
@@ -309,44 +377,16 @@ impl<F: Float, L: Label + std::fmt::Debug> TreeNode<F, L> {
         TreeNode {
             feature_idx: best_feature_idx,
             split_value: best_split_value,
+            missing_left,
             impurity_decrease,
             left_child,
             right_child,
             leaf_node,
             prediction,
+            class_freq,
             depth,
         }
     }
-
-    /// Prune tree after fitting it
-    ///
-    /// This removes parts of the tree which results in the same prediction for
-    /// all sub-trees. This is called right after fit to ensure that the tree
-    /// is small.
-    fn prune(&mut self) -> Option<L> {
-        if self.is_leaf() {
-            return Some(self.prediction.clone());
-        }
-
-        let left = self.left_child.as_mut().and_then(|x| x.prune());
-        let right = self.right_child.as_mut().and_then(|x| x.prune());
-
-        match (left, right) {
-            (Some(x), Some(y)) => {
-                if x == y {
-                    self.prediction = x.clone();
-                    self.right_child = None;
-                    self.left_child = None;
-                    self.leaf_node = true;
-
-                    Some(x)
-                } else {
-                    None
-                }
-            }
-            _ => None,
-        }
-    }
 }
 
 /// A fitted decision tree model.
@@ -358,6 +398,7 @@ impl<F: Float, L: Label + std::fmt::Debug> TreeNode<F, L> {
 #[derive(Debug)]
 pub struct DecisionTree<F: Float, L: Label> {
     root_node: TreeNode<F, L>,
+    classes: Vec<L>,
     num_features: usize,
 }
 
@@ -393,23 +434,53 @@ impl<'a, F: Float, L: Label + 'a + std::fmt::Debug, D: Data<Elem = F>, T: Labels
 
         let x = dataset.records();
         let all_idxs = RowMask::all(x.nrows());
-        let sorted_indices: Vec<_> = (0..(x.ncols()))
-            .map(|feature_idx| SortedIndex::of_array_column(&x, feature_idx))
-            .collect();
+        let index = FeatureIndex::new(x, self.split_search);
 
-        let mut root_node = TreeNode::fit(&dataset, &all_idxs, &self, &sorted_indices, 0);
-        root_node.prune();
+        let nfeatures = x.ncols();
+        let root_node = self.fit_node(dataset, &all_idxs, &index, &mut || (0..nfeatures).collect());
 
         DecisionTree {
             root_node,
+            classes: classes_of(dataset.targets().as_slice()),
+            num_features: nfeatures,
+        }
+    }
+}
+
+impl<F: Float, L: Label + std::fmt::Debug> DecisionTreeParams<F, L> {
+    /// Fit a tree on the samples in `mask`, the candidate features of each split are
+    /// drawn from `features`
+    ///
+    /// Used by ensembles, which share the feature index between their trees.
+    pub(crate) fn fit_with_features<D: Data<Elem = F>, T: Labels<Elem = L>>(
+        &self,
+        dataset: &Dataset<ArrayBase<D, Ix2>, T>,
+        mask: &RowMask,
+        index: &FeatureIndex<F>,
+        features: &mut dyn FnMut() -> Vec<usize>,
+    ) -> DecisionTree<F, L> {
+        DecisionTree {
+            root_node: self.fit_node(dataset, mask, index, features),
+            classes: classes_of(dataset.targets().as_slice()),
             num_features: dataset.records().ncols(),
         }
     }
+
+    fn fit_node<D: Data<Elem = F>, T: Labels<Elem = L>>(
+        &self,
+        dataset: &Dataset<ArrayBase<D, Ix2>, T>,
+        mask: &RowMask,
+        index: &FeatureIndex<F>,
+        features: &mut dyn FnMut() -> Vec<usize>,
+    ) -> TreeNode<F, L> {
+        TreeNode::fit(dataset, mask, self, index, 0, features)
+    }
 }
 
 impl<F: Float, L: Label + std::fmt::Debug> DecisionTree<F, L> {
     /// Defaults are provided if the optional parameters are not specified:
     /// * `split_quality = SplitQuality::Gini`
+    /// * `split_search = SplitSearch::Exact`
     /// * `max_depth = None`
     /// * `min_weight_split = 2.0`
     /// * `min_weight_leaf = 1.0`
@@ -419,6 +490,7 @@ impl<F: Float, L: Label + std::fmt::Debug> DecisionTree<F, L> {
     pub fn params() -> DecisionTreeParams<F, L> {
         DecisionTreeParams {
             split_quality: SplitQuality::Gini,
+            split_search: SplitSearch::Exact,
             max_depth: None,
             min_weight_split: 2.0,
             min_weight_leaf: 1.0,
@@ -491,11 +563,43 @@ impl<F: Float, L: Label + std::fmt::Debug> DecisionTree<F, L> {
         self.relative_impurity_decrease()
     }
 
+    /// Return the classes in the order of the columns of `predict_proba`
+    ///
+    /// The classes are ordered by their first appearance in the training targets.
+    pub fn classes(&self) -> &[L] {
+        &self.classes
+    }
+
+    /// Return the probability of each class for each row of a matrix of features `x`
+    ///
+    /// The probabilities are the weighted class frequencies of the training samples in the
+    /// leaf reached by a row. The columns are ordered like [`classes`](#method.classes).
+    pub fn predict_proba<D: Data<Elem = F>>(&self, x: &ArrayBase<D, Ix2>) -> Array2<F> {
+        let class_idx = self
+            .classes
+            .iter()
+            .enumerate()
+            .map(|(i, class)| (class, i))
+            .collect::<HashMap<_, _>>();
+
+        let mut proba = Array2::zeros((x.nrows(), self.classes.len()));
+        for (row, proba) in x.genrows().into_iter().zip(proba.genrows_mut()) {
+            add_leaf_proba(self, &row, &class_idx, proba);
+        }
+
+        proba
+    }
+
     /// Return root node of the tree
     pub fn root_node(&self) -> &TreeNode<F, L> {
         &self.root_node
     }
 
+    /// Return the leaf reached by a sample
+    pub(crate) fn leaf_for(&self, x: &ArrayBase<impl Data<Elem = F>, Ix1>) -> &TreeNode<F, L> {
+        find_leaf(x, &self.root_node)
+    }
+
     /// Return max depth of the tree
     pub fn max_depth(&self) -> usize {
         self.iter_nodes()
@@ -518,15 +622,56 @@ fn make_prediction<F: Float, L: Label>(
     x: &ArrayBase<impl Data<Elem = F>, Ix1>,
     node: &TreeNode<F, L>,
 ) -> L {
+    find_leaf(x, node).prediction.clone()
+}
+
+/// Find the leaf of a sample &x recursively starting at the tree node `node`.
+fn find_leaf<'a, F: Float, L: Label>(
+    x: &ArrayBase<impl Data<Elem = F>, Ix1>,
+    node: &'a TreeNode<F, L>,
+) -> &'a TreeNode<F, L> {
+    let value = x[node.feature_idx];
     if node.leaf_node {
-        node.prediction.clone()
-    } else if x[node.feature_idx] < node.split_value {
-        make_prediction(x, node.left_child.as_ref().unwrap())
+        node
+    } else if value < node.split_value || (value.is_nan() && node.missing_left) {
+        find_leaf(x, node.left_child.as_ref().unwrap())
     } else {
-        make_prediction(x, node.right_child.as_ref().unwrap())
+        find_leaf(x, node.right_child.as_ref().unwrap())
     }
 }
 
+/// Add the class distribution of the leaf reached by a sample to `proba`
+///
+/// Leaves without weight, for example of samples with zero weight only, add nothing.
+pub(crate) fn add_leaf_proba<F: Float, L: Label + std::fmt::Debug>(
+    tree: &DecisionTree<F, L>,
+    x: &ArrayBase<impl Data<Elem = F>, Ix1>,
+    class_idx: &HashMap<&L, usize>,
+    mut proba: ArrayViewMut1<F>,
+) {
+    let freqs = tree.leaf_for(x).class_frequencies();
+    let total = freqs.iter().map(|(_, freq)| *freq).sum::<f32>();
+    if total <= 0.0 {
+        return;
+    }
+
+    for (class, freq) in freqs {
+        proba[class_idx[class]] += F::from(*freq / total).unwrap();
+    }
+}
+
+/// Return the distinct labels in order of their first appearance
+pub(crate) fn classes_of<L: Label>(targets: &[L]) -> Vec<L> {
+    let mut classes = Vec::new();
+    for target in targets {
+        if !classes.contains(target) {
+            classes.push(target.clone());
+        }
+    }
+
+    classes
+}
+
 /// Finds the most frequent class for a hash map of frequencies. If two
 /// classes have the same weight then the first class found with that
 /// frequency is returned.
@@ -551,6 +696,119 @@ fn find_modal_class<L: Label>(class_freq: &HashMap<&L, f32>) -> L {
     (*val).clone()
 }
 
+/// Keep the candidate split if its score is lower than the score of the best split so far
+fn better_split<F>(
+    best: Option<(usize, F, f32, bool)>,
+    candidate: (usize, F, f32, bool),
+) -> Option<(usize, F, f32, bool)> {
+    match best {
+        Some(best) if best.2 <= candidate.2 => Some(best),
+        _ => Some(candidate),
+    }
+}
+
+/// Weight the impurities of both subsets by the fraction `w` of the weight on the left side
+fn split_score<L: Label>(
+    split_quality: SplitQuality,
+    left_class_freq: &HashMap<&L, f32>,
+    right_class_freq: &HashMap<&L, f32>,
+    w: f32,
+) -> f32 {
+    let (left_score, right_score) = match split_quality {
+        SplitQuality::Gini => (
+            gini_impurity(left_class_freq),
+            gini_impurity(right_class_freq),
+        ),
+        SplitQuality::Entropy => (entropy(left_class_freq), entropy(right_class_freq)),
+    };
+
+    w * left_score + (1.0 - w) * right_score
+}
+
+/// Find the best threshold between the bins of a feature, returns the threshold, its score and
+/// whether missing values go to the left side
+///
+/// If there are missing values, the split of the missing from the non-missing values is a
+/// candidate as well, with the largest finite value as threshold.
+fn best_binned_split<'a, F: Float, L: Label, D: Data<Elem = F>, T: Labels<Elem = L>>(
+    data: &'a Dataset<ArrayBase<D, Ix2>, T>,
+    mask: &RowMask,
+    binned: &BinnedFeature<F>,
+    parent_class_freq: &HashMap<&'a L, f32>,
+    hyperparameters: &DecisionTreeParams<F, L>,
+) -> Option<(F, f32, bool)> {
+    let histogram = binned.histogram(data, mask);
+    let total_weight = parent_class_freq.values().sum::<f32>();
+
+    // missing values are tried on both sides, if there are any
+    let routes: &[bool] = if histogram.missing.is_empty() {
+        &[false]
+    } else {
+        &[false, true]
+    };
+
+    let bins = (0..binned.num_bins())
+        .filter(|bin| !histogram.bins[*bin].is_empty())
+        .collect::<Vec<_>>();
+
+    let mut left_class_freq = HashMap::new();
+    let mut best: Option<(F, f32, bool)> = None;
+    for (pos, &bin) in bins.iter().enumerate() {
+        for (class, freq) in &histogram.bins[bin] {
+            *left_class_freq.entry(*class).or_insert(0.0) += *freq;
+        }
+
+        // after the last bin only the missing values are left for the right side
+        let (threshold, routes) = match bins.get(pos + 1) {
+            Some(&next) => (binned.threshold(bin, next), routes),
+            None if !histogram.missing.is_empty() => (F::max_value(), &[false][..]),
+            None => break,
+        };
+
+        for &missing_left in routes {
+            let mut left_class_freq = left_class_freq.clone();
+            if missing_left {
+                for (class, freq) in &histogram.missing {
+                    *left_class_freq.entry(*class).or_insert(0.0) += *freq;
+                }
+            }
+            let right_class_freq = parent_class_freq
+                .iter()
+                .map(|(class, freq)| (*class, freq - left_class_freq.get(class).unwrap_or(&0.0)))
+                .collect::<HashMap<_, _>>();
+
+            let weight_on_left_side = left_class_freq.values().sum::<f32>();
+            let weight_on_right_side = total_weight - weight_on_left_side;
+
+            // If the split would result in too few samples in a leaf
+            // then skip computing the quality
+            if weight_on_left_side < hyperparameters.min_weight_leaf
+                || weight_on_right_side < hyperparameters.min_weight_leaf
+                || weight_on_left_side <= 0.0
+                || weight_on_right_side <= 0.0
+            {
+                continue;
+            }
+
+            let score = split_score(
+                hyperparameters.split_quality,
+                &left_class_freq,
+                &right_class_freq,
+                weight_on_left_side / total_weight,
+            );
+
+            if best
+                .map(|(_, best_score, _)| score < best_score)
+                .unwrap_or(true)
+            {
+                best = Some((threshold, score, missing_left));
+            }
+        }
+    }
+
+    best
+}
+
 /// Given the class frequencies calculates the gini impurity of the subset.
 fn gini_impurity<L: Label>(class_freq: &HashMap<&L, f32>) -> f32 {
     let n_samples = class_freq.values().sum::<f32>();
@@ -582,7 +840,8 @@ mod tests {
     use super::*;
 
     use approx::assert_abs_diff_eq;
-    use linfa::metrics::ToConfusionMatrix;
+    use linfa::dataset::Pr;
+    use linfa::metrics::{BinaryClassification, ToConfusionMatrix};
     use ndarray::{array, s, stack, Array, Array1, Array2, Axis};
     use rand_isaac::Isaac64Rng;
 
@@ -772,6 +1031,38 @@ mod tests {
         assert!(cm.accuracy() > 0.99);
     }
 
+    #[test]
+    /// Thresholds lie between neighbouring values of the samples in a node
+    ///
+    /// The second feature of the samples in the left child interleaves with the samples of the
+    /// right child. The split of the left child should not consider these.
+    fn split_between_samples_of_node() {
+        let data = array![
+            [0., 0.],
+            [0., 2.],
+            [0., 4.],
+            [0., 6.],
+            [1., 1.],
+            [1., 3.],
+            [1., 5.],
+            [1., 7.]
+        ];
+        let targets = array![0, 0, 1, 1, 2, 2, 2, 2];
+
+        let dataset = Dataset::new(data, targets);
+        let model = DecisionTree::params().fit(&dataset);
+
+        let root = model.root_node();
+        assert_eq!(root.split().0, 0);
+        assert_abs_diff_eq!(root.split().1, 0.5);
+
+        let left = root.childs()[0].as_ref().unwrap();
+        assert_eq!(left.split().0, 1);
+        assert_abs_diff_eq!(left.split().1, 3.0);
+
+        assert_eq!(model.predict(&array![[0., 2.75], [0., 3.25]]), vec![0, 1]);
+    }
+
     #[test]
     #[should_panic]
     /// Check that a small or negative impurity decrease panics
@@ -781,4 +1072,154 @@ mod tests {
             .validate()
             .unwrap();
     }
+
+    #[test]
+    /// With fewer distinct values than bins the histogram search finds the exact splits
+    fn histogram_equals_exact() {
+        let mut rng = Isaac64Rng::seed_from_u64(42);
+        let data = Array::random_using((100, 3), Uniform::new(-1., 1.), &mut rng);
+        let targets = data
+            .genrows()
+            .into_iter()
+            .map(|x| (x[0] > 0.0) as usize + (x[1] > 0.5) as usize)
+            .collect::<Vec<_>>();
+        let dataset = Dataset::new(data.clone(), targets);
+
+        let exact = DecisionTree::params().fit(&dataset);
+        let histogram = DecisionTree::params()
+            .split_search(SplitSearch::Histogram(255))
+            .fit(&dataset);
+        assert_eq!(exact.predict(&data), histogram.predict(&data));
+        assert_eq!(exact.num_leaves(), histogram.num_leaves());
+
+        // a few bins are still enough to separate the classes
+        let coarse = DecisionTree::params()
+            .split_search(SplitSearch::Histogram(16))
+            .fit(&dataset);
+        let cm = coarse.predict(&data).confusion_matrix(&dataset);
+        assert!(cm.accuracy() > 0.9);
+    }
+
+    #[test]
+    /// Missing values are routed to the side of the class they belong to
+    fn histogram_missing_values() {
+        let data = array![[0.], [1.], [2.], [3.], [f64::NAN], [f64::NAN]];
+
+        for &missing_class in &[false, true] {
+            let targets = vec![false, false, true, true, missing_class, missing_class];
+            let dataset = Dataset::new(data.clone(), targets);
+
+            let model = DecisionTree::params()
+                .split_search(SplitSearch::Histogram(255))
+                .fit(&dataset);
+
+            assert_eq!(model.root_node().split().1, 1.5);
+            assert_eq!(model.root_node().missing_left(), !missing_class);
+            assert_eq!(
+                model.predict(&array![[f64::NAN], [0.5], [2.5]]),
+                vec![missing_class, false, true]
+            );
+        }
+    }
+
+    #[test]
+    /// Missing values are split from the others, even if the others fall into a single bin
+    fn histogram_missing_single_bin() {
+        let data = array![[1.], [1.], [1.], [f64::NAN], [f64::NAN]];
+        let targets = vec![false, false, false, true, true];
+        let dataset = Dataset::new(data.clone(), targets.clone());
+
+        let model = DecisionTree::params()
+            .split_search(SplitSearch::Histogram(255))
+            .fit(&dataset);
+
+        assert_eq!(model.root_node().split().1, f64::MAX);
+        assert!(!model.root_node().missing_left());
+        assert_eq!(model.num_leaves(), 2);
+        assert_eq!(model.predict(&data), targets);
+    }
+
+    #[test]
+    fn histogram_invalid_bins() {
+        for &max_bins in &[1, 256] {
+            assert!(DecisionTree::<f64, bool>::params()
+                .split_search(SplitSearch::Histogram(max_bins))
+                .validate()
+                .is_err());
+        }
+    }
+
+    #[test]
+    /// Leaves predict the weighted class frequencies of their training samples
+    fn predict_proba_weighted_leaves() {
+        let data = array![[0.], [0.], [0.], [1.], [1.]];
+        let targets = vec![1, 0, 0, 2, 2];
+        let dataset = Dataset::new(data, targets).with_weights(vec![2.0, 1.0, 1.0, 1.0, 1.0]);
+
+        let model = DecisionTree::params().fit(&dataset);
+        assert_eq!(model.classes(), &[1, 0, 2]);
+
+        let proba = model.predict_proba(&array![[0.], [1.]]);
+        assert_abs_diff_eq!(proba, array![[0.5, 0.5, 0.0], [0.0, 0.0, 1.0]]);
+
+        let leaf = model.leaf_for(&array![0.]);
+        let mut class_freq = leaf.class_frequencies().to_vec();
+        class_freq.sort_by_key(|x| x.0);
+        assert_eq!(class_freq, vec![(0, 2.0), (1, 2.0)]);
+    }
+
+    #[test]
+    /// Leaves with the same modal class, but different probabilities are kept
+    fn leaves_keep_probabilities() {
+        let data = array![[0.], [0.], [0.], [0.], [1.], [1.], [1.], [1.]];
+        let targets = vec![0, 0, 0, 0, 0, 0, 0, 1];
+        let dataset = Dataset::new(data, targets);
+
+        let model = DecisionTree::params().fit(&dataset);
+        assert_eq!(model.num_leaves(), 2);
+        assert_eq!(model.predict(&array![[0.], [1.]]), vec![0, 0]);
+
+        let proba = model.predict_proba(&array![[0.], [1.]]);
+        assert_abs_diff_eq!(proba, array![[1.0, 0.0], [0.75, 0.25]]);
+    }
+
+    #[test]
+    /// A leaf without weight has no class distribution instead of NaN probabilities
+    fn predict_proba_zero_weight() {
+        let data = array![[0.], [1.]];
+        let dataset = Dataset::new(data.clone(), vec![0, 1]).with_weights(vec![0.0, 0.0]);
+
+        let model = DecisionTree::params().fit(&dataset);
+        assert_eq!(model.predict_proba(&data), Array2::<f64>::zeros((2, 2)));
+    }
+
+    #[test]
+    /// The probabilities of a shallow tree rank noisy samples for a ROC curve
+    fn predict_proba_roc() {
+        let mut rng = Isaac64Rng::seed_from_u64(42);
+        let data = Array::random_using((200, 2), Uniform::new(-1., 1.), &mut rng);
+        let noise = Array1::random_using(200, Uniform::new(-0.5, 0.5), &mut rng);
+        let targets = data
+            .column(0)
+            .iter()
+            .zip(noise.iter())
+            .map(|(x, noise)| x + noise > 0.0)
+            .collect::<Vec<_>>();
+        let dataset = Dataset::new(data.clone(), targets);
+
+        let model = DecisionTree::params().max_depth(Some(3)).fit(&dataset);
+        let proba = model.predict_proba(&data);
+        for row in proba.genrows() {
+            assert_abs_diff_eq!(row.sum(), 1.0, epsilon = 1e-6);
+        }
+
+        let positive = model.classes().iter().position(|x| *x).unwrap();
+        let scores = proba
+            .column(positive)
+            .iter()
+            .map(|x| Pr(*x as f32))
+            .collect::<Vec<_>>();
+        let roc = scores.as_slice().roc(dataset.targets().as_slice());
+        assert!(roc.area_under_curve() > 0.9);
+    }
 }
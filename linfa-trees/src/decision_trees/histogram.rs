@@ -0,0 +1,140 @@
+//! Histogram split search
+//!
+//! Instead of sorting each feature, the values of a feature are assigned to at most 255 bins
+//! between quantiles of the training samples. A node then only accumulates the class
+//! frequencies of each bin and considers the thresholds between non-empty bins. Missing
+//! values, encoded as NaN, are kept in a separate bin and routed to the side of each split
+//! which decreases the impurity most, or split from all other values.
+use std::collections::HashMap;
+
+use ndarray::{ArrayBase, Axis, Data, Ix2};
+
+use super::algorithm::RowMask;
+use linfa::{dataset::Labels, Dataset, Float, Label};
+
+/// The bin of missing values
+const MISSING: u8 = u8::MAX;
+
+/// Quantile bins of the values of a particular feature
+pub(crate) struct BinnedFeature<F: Float> {
+    bins: Vec<u8>,
+    lower: Vec<F>,
+    upper: Vec<F>,
+}
+
+/// Weighted class frequencies of each bin and of the missing values
+pub(crate) struct Histogram<'a, L> {
+    pub(crate) bins: Vec<HashMap<&'a L, f32>>,
+    pub(crate) missing: HashMap<&'a L, f32>,
+}
+
+impl<F: Float> BinnedFeature<F> {
+    /// Assign the values of a feature to at most `max_bins` bins of equal frequency
+    pub(crate) fn of_array_column(
+        x: &ArrayBase<impl Data<Elem = F>, Ix2>,
+        feature_idx: usize,
+        max_bins: usize,
+    ) -> Self {
+        let column = x.index_axis(Axis(1), feature_idx);
+        let mut values = column
+            .iter()
+            .filter(|x| !x.is_nan())
+            .cloned()
+            .collect::<Vec<_>>();
+        values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        let mut distinct = values.clone();
+        distinct.dedup();
+
+        // a value belongs to the bin of the number of cuts lower or equal to it
+        let mut cuts = if distinct.len() <= max_bins {
+            distinct.into_iter().skip(1).collect::<Vec<_>>()
+        } else {
+            (1..max_bins)
+                .map(|bin| values[bin * values.len() / max_bins])
+                .collect()
+        };
+        cuts.dedup();
+
+        let num_bins = cuts.len() + 1;
+        let mut lower = vec![F::infinity(); num_bins];
+        let mut upper = vec![F::neg_infinity(); num_bins];
+        let bins = column
+            .iter()
+            .map(|x| {
+                if x.is_nan() {
+                    return MISSING;
+                }
+
+                let bin = cuts.partition_point(|cut| *cut <= *x);
+                lower[bin] = F::min(lower[bin], *x);
+                upper[bin] = F::max(upper[bin], *x);
+
+                bin as u8
+            })
+            .collect();
+
+        BinnedFeature { bins, lower, upper }
+    }
+
+    /// Number of bins, excluding the bin of missing values
+    pub(crate) fn num_bins(&self) -> usize {
+        self.lower.len()
+    }
+
+    /// Threshold between the largest value of a bin and the smallest value of a following bin
+    pub(crate) fn threshold(&self, bin: usize, next: usize) -> F {
+        (self.upper[bin] + self.lower[next]) / F::from(2.0).unwrap()
+    }
+
+    /// Accumulate the weighted class frequencies of the samples in `mask`
+    ///
+    /// Only the samples of the node are visited, the cost is linear in their number.
+    pub(crate) fn histogram<'a, D: Data<Elem = F>, L: Label, T: Labels<Elem = L>>(
+        &self,
+        data: &'a Dataset<ArrayBase<D, Ix2>, T>,
+        mask: &RowMask,
+    ) -> Histogram<'a, L> {
+        let mut histogram = Histogram {
+            bins: vec![HashMap::new(); self.num_bins()],
+            missing: HashMap::new(),
+        };
+
+        for &idx in mask.marked() {
+            let class_freq = match self.bins[idx] {
+                MISSING => &mut histogram.missing,
+                bin => &mut histogram.bins[bin as usize],
+            };
+            *class_freq.entry(data.target(idx)).or_insert(0.0) += data.weight_for(idx);
+        }
+
+        histogram
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ndarray::{array, Array, Axis};
+
+    #[test]
+    fn distinct_values_get_own_bins() {
+        let x = array![[3.0], [1.0], [2.0], [1.0], [f64::NAN]];
+        let binned = BinnedFeature::of_array_column(&x, 0, 255);
+
+        assert_eq!(binned.num_bins(), 3);
+        assert_eq!(binned.bins, vec![2, 0, 1, 0, MISSING]);
+        assert_eq!(binned.threshold(0, 1), 1.5);
+    }
+
+    #[test]
+    fn quantile_bins() {
+        let x = Array::linspace(0., 999., 1000).insert_axis(Axis(1));
+        let binned = BinnedFeature::of_array_column(&x, 0, 4);
+
+        assert_eq!(binned.num_bins(), 4);
+        assert_eq!(binned.lower, vec![0.0, 250.0, 500.0, 750.0]);
+        assert_eq!(binned.upper, vec![249.0, 499.0, 749.0, 999.0]);
+    }
+}
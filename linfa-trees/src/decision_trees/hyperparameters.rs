@@ -19,6 +19,22 @@ pub enum SplitQuality {
     Entropy,
 }
 
+/// The possible strategies to search for the best split of a node.
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SplitSearch {
+    /// Consider the thresholds between all consecutive values of a feature
+    Exact,
+    /// Consider only the thresholds between quantile bins, with at most the given number of
+    /// bins per feature. Missing values, encoded as NaN, are routed to the side of a split
+    /// which decreases the impurity most.
+    Histogram(usize),
+}
+
 /// The set of hyperparameters that can be specified for fitting a
 /// [decision tree](struct.DecisionTree.html).
 #[cfg_attr(
@@ -29,6 +45,7 @@ pub enum SplitQuality {
 #[derive(Clone, Copy, Debug)]
 pub struct DecisionTreeParams<F, L> {
     pub split_quality: SplitQuality,
+    pub split_search: SplitSearch,
     pub max_depth: Option<usize>,
     pub min_weight_split: f32,
     pub min_weight_leaf: f32,
@@ -42,6 +59,90 @@ impl<F: Float, L: Label> DecisionTreeParams<F, L> {
         self
     }
 
+    pub fn split_search(mut self, split_search: SplitSearch) -> Self {
+        self.split_search = split_search;
+        self
+    }
+
+    pub fn max_depth(mut self, max_depth: Option<usize>) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    pub fn min_weight_split(mut self, min_weight_split: f32) -> Self {
+        self.min_weight_split = min_weight_split;
+        self
+    }
+
+    pub fn min_weight_leaf(mut self, min_weight_leaf: f32) -> Self {
+        self.min_weight_leaf = min_weight_leaf;
+        self
+    }
+
+    pub fn min_impurity_decrease(mut self, min_impurity_decrease: F) -> Self {
+        self.min_impurity_decrease = min_impurity_decrease;
+        self
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.min_impurity_decrease < F::epsilon() {
+            return Err(Error::Parameters(format!(
+                "Minimum impurity decrease should be greater than zero, but was {}",
+                self.min_impurity_decrease
+            )));
+        }
+
+        if let SplitSearch::Histogram(max_bins) = self.split_search {
+            if !(2..=255).contains(&max_bins) {
+                return Err(Error::Parameters(format!(
+                    "Number of histogram bins should be between 2 and 255, but was {}",
+                    max_bins
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The possible impurity measures for training regression trees.
+///
+/// Leaves predict the weighted median of their targets for `Mae` and the weighted mean
+/// otherwise. The Poisson deviance requires non-negative targets.
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
+#[derive(Clone, Copy, Debug)]
+pub enum RegressionCriterion {
+    Mse,
+    Mae,
+    Poisson,
+}
+
+/// The set of hyperparameters that can be specified for fitting a
+/// [regression tree](struct.RegressionTree.html).
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
+#[derive(Clone, Copy, Debug)]
+pub struct RegressionTreeParams<F> {
+    pub criterion: RegressionCriterion,
+    pub max_depth: Option<usize>,
+    pub min_weight_split: f32,
+    pub min_weight_leaf: f32,
+    pub min_impurity_decrease: F,
+}
+
+impl<F: Float> RegressionTreeParams<F> {
+    pub fn criterion(mut self, criterion: RegressionCriterion) -> Self {
+        self.criterion = criterion;
+        self
+    }
+
     pub fn max_depth(mut self, max_depth: Option<usize>) -> Self {
         self.max_depth = max_depth;
         self
@@ -1,9 +1,12 @@
 mod algorithm;
+mod histogram;
 mod hyperparameters;
 mod iter;
+mod regression;
 mod tikz;
 
 pub use algorithm::*;
 pub use hyperparameters::*;
 pub use iter::*;
+pub use regression::*;
 pub use tikz::*;
@@ -0,0 +1,808 @@
+//! Regression trees
+//!
+//! A regression tree splits the observations such that the weighted impurity of the targets in
+//! both children is minimal. The impurity is measured by the [criterion](enum.RegressionCriterion.html)
+//! of the hyperparameters, leaves predict the weighted mean or median of their targets.
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+
+use ndarray::{Array1, ArrayBase, Data, Ix1, Ix2};
+
+use super::algorithm::{RowMask, SortedIndex};
+use super::hyperparameters::{RegressionCriterion, RegressionTreeParams};
+use linfa::{
+    dataset::{Records, Targets},
+    traits::*,
+    Dataset, Float,
+};
+
+#[cfg(feature = "serde")]
+use serde_crate::{Deserialize, Serialize};
+
+/// Weighted sums of the targets in a set of observations
+#[derive(Clone, Copy, Debug)]
+struct Moments<F> {
+    weight: F,
+    sum: F,
+    sum_sq: F,
+    sum_log: F,
+}
+
+impl<F: Float> Moments<F> {
+    fn zero() -> Self {
+        Moments {
+            weight: F::zero(),
+            sum: F::zero(),
+            sum_sq: F::zero(),
+            sum_log: F::zero(),
+        }
+    }
+
+    fn add(&mut self, target: F, weight: F) {
+        self.weight += weight;
+        self.sum += weight * target;
+        self.sum_sq += weight * target * target;
+        if target > F::zero() {
+            self.sum_log += weight * target * target.ln();
+        }
+    }
+
+    fn mean(&self) -> F {
+        self.sum / self.weight
+    }
+
+    /// Weighted variance, the mean squared error of predicting the mean
+    fn variance(&self) -> F {
+        let mean = self.mean();
+        F::max(self.sum_sq / self.weight - mean * mean, F::zero())
+    }
+
+    /// Half of the mean Poisson deviance of predicting the mean, undefined for a mean which is
+    /// not positive
+    fn poisson_deviance(&self) -> Option<F> {
+        let mean = self.mean();
+        if mean > F::zero() {
+            Some(F::max(
+                self.sum_log / self.weight - mean * mean.ln(),
+                F::zero(),
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+/// Float ordered by `partial_cmp`, the targets must not be NaN
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Ordered<F>(F);
+
+impl<F: Float> Eq for Ordered<F> {}
+
+impl<F: Float> PartialOrd for Ordered<F> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<F: Float> Ord for Ordered<F> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.partial_cmp(&other.0).unwrap()
+    }
+}
+
+/// Running weighted median of a set of targets
+///
+/// The targets up to the median are kept in a max-heap, the remaining ones in a min-heap.
+/// Together with the weighted sums of both halves this gives the mean absolute deviation.
+#[derive(Debug)]
+struct WeightedMedian<F> {
+    lower: BinaryHeap<(Ordered<F>, Ordered<F>)>,
+    upper: BinaryHeap<Reverse<(Ordered<F>, Ordered<F>)>>,
+    lower_weight: F,
+    lower_sum: F,
+    upper_weight: F,
+    upper_sum: F,
+}
+
+impl<F: Float> WeightedMedian<F> {
+    fn new() -> Self {
+        WeightedMedian {
+            lower: BinaryHeap::new(),
+            upper: BinaryHeap::new(),
+            lower_weight: F::zero(),
+            lower_sum: F::zero(),
+            upper_weight: F::zero(),
+            upper_sum: F::zero(),
+        }
+    }
+
+    fn push_lower(&mut self, target: F, weight: F) {
+        self.lower_weight += weight;
+        self.lower_sum += weight * target;
+        self.lower.push((Ordered(target), Ordered(weight)));
+    }
+
+    fn push_upper(&mut self, target: F, weight: F) {
+        self.upper_weight += weight;
+        self.upper_sum += weight * target;
+        self.upper.push(Reverse((Ordered(target), Ordered(weight))));
+    }
+
+    fn add(&mut self, target: F, weight: F) {
+        match self.lower.peek() {
+            Some((median, _)) if target > median.0 => self.push_upper(target, weight),
+            _ => self.push_lower(target, weight),
+        }
+
+        // the median is the smallest target with at least half of the weight up to it
+        let half = (self.lower_weight + self.upper_weight) / F::from(2.0).unwrap();
+        loop {
+            if self.lower_weight < half {
+                let Reverse((target, weight)) = self.upper.pop().unwrap();
+                self.upper_weight -= weight.0;
+                self.upper_sum -= weight.0 * target.0;
+                self.push_lower(target.0, weight.0);
+            } else if self.lower.len() > 1
+                && self.lower_weight - (self.lower.peek().unwrap().1).0 >= half
+            {
+                let (target, weight) = self.lower.pop().unwrap();
+                self.lower_weight -= weight.0;
+                self.lower_sum -= weight.0 * target.0;
+                self.push_upper(target.0, weight.0);
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn median(&self) -> F {
+        (self.lower.peek().unwrap().0).0
+    }
+
+    /// Weighted mean absolute deviation from the median
+    fn absolute_deviation(&self) -> F {
+        let median = self.median();
+        let deviation = median * self.lower_weight - self.lower_sum + self.upper_sum
+            - median * self.upper_weight;
+
+        deviation / (self.lower_weight + self.upper_weight)
+    }
+}
+
+/// Impurity of a set of targets, to which observations are added one at a time
+struct Impurity<F> {
+    criterion: RegressionCriterion,
+    moments: Moments<F>,
+    median: WeightedMedian<F>,
+}
+
+impl<F: Float> Impurity<F> {
+    fn new(criterion: RegressionCriterion) -> Self {
+        Impurity {
+            criterion,
+            moments: Moments::zero(),
+            median: WeightedMedian::new(),
+        }
+    }
+
+    fn add(&mut self, target: F, weight: F) {
+        self.moments.add(target, weight);
+        if let RegressionCriterion::Mae = self.criterion {
+            self.median.add(target, weight);
+        }
+    }
+
+    fn weight(&self) -> F {
+        self.moments.weight
+    }
+
+    /// Return the impurity, `None` if it is undefined for these targets
+    fn impurity(&self) -> Option<F> {
+        match self.criterion {
+            RegressionCriterion::Mse => Some(self.moments.variance()),
+            RegressionCriterion::Mae => Some(self.median.absolute_deviation()),
+            RegressionCriterion::Poisson => self.moments.poisson_deviance(),
+        }
+    }
+
+    /// Return the constant prediction minimizing the impurity
+    fn prediction(&self) -> F {
+        match self.criterion {
+            RegressionCriterion::Mae => self.median.median(),
+            _ => self.moments.mean(),
+        }
+    }
+}
+
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
+#[derive(Debug, Clone)]
+/// A node in the regression tree
+pub struct RegressionNode<F> {
+    feature_idx: usize,
+    split_value: F,
+    impurity_decrease: F,
+    left_child: Option<Box<RegressionNode<F>>>,
+    right_child: Option<Box<RegressionNode<F>>>,
+    leaf_node: bool,
+    prediction: F,
+    depth: usize,
+    /// Position among the leaves of the tree in depth-first order
+    leaf_id: usize,
+}
+
+impl<F: Float> RegressionNode<F> {
+    fn empty_leaf(prediction: F, depth: usize) -> Self {
+        RegressionNode {
+            feature_idx: 0,
+            split_value: F::zero(),
+            impurity_decrease: F::zero(),
+            left_child: None,
+            right_child: None,
+            leaf_node: true,
+            prediction,
+            depth,
+            leaf_id: 0,
+        }
+    }
+
+    pub fn is_leaf(&self) -> bool {
+        self.leaf_node
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn prediction(&self) -> Option<F> {
+        if self.is_leaf() {
+            Some(self.prediction)
+        } else {
+            None
+        }
+    }
+
+    /// Return both childs
+    pub fn childs(&self) -> Vec<&Option<Box<RegressionNode<F>>>> {
+        vec![&self.left_child, &self.right_child]
+    }
+
+    /// Return the split and its impurity decrease
+    pub fn split(&self) -> (usize, F, F) {
+        (self.feature_idx, self.split_value, self.impurity_decrease)
+    }
+
+    /// Fit a node on the samples in `mask`
+    ///
+    /// The candidate features of each split are drawn from `features`.
+    fn fit<D: Data<Elem = F>, T: Targets<Elem = F>>(
+        data: &Dataset<ArrayBase<D, Ix2>, T>,
+        mask: &RowMask,
+        hyperparameters: &RegressionTreeParams<F>,
+        sorted_indices: &[SortedIndex<F>],
+        depth: usize,
+        features: &mut dyn FnMut() -> Vec<usize>,
+    ) -> Self {
+        let criterion = hyperparameters.criterion;
+        let weight_for = |idx: usize| F::from(data.weight_for(idx)).unwrap();
+
+        let mut parent = Impurity::new(criterion);
+        for &idx in mask.marked() {
+            parent.add(*data.target(idx), weight_for(idx));
+        }
+        let prediction = parent.prediction();
+
+        // return empty leaf when we don't have enough samples or the maximal depth is reached
+        if (mask.nsamples() as f32) < hyperparameters.min_weight_split
+            || hyperparameters
+                .max_depth
+                .map(|max_depth| depth >= max_depth)
+                .unwrap_or(false)
+        {
+            return Self::empty_leaf(prediction, depth);
+        }
+
+        let min_weight_leaf = F::from(hyperparameters.min_weight_leaf).unwrap();
+
+        // Find best split for current level
+        let mut best = None;
+
+        // Iterate over features
+        for feature_idx in features() {
+            let sorted_values = sorted_indices[feature_idx]
+                .sorted_values
+                .iter()
+                .filter(|(idx, _)| mask.is_marked(*idx))
+                .cloned()
+                .collect::<Vec<_>>();
+
+            // The impurity of the right side, when it starts at each of the sorted values
+            let mut right = Impurity::new(criterion);
+            let mut right_impurity = vec![(F::zero(), None); sorted_values.len()];
+            for (i, (idx, _)) in sorted_values.iter().enumerate().rev() {
+                right.add(*data.target(*idx), weight_for(*idx));
+                right_impurity[i] = (right.weight(), right.impurity());
+            }
+
+            // Iterate over sorted values, moving one sample after the other to the left side
+            let mut left = Impurity::new(criterion);
+            for (i, window) in sorted_values.windows(2).enumerate() {
+                let ((presorted_index, value), (_, next_value)) = (window[0], window[1]);
+
+                left.add(*data.target(presorted_index), weight_for(presorted_index));
+
+                // Continue if the next values is equal
+                if (value - next_value).abs() < F::from(1e-5).unwrap() {
+                    continue;
+                }
+
+                // If the split would result in too few samples in a leaf
+                // then skip computing the quality
+                let (right_weight, right_impurity) = right_impurity[i + 1];
+                if left.weight() < min_weight_leaf
+                    || right_weight < min_weight_leaf
+                    || right_weight <= F::zero()
+                {
+                    continue;
+                }
+
+                // Skip splits with an undefined impurity, e.g. children without any positive
+                // target for the Poisson deviance
+                let (left_impurity, right_impurity) = match (left.impurity(), right_impurity) {
+                    (Some(left), Some(right)) => (left, right),
+                    _ => continue,
+                };
+
+                // Weight the impurities based on the weight of each subset
+                let score = (left.weight() * left_impurity + right_weight * right_impurity)
+                    / parent.weight();
+
+                // Take the midpoint from this value and the next one as split_value
+                let split_value = (value + next_value) / F::from(2.0).unwrap();
+
+                // override best indices when score improved
+                best = match best.take() {
+                    None => Some((feature_idx, split_value, score)),
+                    Some((_, _, best_score)) if score < best_score => {
+                        Some((feature_idx, split_value, score))
+                    }
+                    x => x,
+                };
+            }
+        }
+
+        let impurity_decrease = match (best, parent.impurity()) {
+            (Some((_, _, best_score)), Some(parent_impurity)) => parent_impurity - best_score,
+            _ => F::zero(),
+        };
+
+        // return empty leaf if impurity has not decreased enough
+        if impurity_decrease < hyperparameters.min_impurity_decrease {
+            return Self::empty_leaf(prediction, depth);
+        }
+
+        let (best_feature_idx, best_split_value, _) = best.unwrap();
+        // determine new masks for the left and right subtrees
+        let mut left_mask = RowMask::none(data.observations());
+        let mut right_mask = RowMask::none(data.observations());
+
+        for &i in mask.marked() {
+            if data.records()[(i, best_feature_idx)] <= best_split_value {
+                left_mask.mark(i);
+            } else {
+                right_mask.mark(i);
+            }
+        }
+
+        let left_child = RegressionNode::fit(
+            data,
+            &left_mask,
+            hyperparameters,
+            sorted_indices,
+            depth + 1,
+            features,
+        );
+        let right_child = RegressionNode::fit(
+            data,
+            &right_mask,
+            hyperparameters,
+            sorted_indices,
+            depth + 1,
+            features,
+        );
+
+        RegressionNode {
+            feature_idx: best_feature_idx,
+            split_value: best_split_value,
+            impurity_decrease,
+            left_child: Some(Box::new(left_child)),
+            right_child: Some(Box::new(right_child)),
+            leaf_node: false,
+            prediction,
+            depth,
+            leaf_id: 0,
+        }
+    }
+
+    /// Collect this node and all of its descendants
+    fn collect<'a>(&'a self, nodes: &mut Vec<&'a RegressionNode<F>>) {
+        nodes.push(self);
+        for child in self.childs().into_iter().filter_map(|x| x.as_ref()) {
+            child.collect(nodes);
+        }
+    }
+
+    /// Number the leaves below this node in depth-first order, starting with `next`
+    fn number_leaves(&mut self, next: &mut usize) {
+        if self.leaf_node {
+            self.leaf_id = *next;
+            *next += 1;
+        } else {
+            for child in self.childs_mut() {
+                child.number_leaves(next);
+            }
+        }
+    }
+
+    /// Replace the predictions of this node's leaves with the value of their leaf id
+    fn set_leaf_predictions(&mut self, predictions: &[F]) {
+        if self.leaf_node {
+            self.prediction = predictions[self.leaf_id];
+        } else {
+            for child in self.childs_mut() {
+                child.set_leaf_predictions(predictions);
+            }
+        }
+    }
+
+    fn childs_mut(&mut self) -> impl Iterator<Item = &mut RegressionNode<F>> {
+        vec![&mut self.left_child, &mut self.right_child]
+            .into_iter()
+            .filter_map(|x| x.as_deref_mut())
+    }
+}
+
+/// A fitted regression tree model.
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
+#[derive(Debug)]
+pub struct RegressionTree<F: Float> {
+    root_node: RegressionNode<F>,
+    num_features: usize,
+}
+
+impl<F: Float, D: Data<Elem = F>> Predict<ArrayBase<D, Ix2>, Array1<F>> for RegressionTree<F> {
+    /// Make predictions for each row of a matrix of features `x`.
+    fn predict(&self, x: ArrayBase<D, Ix2>) -> Array1<F> {
+        x.genrows()
+            .into_iter()
+            .map(|row| self.leaf_for(&row).prediction)
+            .collect()
+    }
+}
+
+impl<F: Float, D: Data<Elem = F>> Predict<&ArrayBase<D, Ix2>, Array1<F>> for RegressionTree<F> {
+    fn predict(&self, x: &ArrayBase<D, Ix2>) -> Array1<F> {
+        self.predict(x.view())
+    }
+}
+
+impl<'a, F: Float, D: Data<Elem = F>, T: Targets<Elem = F>> Fit<'a, ArrayBase<D, Ix2>, T>
+    for RegressionTreeParams<F>
+{
+    type Object = RegressionTree<F>;
+
+    /// Fit a regression tree using `hyperparamters` on the dataset consisting of
+    /// a matrix of features `x` and an array of targets `y`.
+    fn fit(&self, dataset: &Dataset<ArrayBase<D, Ix2>, T>) -> Self::Object {
+        self.validate().unwrap();
+
+        let x = dataset.records();
+        let sorted_indices: Vec<_> = (0..(x.ncols()))
+            .map(|feature_idx| SortedIndex::of_array_column(x, feature_idx))
+            .collect();
+
+        let nfeatures = x.ncols();
+        self.fit_with_features(
+            dataset,
+            &RowMask::all(x.nrows()),
+            &sorted_indices,
+            &mut || (0..nfeatures).collect(),
+        )
+    }
+}
+
+impl<F: Float> RegressionTreeParams<F> {
+    /// Fit a tree on the samples in `mask`, the candidate features of each split are drawn from
+    /// `features`
+    pub(crate) fn fit_with_features<D: Data<Elem = F>, T: Targets<Elem = F>>(
+        &self,
+        dataset: &Dataset<ArrayBase<D, Ix2>, T>,
+        mask: &RowMask,
+        sorted_indices: &[SortedIndex<F>],
+        features: &mut dyn FnMut() -> Vec<usize>,
+    ) -> RegressionTree<F> {
+        if let RegressionCriterion::Poisson = self.criterion {
+            assert!(
+                dataset.targets().as_slice().iter().all(|x| *x >= F::zero()),
+                "Poisson criterion requires non-negative targets"
+            );
+        }
+
+        let mut root_node = RegressionNode::fit(dataset, mask, self, sorted_indices, 0, features);
+        root_node.number_leaves(&mut 0);
+
+        RegressionTree {
+            root_node,
+            num_features: dataset.records().ncols(),
+        }
+    }
+}
+
+impl<F: Float> RegressionTree<F> {
+    /// Defaults are provided if the optional parameters are not specified:
+    /// * `criterion = RegressionCriterion::Mse`
+    /// * `max_depth = None`
+    /// * `min_weight_split = 2.0`
+    /// * `min_weight_leaf = 1.0`
+    /// * `min_impurity_decrease = 0.00001`
+    // Violates the convention that new should return a value of type `Self`
+    #[allow(clippy::new_ret_no_self)]
+    pub fn params() -> RegressionTreeParams<F> {
+        RegressionTreeParams {
+            criterion: RegressionCriterion::Mse,
+            max_depth: None,
+            min_weight_split: 2.0,
+            min_weight_leaf: 1.0,
+            min_impurity_decrease: F::from(0.00001).unwrap(),
+        }
+    }
+
+    /// Return all nodes of the tree in depth-first order
+    pub fn nodes(&self) -> Vec<&RegressionNode<F>> {
+        let mut nodes = Vec::new();
+        self.root_node.collect(&mut nodes);
+
+        nodes
+    }
+
+    /// Return the mean impurity decrease for each feature
+    pub fn mean_impurity_decrease(&self) -> Vec<F> {
+        // total impurity decrease for each feature
+        let mut impurity_decrease = vec![F::zero(); self.num_features];
+        let mut num_nodes = vec![0; self.num_features];
+
+        for node in self.nodes().into_iter().filter(|node| !node.leaf_node) {
+            impurity_decrease[node.feature_idx] += node.impurity_decrease;
+            num_nodes[node.feature_idx] += 1;
+        }
+
+        impurity_decrease
+            .into_iter()
+            .zip(num_nodes)
+            .map(|(val, n)| {
+                if n == 0 {
+                    F::zero()
+                } else {
+                    val / F::from(n).unwrap()
+                }
+            })
+            .collect()
+    }
+
+    /// Return the feature importance, i.e. the relative impurity decrease, for each feature
+    pub fn feature_importance(&self) -> Vec<F> {
+        let mean_impurity_decrease = self.mean_impurity_decrease();
+        let sum = mean_impurity_decrease.iter().cloned().sum();
+
+        mean_impurity_decrease
+            .into_iter()
+            .map(|x| x / sum)
+            .collect()
+    }
+
+    /// Return root node of the tree
+    pub fn root_node(&self) -> &RegressionNode<F> {
+        &self.root_node
+    }
+
+    /// Return max depth of the tree
+    pub fn max_depth(&self) -> usize {
+        self.nodes()
+            .into_iter()
+            .fold(0, |max, node| usize::max(max, node.depth))
+    }
+
+    /// Return the number of leaves in this tree
+    pub fn num_leaves(&self) -> usize {
+        self.nodes()
+            .into_iter()
+            .filter(|node| node.is_leaf())
+            .count()
+    }
+
+    /// Return the leaf reached by a sample
+    pub(crate) fn leaf_for(&self, x: &ArrayBase<impl Data<Elem = F>, Ix1>) -> &RegressionNode<F> {
+        let mut node = &self.root_node;
+        while !node.leaf_node {
+            node = if x[node.feature_idx] < node.split_value {
+                node.left_child.as_ref().unwrap()
+            } else {
+                node.right_child.as_ref().unwrap()
+            };
+        }
+
+        node
+    }
+
+    /// Return the position of the leaf reached by a sample among the leaves in depth-first order
+    pub(crate) fn leaf_index(&self, x: &ArrayBase<impl Data<Elem = F>, Ix1>) -> usize {
+        self.leaf_for(x).leaf_id
+    }
+
+    /// Replace the prediction of each leaf, the leaves are ordered depth-first
+    pub(crate) fn set_leaf_predictions(&mut self, predictions: &[F]) {
+        self.root_node.set_leaf_predictions(predictions);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use approx::assert_abs_diff_eq;
+    use linfa::metrics::Regression;
+    use ndarray::{array, Array, Axis};
+    use ndarray_rand::{rand::SeedableRng, rand_distr::Uniform, RandomExt};
+    use rand_isaac::Isaac64Rng;
+
+    #[test]
+    /// A step function is learned exactly with a single split
+    fn step_function() {
+        let data = Array::linspace(0., 1., 20).insert_axis(Axis(1));
+        let targets = (0..20)
+            .map(|x| if x < 10 { 1.0 } else { 3.0 })
+            .collect::<Array1<_>>();
+
+        let dataset = Dataset::new(data.clone(), targets.clone());
+        let model = RegressionTree::params().fit(&dataset);
+
+        assert_eq!(model.num_leaves(), 2);
+        assert_eq!(model.max_depth(), 1);
+        assert_abs_diff_eq!(model.predict(&data), targets, epsilon = 1e-10);
+        // the variance decreases from one to zero
+        assert_abs_diff_eq!(model.root_node().split().2, 1.0, epsilon = 1e-10);
+    }
+
+    #[test]
+    /// Leaves predict the weighted mean of their targets
+    fn weighted_mean_leaf() {
+        let data = array![[0.], [0.], [0.]];
+        let targets = array![1.0, 2.0, 4.0];
+
+        let dataset = Dataset::new(data.clone(), targets).with_weights(vec![1.0, 1.0, 2.0]);
+        let model = RegressionTree::params().fit(&dataset);
+
+        assert_eq!(model.num_leaves(), 1);
+        assert_abs_diff_eq!(
+            model.predict(&data),
+            array![2.75, 2.75, 2.75],
+            epsilon = 1e-10
+        );
+    }
+
+    #[test]
+    /// The informative feature dominates and the maximal depth is respected
+    fn single_feature_random_noise() {
+        let mut rng = Isaac64Rng::seed_from_u64(42);
+        let data = Array::random_using((100, 5), Uniform::new(-1., 1.), &mut rng);
+        let targets = data.column(2).mapv(|x: f64| x * x);
+
+        let dataset = Dataset::new(data.clone(), targets.clone());
+        let model = RegressionTree::params().max_depth(Some(4)).fit(&dataset);
+
+        assert_eq!(model.max_depth(), 4);
+        let importance = model.feature_importance();
+        assert!(importance[2] > 0.8);
+        assert!(model.predict(&data).mean_squared_error(&targets) < 1e-2);
+    }
+
+    #[test]
+    /// Leaves are numbered in depth-first order and their predictions can be replaced
+    fn numbered_leaves() {
+        let data = Array::linspace(0., 1., 8).insert_axis(Axis(1));
+        let targets = array![0., 0., 1., 1., 2., 2., 3., 3.];
+
+        let dataset = Dataset::new(data.clone(), targets.clone());
+        let mut model = RegressionTree::params().fit(&dataset);
+
+        assert_eq!(model.num_leaves(), 4);
+        let leaves = data
+            .genrows()
+            .into_iter()
+            .map(|row| model.leaf_index(&row))
+            .collect::<Vec<_>>();
+        assert_eq!(leaves, vec![0, 0, 1, 1, 2, 2, 3, 3]);
+
+        model.set_leaf_predictions(&[3., 2., 1., 0.]);
+        assert_abs_diff_eq!(model.predict(&data), 3. - targets, epsilon = 1e-10);
+    }
+
+    #[test]
+    /// The weighted median is maintained while adding targets in any order
+    fn running_weighted_median() {
+        let mut median = WeightedMedian::new();
+        for (target, weight) in &[(5.0, 1.0), (1.0, 1.0), (3.0, 1.0)] {
+            median.add(*target, *weight);
+        }
+        assert_abs_diff_eq!(median.median(), 3.0);
+        // |5 - 3| + |1 - 3| + |3 - 3| over three samples
+        assert_abs_diff_eq!(median.absolute_deviation(), 4.0 / 3.0, epsilon = 1e-10);
+
+        // a heavy sample pulls the median towards itself
+        median.add(10.0, 4.0);
+        assert_abs_diff_eq!(median.median(), 10.0);
+        assert_abs_diff_eq!(median.absolute_deviation(), 21.0 / 7.0, epsilon = 1e-10);
+    }
+
+    #[test]
+    /// Leaves of the MAE criterion predict the weighted median, ignoring outliers
+    fn mae_median_leaf() {
+        let data = Array::linspace(0., 1., 10).insert_axis(Axis(1));
+        let targets = array![1.0, 1.0, 100.0, 1.0, 1.0, 2.0, 2.0, 2.0, 2.0, 2.0];
+
+        let dataset = Dataset::new(data.clone(), targets);
+        let model = RegressionTree::params()
+            .criterion(RegressionCriterion::Mae)
+            .max_depth(Some(1))
+            .fit(&dataset);
+
+        // the outlier does not shift the split and the prediction of the left side
+        assert_eq!(model.root_node().split().0, 0);
+        assert_abs_diff_eq!(
+            model.predict(&data),
+            array![1.0, 1.0, 1.0, 1.0, 1.0, 2.0, 2.0, 2.0, 2.0, 2.0],
+            epsilon = 1e-10
+        );
+    }
+
+    #[test]
+    /// The Poisson deviance separates counts with different rates
+    fn poisson_counts() {
+        let data = Array::linspace(0., 1., 20).insert_axis(Axis(1));
+        let targets = (0..20)
+            .map(|x| {
+                if x < 10 {
+                    (x % 2) as f64
+                } else {
+                    10.0 + (x % 3) as f64
+                }
+            })
+            .collect::<Array1<_>>();
+
+        let dataset = Dataset::new(data.clone(), targets);
+        let model = RegressionTree::params()
+            .criterion(RegressionCriterion::Poisson)
+            .max_depth(Some(1))
+            .fit(&dataset);
+
+        let predicted = model.predict(&data);
+        assert_abs_diff_eq!(predicted[0], 0.5, epsilon = 1e-10);
+        assert_abs_diff_eq!(predicted[19], 11.0, epsilon = 1e-10);
+    }
+
+    #[test]
+    #[should_panic]
+    fn poisson_negative_targets() {
+        let dataset = Dataset::new(array![[0.], [1.]], array![-1.0, 1.0]);
+        RegressionTree::params()
+            .criterion(RegressionCriterion::Poisson)
+            .fit(&dataset);
+    }
+}
@@ -0,0 +1,1042 @@
+//! Gradient boosted trees
+//!
+//! Gradient boosting fits an additive model in stages. Each stage fits a regression tree to the
+//! negative gradient of the loss with respect to the current predictions, re-estimates the
+//! values of its leaves for the loss and adds them, shrunken by the learning rate, to the
+//! predictions.
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+use ndarray::{Array1, Array2, ArrayBase, ArrayView1, Axis, Data, Ix2};
+use ndarray_rand::rand::{seq::index, SeedableRng};
+use rand_isaac::Isaac64Rng;
+
+use super::hyperparameters::{
+    GradientBoostingParams, GradientBoostingRegressorParams, RegressionLoss,
+};
+use crate::decision_trees::{
+    classes_of, RegressionTree, RegressionTreeParams, RowMask, SortedIndex,
+};
+use crate::random_forest::average_importance;
+use linfa::{
+    dataset::{Labels, Targets},
+    traits::*,
+    Dataset, Float, Label,
+};
+
+#[cfg(feature = "serde")]
+use serde_crate::{Deserialize, Serialize};
+
+/// Loss function minimized by the stages, with one raw prediction per output
+trait Objective<F: Float> {
+    /// Number of raw predictions of each sample, and trees of each stage
+    fn num_outputs(&self) -> usize;
+
+    /// Constant initial raw predictions minimizing the loss on `samples`
+    fn init(&self, samples: &[usize], weights: &[F]) -> Vec<F>;
+
+    /// Negative gradient of the loss of a sample for one output
+    fn negative_gradient(&self, idx: usize, raw: ArrayView1<F>, output: usize) -> F;
+
+    /// Value of a leaf for one output minimizing the loss of the samples in the leaf
+    fn leaf_value(
+        &self,
+        samples: &[usize],
+        raw: &Array2<F>,
+        gradients: &[F],
+        weights: &[F],
+        output: usize,
+    ) -> F;
+
+    /// Loss of a sample
+    fn loss(&self, idx: usize, raw: ArrayView1<F>) -> F;
+
+    /// Weighted mean loss of the samples
+    fn mean_loss(&self, samples: &[usize], raw: &Array2<F>, weights: &[F]) -> F {
+        let (loss, weight) = samples
+            .iter()
+            .fold((F::zero(), F::zero()), |(loss, weight), idx| {
+                (
+                    loss + weights[*idx] * self.loss(*idx, raw.row(*idx)),
+                    weight + weights[*idx],
+                )
+            });
+
+        loss / weight
+    }
+}
+
+/// Weighted median of pairs of values and weights
+fn weighted_median<F: Float>(mut values: Vec<(F, F)>) -> F {
+    values.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+
+    let half = values.iter().map(|(_, weight)| *weight).sum::<F>() / F::from(2.0).unwrap();
+    let mut cumulative = F::zero();
+    for (value, weight) in &values {
+        cumulative += *weight;
+        if cumulative >= half {
+            return *value;
+        }
+    }
+
+    values
+        .last()
+        .map(|(value, _)| *value)
+        .unwrap_or_else(F::zero)
+}
+
+/// Weighted mean of the values of `samples`
+fn weighted_mean<F: Float>(samples: &[usize], values: &[F], weights: &[F]) -> F {
+    let (sum, weight) = samples
+        .iter()
+        .fold((F::zero(), F::zero()), |(sum, weight), idx| {
+            (sum + weights[*idx] * values[*idx], weight + weights[*idx])
+        });
+
+    if weight > F::zero() {
+        sum / weight
+    } else {
+        F::zero()
+    }
+}
+
+/// Regression with a single raw prediction, the predicted target
+struct RegressionObjective<'a, F> {
+    loss: RegressionLoss<F>,
+    targets: &'a [F],
+}
+
+impl<'a, F: Float> Objective<F> for RegressionObjective<'a, F> {
+    fn num_outputs(&self) -> usize {
+        1
+    }
+
+    fn init(&self, samples: &[usize], weights: &[F]) -> Vec<F> {
+        let init = match self.loss {
+            RegressionLoss::Squared => weighted_mean(samples, self.targets, weights),
+            RegressionLoss::Absolute | RegressionLoss::Huber(_) => weighted_median(
+                samples
+                    .iter()
+                    .map(|idx| (self.targets[*idx], weights[*idx]))
+                    .collect(),
+            ),
+        };
+
+        vec![init]
+    }
+
+    fn negative_gradient(&self, idx: usize, raw: ArrayView1<F>, _output: usize) -> F {
+        let residual = self.targets[idx] - raw[0];
+
+        match self.loss {
+            RegressionLoss::Squared => residual,
+            RegressionLoss::Absolute => residual.signum(),
+            RegressionLoss::Huber(delta) => {
+                if residual.abs() <= delta {
+                    residual
+                } else {
+                    delta * residual.signum()
+                }
+            }
+        }
+    }
+
+    fn leaf_value(
+        &self,
+        samples: &[usize],
+        raw: &Array2<F>,
+        gradients: &[F],
+        weights: &[F],
+        _output: usize,
+    ) -> F {
+        if samples.is_empty() {
+            return F::zero();
+        }
+
+        let residuals = samples
+            .iter()
+            .map(|idx| (self.targets[*idx] - raw[(*idx, 0)], weights[*idx]))
+            .collect::<Vec<_>>();
+
+        match self.loss {
+            RegressionLoss::Squared => weighted_mean(samples, gradients, weights),
+            RegressionLoss::Absolute => weighted_median(residuals),
+            RegressionLoss::Huber(delta) => {
+                // one step from the median towards the mean of the clipped residuals
+                let median = weighted_median(residuals.clone());
+                let (sum, weight) =
+                    residuals
+                        .iter()
+                        .fold((F::zero(), F::zero()), |(sum, weight), (x, w)| {
+                            let diff = *x - median;
+                            (
+                                sum + *w * diff.signum() * diff.abs().min(delta),
+                                weight + *w,
+                            )
+                        });
+
+                median + sum / weight
+            }
+        }
+    }
+
+    fn loss(&self, idx: usize, raw: ArrayView1<F>) -> F {
+        let residual = self.targets[idx] - raw[0];
+
+        match self.loss {
+            RegressionLoss::Squared => residual * residual,
+            RegressionLoss::Absolute => residual.abs(),
+            RegressionLoss::Huber(delta) => {
+                if residual.abs() <= delta {
+                    residual * residual / F::from(2.0).unwrap()
+                } else {
+                    delta * (residual.abs() - delta / F::from(2.0).unwrap())
+                }
+            }
+        }
+    }
+}
+
+/// Classification with the logistic loss for two classes and the multinomial loss otherwise
+///
+/// For two classes the raw prediction is the log-odds of the second class, otherwise there
+/// is one raw prediction per class.
+struct ClassificationObjective {
+    targets: Vec<usize>,
+    num_classes: usize,
+}
+
+impl ClassificationObjective {
+    fn is_target<F: Float>(&self, idx: usize, class: usize) -> F {
+        if self.targets[idx] == class {
+            F::one()
+        } else {
+            F::zero()
+        }
+    }
+}
+
+/// Probabilities of the classes from the raw predictions of a sample
+fn class_probabilities<F: Float>(raw: ArrayView1<F>) -> Array1<F> {
+    if raw.len() == 1 {
+        let p = F::one() / (F::one() + (-raw[0]).exp());
+        return Array1::from(vec![F::one() - p, p]);
+    }
+
+    // subtract the maximum to avoid overflows
+    let max = raw.fold(F::neg_infinity(), |max, x| F::max(max, *x));
+    let exp = raw.mapv(|x| (x - max).exp());
+    let sum = exp.sum();
+
+    exp / sum
+}
+
+impl<F: Float> Objective<F> for ClassificationObjective {
+    fn num_outputs(&self) -> usize {
+        if self.num_classes == 2 {
+            1
+        } else {
+            self.num_classes
+        }
+    }
+
+    fn init(&self, samples: &[usize], weights: &[F]) -> Vec<F> {
+        let total = samples.iter().map(|idx| weights[*idx]).sum::<F>();
+        let prior = |class| {
+            let weight = samples
+                .iter()
+                .filter(|idx| self.targets[**idx] == class)
+                .map(|idx| weights[*idx])
+                .sum::<F>();
+
+            // avoid infinite raw predictions for classes missing in the samples
+            F::max(weight / total, F::from(1e-10).unwrap())
+        };
+
+        if self.num_classes == 2 {
+            let p = prior(1);
+            vec![(p / (F::one() - p)).ln()]
+        } else {
+            (0..self.num_classes)
+                .map(|class| prior(class).ln())
+                .collect()
+        }
+    }
+
+    fn negative_gradient(&self, idx: usize, raw: ArrayView1<F>, output: usize) -> F {
+        let class = if self.num_classes == 2 { 1 } else { output };
+        let proba = class_probabilities(raw);
+
+        self.is_target::<F>(idx, class) - proba[class]
+    }
+
+    fn leaf_value(
+        &self,
+        samples: &[usize],
+        _raw: &Array2<F>,
+        gradients: &[F],
+        weights: &[F],
+        _output: usize,
+    ) -> F {
+        // a single Newton step, the residuals are the target minus the probability
+        let (numerator, denominator) =
+            samples
+                .iter()
+                .fold((F::zero(), F::zero()), |(num, denom), idx| {
+                    let residual = gradients[*idx];
+                    (
+                        num + weights[*idx] * residual,
+                        denom + weights[*idx] * residual.abs() * (F::one() - residual.abs()),
+                    )
+                });
+
+        if denominator <= F::from(1e-10).unwrap() {
+            return F::zero();
+        }
+
+        let value = numerator / denominator;
+        if self.num_classes == 2 {
+            value
+        } else {
+            let k = F::from(self.num_classes).unwrap();
+            value * (k - F::one()) / k
+        }
+    }
+
+    fn loss(&self, idx: usize, raw: ArrayView1<F>) -> F {
+        let proba = class_probabilities(raw);
+
+        -F::max(proba[self.targets[idx]], F::from(1e-15).unwrap()).ln()
+    }
+}
+
+/// Settings of the boosting shared by classification and regression
+struct Boosting<F> {
+    tree_params: RegressionTreeParams<F>,
+    num_stages: usize,
+    learning_rate: F,
+    subsample: f32,
+    early_stopping: Option<usize>,
+    validation_fraction: f32,
+    seed: u64,
+}
+
+/// Stages of fitted trees, shared by classification and regression
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
+#[derive(Debug)]
+struct Ensemble<F: Float> {
+    init: Array1<F>,
+    stages: Vec<Vec<RegressionTree<F>>>,
+    learning_rate: F,
+    train_loss: Vec<F>,
+    validation_loss: Option<Vec<F>>,
+    num_features: usize,
+}
+
+impl<F: Float> Boosting<F> {
+    fn fit<D: Data<Elem = F>, O: Objective<F>>(
+        &self,
+        x: &ArrayBase<D, Ix2>,
+        weights: Vec<f32>,
+        objective: &O,
+    ) -> Ensemble<F> {
+        let (nsamples, nfeatures) = x.dim();
+        let num_outputs = objective.num_outputs();
+        let float_weights = weights
+            .iter()
+            .map(|w| F::from(*w).unwrap())
+            .collect::<Vec<_>>();
+        let mut rng = Isaac64Rng::seed_from_u64(self.seed);
+
+        // hold out a random part of the samples to decide about early stopping
+        let (train, validation) = if self.early_stopping.is_some() {
+            assert!(
+                nsamples >= 2,
+                "Early stopping needs at least two samples, one for training and one for validation"
+            );
+            let num_validation = ((self.validation_fraction * nsamples as f32).round() as usize)
+                .max(1)
+                .min(nsamples - 1);
+            let mut permutation = index::sample(&mut rng, nsamples, nsamples).into_vec();
+            let mut validation = permutation.split_off(nsamples - num_validation);
+            permutation.sort_unstable();
+            validation.sort_unstable();
+
+            (permutation, validation)
+        } else {
+            ((0..nsamples).collect::<Vec<_>>(), Vec::new())
+        };
+
+        // the features are sorted only once for all stages
+        let sorted_indices: Vec<_> = (0..nfeatures)
+            .map(|feature_idx| SortedIndex::of_array_column(x, feature_idx))
+            .collect();
+
+        let init = Array1::from(objective.init(&train, &float_weights));
+        let mut raw = Array2::zeros((nsamples, num_outputs));
+        raw += &init;
+
+        let mut stages = Vec::with_capacity(self.num_stages);
+        let mut train_loss = Vec::with_capacity(self.num_stages);
+        let mut validation_loss = Vec::with_capacity(self.num_stages);
+        let (mut best_loss, mut best_stages) = (F::infinity(), 0);
+
+        for _ in 0..self.num_stages {
+            // draw the samples of this stage without replacement
+            let samples = if self.subsample < 1.0 {
+                let num = ((self.subsample * train.len() as f32) as usize).max(1);
+                let mut samples = index::sample(&mut rng, train.len(), num)
+                    .into_iter()
+                    .map(|idx| train[idx])
+                    .collect::<Vec<_>>();
+                samples.sort_unstable();
+                samples
+            } else {
+                train.clone()
+            };
+
+            let mut mask = RowMask::none(nsamples);
+            for idx in &samples {
+                mask.mark(*idx);
+            }
+
+            let mut trees = Vec::with_capacity(num_outputs);
+            for output in 0..num_outputs {
+                let gradients = (0..nsamples)
+                    .map(|idx| objective.negative_gradient(idx, raw.row(idx), output))
+                    .collect::<Vec<_>>();
+
+                let dataset =
+                    Dataset::new(x.view(), gradients.as_slice()).with_weights(weights.clone());
+                let mut tree = self.tree_params.fit_with_features(
+                    &dataset,
+                    &mask,
+                    &sorted_indices,
+                    &mut || (0..nfeatures).collect(),
+                );
+
+                // re-estimate the leaves for the loss, the tree only approximates the gradient
+                let mut leaves = vec![Vec::new(); tree.num_leaves()];
+                for idx in &samples {
+                    leaves[tree.leaf_index(&x.row(*idx))].push(*idx);
+                }
+                let values = leaves
+                    .iter()
+                    .map(|leaf| {
+                        objective.leaf_value(leaf, &raw, &gradients, &float_weights, output)
+                    })
+                    .collect::<Vec<_>>();
+                tree.set_leaf_predictions(&values);
+
+                trees.push(tree);
+            }
+
+            // the trees of all outputs are fitted to the gradients of the previous stage
+            for (output, tree) in trees.iter().enumerate() {
+                let predicted = tree.predict(x);
+                raw.column_mut(output)
+                    .zip_mut_with(&predicted, |raw, x| *raw += self.learning_rate * *x);
+            }
+
+            stages.push(trees);
+            train_loss.push(objective.mean_loss(&train, &raw, &float_weights));
+
+            if let Some(patience) = self.early_stopping {
+                let loss = objective.mean_loss(&validation, &raw, &float_weights);
+                validation_loss.push(loss);
+
+                if loss < best_loss {
+                    best_loss = loss;
+                    best_stages = stages.len();
+                } else if stages.len() - best_stages >= patience {
+                    break;
+                }
+            }
+        }
+
+        // drop the stages after the best validation loss
+        if self.early_stopping.is_some() {
+            stages.truncate(best_stages);
+            train_loss.truncate(best_stages);
+            validation_loss.truncate(best_stages);
+        }
+
+        Ensemble {
+            init,
+            stages,
+            learning_rate: self.learning_rate,
+            train_loss,
+            validation_loss: self.early_stopping.map(|_| validation_loss),
+            num_features: nfeatures,
+        }
+    }
+}
+
+impl<F: Float> Ensemble<F> {
+    /// Raw predictions after each stage
+    fn staged_raw_predict<'a, D: Data<Elem = F>>(
+        &'a self,
+        x: &'a ArrayBase<D, Ix2>,
+    ) -> impl Iterator<Item = Array2<F>> + 'a {
+        let mut raw = Array2::zeros((x.nrows(), self.init.len()));
+        raw += &self.init;
+
+        self.stages.iter().map(move |trees| {
+            for (output, tree) in trees.iter().enumerate() {
+                let predicted = tree.predict(x);
+                raw.column_mut(output)
+                    .zip_mut_with(&predicted, |raw, x| *raw += self.learning_rate * *x);
+            }
+
+            raw.clone()
+        })
+    }
+
+    /// Raw predictions of all stages
+    fn raw_predict<D: Data<Elem = F>>(&self, x: &ArrayBase<D, Ix2>) -> Array2<F> {
+        match self.staged_raw_predict(x).last() {
+            Some(raw) => raw,
+            None => {
+                let mut raw = Array2::zeros((x.nrows(), self.init.len()));
+                raw += &self.init;
+                raw
+            }
+        }
+    }
+
+    fn feature_importance(&self) -> Vec<F> {
+        average_importance(
+            self.stages
+                .iter()
+                .flatten()
+                .map(|tree| tree.feature_importance()),
+            self.num_features,
+        )
+    }
+}
+
+/// A fitted gradient boosted classifier.
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
+#[derive(Debug)]
+pub struct GradientBoosting<F: Float, L: Label> {
+    ensemble: Ensemble<F>,
+    classes: Vec<L>,
+}
+
+impl<F: Float, L: Label + Debug> GradientBoosting<F, L> {
+    /// Defaults are provided if the optional parameters are not specified:
+    /// * `tree_params = RegressionTree::params().max_depth(Some(3))`
+    /// * `num_stages = 100`
+    /// * `learning_rate = 0.1`
+    /// * `subsample = 1.0`
+    /// * `early_stopping = None`
+    /// * `validation_fraction = 0.1`
+    /// * `seed = 42`
+    // Violates the convention that new should return a value of type `Self`
+    #[allow(clippy::new_ret_no_self)]
+    pub fn params() -> GradientBoostingParams<F, L> {
+        GradientBoostingParams {
+            tree_params: RegressionTree::params().max_depth(Some(3)),
+            num_stages: 100,
+            learning_rate: F::from(0.1).unwrap(),
+            subsample: 1.0,
+            early_stopping: None,
+            validation_fraction: 0.1,
+            seed: 42,
+            phantom: std::marker::PhantomData,
+        }
+    }
+
+    /// Return the classes in the order of the columns of `predict_proba`
+    pub fn classes(&self) -> &[L] {
+        &self.classes
+    }
+
+    /// Return the trees of each stage, one tree for two classes and one tree per class
+    /// otherwise
+    pub fn stages(&self) -> &[Vec<RegressionTree<F>>] {
+        &self.ensemble.stages
+    }
+
+    /// Return the mean loss on the training samples after each stage
+    pub fn train_loss(&self) -> &[F] {
+        &self.ensemble.train_loss
+    }
+
+    /// Return the mean loss on the validation samples after each stage, if early stopping was
+    /// enabled
+    pub fn validation_loss(&self) -> Option<&[F]> {
+        self.ensemble.validation_loss.as_deref()
+    }
+
+    /// Return the feature importance, i.e. the relative impurity decrease averaged over all
+    /// trees, for each feature
+    pub fn feature_importance(&self) -> Vec<F> {
+        self.ensemble.feature_importance()
+    }
+
+    /// Return the raw predictions for each row of a matrix of features `x`
+    ///
+    /// This is the log-odds of the second class for two classes and the unnormalized log
+    /// probability of each class otherwise.
+    pub fn decision_function<D: Data<Elem = F>>(&self, x: &ArrayBase<D, Ix2>) -> Array2<F> {
+        self.ensemble.raw_predict(x)
+    }
+
+    /// Return the probability of each class for each row of a matrix of features `x`
+    ///
+    /// The columns are ordered like [`classes`](#method.classes).
+    pub fn predict_proba<D: Data<Elem = F>>(&self, x: &ArrayBase<D, Ix2>) -> Array2<F> {
+        raw_to_proba(&self.ensemble.raw_predict(x))
+    }
+
+    /// Return the probabilities after each stage, for example to select the number of stages
+    pub fn staged_predict_proba<'a, D: Data<Elem = F>>(
+        &'a self,
+        x: &'a ArrayBase<D, Ix2>,
+    ) -> impl Iterator<Item = Array2<F>> + 'a {
+        self.ensemble
+            .staged_raw_predict(x)
+            .map(|raw| raw_to_proba(&raw))
+    }
+
+    /// Return the class with the largest probability in each row
+    fn most_probable(&self, proba: &Array2<F>) -> Vec<L> {
+        proba
+            .genrows()
+            .into_iter()
+            .map(|row| {
+                let best = row
+                    .iter()
+                    .enumerate()
+                    .fold((0, F::neg_infinity()), |(best_idx, best), (idx, val)| {
+                        if *val > best {
+                            (idx, *val)
+                        } else {
+                            (best_idx, best)
+                        }
+                    })
+                    .0;
+
+                self.classes[best].clone()
+            })
+            .collect()
+    }
+}
+
+/// Probabilities of the classes for each row of raw predictions
+fn raw_to_proba<F: Float>(raw: &Array2<F>) -> Array2<F> {
+    let num_classes = if raw.ncols() == 1 { 2 } else { raw.ncols() };
+    let mut proba = Array2::zeros((raw.nrows(), num_classes));
+    for (raw, mut proba) in raw.genrows().into_iter().zip(proba.genrows_mut()) {
+        proba.assign(&class_probabilities(raw));
+    }
+
+    proba
+}
+
+impl<F: Float, L: Label + Debug, D: Data<Elem = F>> Predict<ArrayBase<D, Ix2>, Vec<L>>
+    for GradientBoosting<F, L>
+{
+    /// Make predictions for each row of a matrix of features `x`.
+    fn predict(&self, x: ArrayBase<D, Ix2>) -> Vec<L> {
+        self.most_probable(&self.predict_proba(&x))
+    }
+}
+
+impl<F: Float, L: Label + Debug, D: Data<Elem = F>> Predict<&ArrayBase<D, Ix2>, Vec<L>>
+    for GradientBoosting<F, L>
+{
+    fn predict(&self, x: &ArrayBase<D, Ix2>) -> Vec<L> {
+        self.most_probable(&self.predict_proba(x))
+    }
+}
+
+impl<'a, F: Float, L: Label + Debug + 'a, D: Data<Elem = F>, T: Labels<Elem = L>>
+    Fit<'a, ArrayBase<D, Ix2>, T> for GradientBoostingParams<F, L>
+{
+    type Object = GradientBoosting<F, L>;
+
+    /// Fit gradient boosted trees using `hyperparamters` on the dataset consisting of
+    /// a matrix of features `x` and an array of labels `y`.
+    fn fit(&self, dataset: &Dataset<ArrayBase<D, Ix2>, T>) -> Self::Object {
+        self.validate().unwrap();
+
+        // classes in order of their first appearance, like in a decision tree
+        let classes = classes_of(dataset.targets().as_slice());
+        let class_idx = classes
+            .iter()
+            .enumerate()
+            .map(|(i, class)| (class, i))
+            .collect::<HashMap<_, _>>();
+        let targets = dataset
+            .targets()
+            .as_slice()
+            .iter()
+            .map(|target| class_idx[target])
+            .collect();
+        assert!(
+            classes.len() >= 2,
+            "Gradient boosting needs at least two classes"
+        );
+
+        let objective = ClassificationObjective {
+            targets,
+            num_classes: classes.len(),
+        };
+        let boosting = Boosting {
+            tree_params: self.tree_params,
+            num_stages: self.num_stages,
+            learning_rate: self.learning_rate,
+            subsample: self.subsample,
+            early_stopping: self.early_stopping,
+            validation_fraction: self.validation_fraction,
+            seed: self.seed,
+        };
+
+        let weights = (0..dataset.records().nrows())
+            .map(|i| dataset.weight_for(i))
+            .collect();
+
+        GradientBoosting {
+            ensemble: boosting.fit(dataset.records(), weights, &objective),
+            classes,
+        }
+    }
+}
+
+/// A fitted gradient boosted regressor.
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
+#[derive(Debug)]
+pub struct GradientBoostingRegressor<F: Float> {
+    ensemble: Ensemble<F>,
+}
+
+impl<F: Float> GradientBoostingRegressor<F> {
+    /// Defaults are provided if the optional parameters are not specified:
+    /// * `loss = RegressionLoss::Squared`
+    /// * `tree_params = RegressionTree::params().max_depth(Some(3))`
+    /// * `num_stages = 100`
+    /// * `learning_rate = 0.1`
+    /// * `subsample = 1.0`
+    /// * `early_stopping = None`
+    /// * `validation_fraction = 0.1`
+    /// * `seed = 42`
+    // Violates the convention that new should return a value of type `Self`
+    #[allow(clippy::new_ret_no_self)]
+    pub fn params() -> GradientBoostingRegressorParams<F> {
+        GradientBoostingRegressorParams {
+            loss: RegressionLoss::Squared,
+            tree_params: RegressionTree::params().max_depth(Some(3)),
+            num_stages: 100,
+            learning_rate: F::from(0.1).unwrap(),
+            subsample: 1.0,
+            early_stopping: None,
+            validation_fraction: 0.1,
+            seed: 42,
+        }
+    }
+
+    /// Return the trees of each stage, each stage consists of a single tree
+    pub fn stages(&self) -> &[Vec<RegressionTree<F>>] {
+        &self.ensemble.stages
+    }
+
+    /// Return the mean loss on the training samples after each stage
+    pub fn train_loss(&self) -> &[F] {
+        &self.ensemble.train_loss
+    }
+
+    /// Return the mean loss on the validation samples after each stage, if early stopping was
+    /// enabled
+    pub fn validation_loss(&self) -> Option<&[F]> {
+        self.ensemble.validation_loss.as_deref()
+    }
+
+    /// Return the feature importance, i.e. the relative impurity decrease averaged over all
+    /// trees, for each feature
+    pub fn feature_importance(&self) -> Vec<F> {
+        self.ensemble.feature_importance()
+    }
+
+    /// Return the predictions after each stage, for example to select the number of stages
+    pub fn staged_predict<'a, D: Data<Elem = F>>(
+        &'a self,
+        x: &'a ArrayBase<D, Ix2>,
+    ) -> impl Iterator<Item = Array1<F>> + 'a {
+        self.ensemble
+            .staged_raw_predict(x)
+            .map(|raw| raw.index_axis_move(Axis(1), 0))
+    }
+}
+
+impl<F: Float, D: Data<Elem = F>> Predict<ArrayBase<D, Ix2>, Array1<F>>
+    for GradientBoostingRegressor<F>
+{
+    /// Make predictions for each row of a matrix of features `x`.
+    fn predict(&self, x: ArrayBase<D, Ix2>) -> Array1<F> {
+        self.ensemble.raw_predict(&x).index_axis_move(Axis(1), 0)
+    }
+}
+
+impl<F: Float, D: Data<Elem = F>> Predict<&ArrayBase<D, Ix2>, Array1<F>>
+    for GradientBoostingRegressor<F>
+{
+    fn predict(&self, x: &ArrayBase<D, Ix2>) -> Array1<F> {
+        self.ensemble.raw_predict(x).index_axis_move(Axis(1), 0)
+    }
+}
+
+impl<'a, F: Float, D: Data<Elem = F>, T: Targets<Elem = F>> Fit<'a, ArrayBase<D, Ix2>, T>
+    for GradientBoostingRegressorParams<F>
+{
+    type Object = GradientBoostingRegressor<F>;
+
+    /// Fit gradient boosted trees using `hyperparamters` on the dataset consisting of
+    /// a matrix of features `x` and an array of targets `y`.
+    fn fit(&self, dataset: &Dataset<ArrayBase<D, Ix2>, T>) -> Self::Object {
+        self.validate().unwrap();
+
+        let objective = RegressionObjective {
+            loss: self.loss,
+            targets: dataset.targets().as_slice(),
+        };
+        let boosting = Boosting {
+            tree_params: self.tree_params,
+            num_stages: self.num_stages,
+            learning_rate: self.learning_rate,
+            subsample: self.subsample,
+            early_stopping: self.early_stopping,
+            validation_fraction: self.validation_fraction,
+            seed: self.seed,
+        };
+
+        let weights = (0..dataset.records().nrows())
+            .map(|i| dataset.weight_for(i))
+            .collect();
+
+        GradientBoostingRegressor {
+            ensemble: boosting.fit(dataset.records(), weights, &objective),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decision_trees::RegressionCriterion;
+
+    use approx::assert_abs_diff_eq;
+    use linfa::metrics::{Regression, ToConfusionMatrix};
+    use ndarray::{array, Array};
+    use ndarray_rand::{rand_distr::Uniform, RandomExt};
+
+    #[test]
+    fn weighted_median_of_pairs() {
+        assert_abs_diff_eq!(
+            weighted_median(vec![(3.0, 1.0), (1.0, 1.0), (2.0, 1.0)]),
+            2.0
+        );
+        assert_abs_diff_eq!(
+            weighted_median(vec![(3.0, 5.0), (1.0, 1.0), (2.0, 1.0)]),
+            3.0
+        );
+    }
+
+    #[test]
+    /// All losses fit a smooth function and the training loss decreases in each stage
+    fn regression_losses() {
+        let mut rng = Isaac64Rng::seed_from_u64(42);
+        let data = Array::random_using((200, 2), Uniform::new(-1., 1.), &mut rng);
+        let targets = data.column(0).mapv(|x: f64| (3.0 * x).sin());
+        let dataset = Dataset::new(data.clone(), targets.clone());
+
+        for loss in &[
+            RegressionLoss::Squared,
+            RegressionLoss::Absolute,
+            RegressionLoss::Huber(0.1),
+        ] {
+            let model = GradientBoostingRegressor::params()
+                .loss(*loss)
+                .fit(&dataset);
+
+            assert_eq!(model.stages().len(), 100);
+            assert!(model.predict(&data).r2(&targets) > 0.95);
+            assert!(model.validation_loss().is_none());
+            assert!(model.train_loss().windows(2).all(|x| x[1] <= x[0] + 1e-10));
+        }
+    }
+
+    #[test]
+    /// The last staged prediction equals the prediction of the model
+    fn staged_predictions() {
+        let data = Array::linspace(0., 1., 50).insert_axis(Axis(1));
+        let targets = data.column(0).mapv(|x| x * x);
+        let dataset = Dataset::new(data.clone(), targets.clone());
+
+        let model = GradientBoostingRegressor::params()
+            .num_stages(20)
+            .subsample(0.5)
+            .fit(&dataset);
+
+        let staged = model.staged_predict(&data).collect::<Vec<_>>();
+        assert_eq!(staged.len(), 20);
+        assert_abs_diff_eq!(staged[19], model.predict(&data), epsilon = 1e-10);
+
+        // the error decreases with the number of stages
+        let first = staged[0].mean_squared_error(&targets);
+        let last = staged[19].mean_squared_error(&targets);
+        assert!(last < first);
+    }
+
+    #[test]
+    /// The absolute loss ignores outliers in the targets
+    fn absolute_loss_outliers() {
+        let data = Array::linspace(0., 1., 40).insert_axis(Axis(1));
+        let mut targets = data.column(0).mapv(|x| if x < 0.5 { 0.0 } else { 1.0 });
+        targets[5] = 1000.0;
+        targets[35] = -1000.0;
+        let dataset = Dataset::new(data.clone(), targets);
+
+        let model = GradientBoostingRegressor::params()
+            .loss(RegressionLoss::Absolute)
+            .learning_rate(1.0)
+            .num_stages(10)
+            .tree_params(RegressionTree::params().max_depth(Some(1)))
+            .fit(&dataset);
+
+        let predicted = model.predict(&array![[0.1], [0.9]]);
+        assert_abs_diff_eq!(predicted, array![0.0, 1.0], epsilon = 1e-10);
+    }
+
+    #[test]
+    /// Early stopping drops the stages after the best validation loss
+    fn early_stopping_noise() {
+        let mut rng = Isaac64Rng::seed_from_u64(42);
+        let data = Array::random_using((200, 3), Uniform::new(-1., 1.), &mut rng);
+        let targets = Array::random_using(200, Uniform::new(-1., 1.), &mut rng);
+        let dataset = Dataset::new(data, targets);
+
+        let model = GradientBoostingRegressor::params()
+            .num_stages(500)
+            .early_stopping(Some(5))
+            .validation_fraction(0.2)
+            .fit(&dataset);
+
+        let validation_loss = model.validation_loss().unwrap();
+        assert!(model.stages().len() < 500);
+        assert_eq!(validation_loss.len(), model.stages().len());
+        assert_eq!(model.train_loss().len(), model.stages().len());
+    }
+
+    #[test]
+    #[should_panic(expected = "Early stopping needs at least two samples")]
+    /// A single sample can not be split into a training and a validation set
+    fn early_stopping_single_sample() {
+        let dataset = Dataset::new(array![[0.0]], array![1.0]);
+
+        GradientBoostingRegressor::params()
+            .early_stopping(Some(5))
+            .fit(&dataset);
+    }
+
+    #[test]
+    /// Two separable classes are fitted with the logistic loss
+    fn binary_classification() {
+        let mut rng = Isaac64Rng::seed_from_u64(42);
+        let data = Array::random_using((100, 2), Uniform::new(-1., 1.), &mut rng);
+        let targets = data
+            .genrows()
+            .into_iter()
+            .map(|x| x[0] + x[1] > 0.0)
+            .collect::<Vec<_>>();
+        let dataset = Dataset::new(data.clone(), targets);
+
+        let model = GradientBoosting::params().fit(&dataset);
+        assert_eq!(model.stages()[0].len(), 1);
+        assert_eq!(model.decision_function(&data).ncols(), 1);
+
+        let cm = model.predict(&data).confusion_matrix(&dataset);
+        assert!(cm.accuracy() > 0.95);
+
+        let proba = model.predict_proba(&data);
+        assert_eq!(proba.dim(), (100, 2));
+        for (row, target) in proba.genrows().into_iter().zip(dataset.targets()) {
+            assert_abs_diff_eq!(row.sum(), 1.0, epsilon = 1e-10);
+            let idx = model.classes().iter().position(|x| x == target).unwrap();
+            assert!(row[idx] > 0.5);
+        }
+
+        let staged = model.staged_predict_proba(&data).last().unwrap();
+        assert_abs_diff_eq!(staged, proba, epsilon = 1e-10);
+    }
+
+    #[test]
+    /// Four classes are fitted with the multinomial loss
+    fn multiclass_four_quadrants() {
+        let mut rng = Isaac64Rng::seed_from_u64(42);
+        let data = Array::random_using((200, 2), Uniform::new(-1., 1.), &mut rng);
+        let targets = data
+            .genrows()
+            .into_iter()
+            .map(|x| (x[0] > 0.0) as usize * 2 + (x[1] > 0.0) as usize)
+            .collect::<Vec<_>>();
+        let dataset = Dataset::new(data.clone(), targets);
+
+        let model = GradientBoosting::params()
+            .num_stages(50)
+            .subsample(0.8)
+            .fit(&dataset);
+        assert_eq!(model.classes().len(), 4);
+        assert_eq!(model.stages()[0].len(), 4);
+
+        let cm = model.predict(&data).confusion_matrix(&dataset);
+        assert!(cm.accuracy() > 0.95);
+
+        let proba = model.predict_proba(&data);
+        for row in proba.genrows() {
+            assert_abs_diff_eq!(row.sum(), 1.0, epsilon = 1e-10);
+        }
+    }
+
+    #[test]
+    fn invalid_parameters() {
+        assert!(GradientBoostingRegressor::<f64>::params()
+            .learning_rate(0.0)
+            .validate()
+            .is_err());
+        assert!(GradientBoostingRegressor::<f64>::params()
+            .loss(RegressionLoss::Huber(-1.0))
+            .validate()
+            .is_err());
+        assert!(GradientBoosting::<f64, bool>::params()
+            .subsample(1.5)
+            .validate()
+            .is_err());
+        assert!(GradientBoosting::<f64, bool>::params()
+            .num_stages(0)
+            .validate()
+            .is_err());
+        assert!(GradientBoostingRegressor::<f64>::params()
+            .tree_params(RegressionTree::params().criterion(RegressionCriterion::Poisson))
+            .validate()
+            .is_err());
+        assert!(GradientBoosting::<f64, bool>::params()
+            .tree_params(RegressionTree::params().criterion(RegressionCriterion::Mae))
+            .validate()
+            .is_err());
+    }
+}
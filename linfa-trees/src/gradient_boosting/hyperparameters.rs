@@ -0,0 +1,245 @@
+use crate::decision_trees::{RegressionCriterion, RegressionTreeParams};
+use linfa::{
+    error::{Error, Result},
+    Float, Label,
+};
+use std::marker::PhantomData;
+
+#[cfg(feature = "serde")]
+use serde_crate::{Deserialize, Serialize};
+
+/// The possible loss functions for gradient boosted regression.
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RegressionLoss<F> {
+    /// Squared error, the stages fit the residuals
+    Squared,
+    /// Absolute error, robust to outliers in the targets
+    Absolute,
+    /// Squared error for residuals up to the given threshold, absolute error above
+    Huber(F),
+}
+
+/// Validate the parameters shared by classification and regression
+fn validate_boosting<F: Float>(
+    tree_params: &RegressionTreeParams<F>,
+    num_stages: usize,
+    learning_rate: F,
+    subsample: f32,
+    validation_fraction: f32,
+) -> Result<()> {
+    // the stages fit gradients, which can be negative and are averaged in the leaves
+    if !matches!(tree_params.criterion, RegressionCriterion::Mse) {
+        return Err(Error::Parameters(format!(
+            "Gradient boosting needs the Mse criterion for its trees, but was {:?}",
+            tree_params.criterion
+        )));
+    }
+
+    if num_stages == 0 {
+        return Err(Error::Parameters(
+            "Gradient boosting needs at least one stage".to_string(),
+        ));
+    }
+
+    if learning_rate <= F::zero() || learning_rate > F::one() {
+        return Err(Error::Parameters(format!(
+            "Learning rate should be in the interval (0, 1], but was {}",
+            learning_rate
+        )));
+    }
+
+    if subsample <= 0.0 || subsample > 1.0 {
+        return Err(Error::Parameters(format!(
+            "Subsample should be in the interval (0, 1], but was {}",
+            subsample
+        )));
+    }
+
+    if validation_fraction <= 0.0 || validation_fraction >= 1.0 {
+        return Err(Error::Parameters(format!(
+            "Validation fraction should be in the interval (0, 1), but was {}",
+            validation_fraction
+        )));
+    }
+
+    Ok(())
+}
+
+/// The set of hyperparameters that can be specified for fitting a
+/// [gradient boosted classifier](struct.GradientBoosting.html).
+///
+/// Two classes are fitted with the logistic loss, more classes with the multinomial loss.
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
+#[derive(Clone, Copy, Debug)]
+pub struct GradientBoostingParams<F, L> {
+    pub tree_params: RegressionTreeParams<F>,
+    pub num_stages: usize,
+    pub learning_rate: F,
+    pub subsample: f32,
+    pub early_stopping: Option<usize>,
+    pub validation_fraction: f32,
+    pub seed: u64,
+    pub phantom: PhantomData<L>,
+}
+
+impl<F: Float, L: Label> GradientBoostingParams<F, L> {
+    /// Hyperparameters of the regression tree fitted in each stage
+    ///
+    /// The trees fit the gradients of the loss, so only `RegressionCriterion::Mse` is valid.
+    pub fn tree_params(mut self, tree_params: RegressionTreeParams<F>) -> Self {
+        self.tree_params = tree_params;
+        self
+    }
+
+    pub fn num_stages(mut self, num_stages: usize) -> Self {
+        self.num_stages = num_stages;
+        self
+    }
+
+    /// Shrink the contribution of each stage by this factor
+    pub fn learning_rate(mut self, learning_rate: F) -> Self {
+        self.learning_rate = learning_rate;
+        self
+    }
+
+    /// Fit each stage on this fraction of the training samples, drawn without replacement
+    pub fn subsample(mut self, subsample: f32) -> Self {
+        self.subsample = subsample;
+        self
+    }
+
+    /// Stop when the validation loss has not improved for this number of stages
+    ///
+    /// Fitting panics if the dataset has less than two samples, because at least one is needed
+    /// for training and one for validation.
+    pub fn early_stopping(mut self, early_stopping: Option<usize>) -> Self {
+        self.early_stopping = early_stopping;
+        self
+    }
+
+    /// Fraction of the samples held out to decide about early stopping
+    pub fn validation_fraction(mut self, validation_fraction: f32) -> Self {
+        self.validation_fraction = validation_fraction;
+        self
+    }
+
+    /// Seed of the random number generator drawing the subsamples and the validation split
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        validate_boosting(
+            &self.tree_params,
+            self.num_stages,
+            self.learning_rate,
+            self.subsample,
+            self.validation_fraction,
+        )?;
+
+        self.tree_params.validate()
+    }
+}
+
+/// The set of hyperparameters that can be specified for fitting a
+/// [gradient boosted regressor](struct.GradientBoostingRegressor.html).
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
+#[derive(Clone, Copy, Debug)]
+pub struct GradientBoostingRegressorParams<F> {
+    pub loss: RegressionLoss<F>,
+    pub tree_params: RegressionTreeParams<F>,
+    pub num_stages: usize,
+    pub learning_rate: F,
+    pub subsample: f32,
+    pub early_stopping: Option<usize>,
+    pub validation_fraction: f32,
+    pub seed: u64,
+}
+
+impl<F: Float> GradientBoostingRegressorParams<F> {
+    pub fn loss(mut self, loss: RegressionLoss<F>) -> Self {
+        self.loss = loss;
+        self
+    }
+
+    /// Hyperparameters of the regression tree fitted in each stage
+    ///
+    /// The trees fit the gradients of the loss, so only `RegressionCriterion::Mse` is valid.
+    pub fn tree_params(mut self, tree_params: RegressionTreeParams<F>) -> Self {
+        self.tree_params = tree_params;
+        self
+    }
+
+    pub fn num_stages(mut self, num_stages: usize) -> Self {
+        self.num_stages = num_stages;
+        self
+    }
+
+    /// Shrink the contribution of each stage by this factor
+    pub fn learning_rate(mut self, learning_rate: F) -> Self {
+        self.learning_rate = learning_rate;
+        self
+    }
+
+    /// Fit each stage on this fraction of the training samples, drawn without replacement
+    pub fn subsample(mut self, subsample: f32) -> Self {
+        self.subsample = subsample;
+        self
+    }
+
+    /// Stop when the validation loss has not improved for this number of stages
+    ///
+    /// Fitting panics if the dataset has less than two samples, because at least one is needed
+    /// for training and one for validation.
+    pub fn early_stopping(mut self, early_stopping: Option<usize>) -> Self {
+        self.early_stopping = early_stopping;
+        self
+    }
+
+    /// Fraction of the samples held out to decide about early stopping
+    pub fn validation_fraction(mut self, validation_fraction: f32) -> Self {
+        self.validation_fraction = validation_fraction;
+        self
+    }
+
+    /// Seed of the random number generator drawing the subsamples and the validation split
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if let RegressionLoss::Huber(delta) = self.loss {
+            if delta <= F::zero() {
+                return Err(Error::Parameters(format!(
+                    "Huber threshold should be positive, but was {}",
+                    delta
+                )));
+            }
+        }
+
+        validate_boosting(
+            &self.tree_params,
+            self.num_stages,
+            self.learning_rate,
+            self.subsample,
+            self.validation_fraction,
+        )?;
+
+        self.tree_params.validate()
+    }
+}
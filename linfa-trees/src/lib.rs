@@ -1,3 +1,7 @@
 mod decision_trees;
+mod gradient_boosting;
+mod random_forest;
 
 pub use decision_trees::*;
+pub use gradient_boosting::*;
+pub use random_forest::*;
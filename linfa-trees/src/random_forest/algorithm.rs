@@ -0,0 +1,631 @@
+//! Random forests
+//!
+//! A random forest averages many decision trees, each fitted on a bootstrap sample of the
+//! dataset and considering only a random subset of the features at each split. The trees are
+//! fitted in parallel.
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+use ndarray::parallel::prelude::*;
+use ndarray::{Array1, Array2, ArrayBase, Data, Ix1, Ix2};
+use ndarray_rand::rand::{seq::index, Rng, SeedableRng};
+use rand_isaac::Isaac64Rng;
+
+use super::hyperparameters::{MaxFeatures, RandomForestParams, RandomForestRegressorParams};
+use crate::decision_trees::{
+    add_leaf_proba, DecisionTree, FeatureIndex, RegressionTree, RowMask, SortedIndex,
+};
+use linfa::{
+    dataset::{Labels, Targets},
+    metrics::Regression,
+    traits::*,
+    Dataset, Float, Label,
+};
+
+#[cfg(feature = "serde")]
+use serde_crate::{Deserialize, Serialize};
+
+/// Sampling of the observations and features of each tree in a forest
+struct Bagging<'a> {
+    num_trees: usize,
+    max_features: MaxFeatures,
+    bootstrap: bool,
+    seed: u64,
+    weights: &'a [f32],
+    nfeatures: usize,
+}
+
+impl<'a> Bagging<'a> {
+    /// Fit the trees in parallel, each tree is returned together with the mask of its
+    /// out-of-bag samples
+    ///
+    /// The fit function receives the in-bag samples, their weights and a function drawing the
+    /// candidate features of each split.
+    fn fit_trees<M, G>(&self, fit: G) -> Vec<(M, Vec<bool>)>
+    where
+        M: Send,
+        G: Fn(&RowMask, Vec<f32>, &mut dyn FnMut() -> Vec<usize>) -> M + Sync,
+    {
+        // draw the seeds up-front to be independent of the scheduling of the threads
+        let mut rng = Isaac64Rng::seed_from_u64(self.seed);
+        let seeds = (0..self.num_trees)
+            .map(|_| rng.gen::<u64>())
+            .collect::<Vec<_>>();
+
+        seeds
+            .into_par_iter()
+            .map(|seed| {
+                let mut rng = Isaac64Rng::seed_from_u64(seed);
+                let nsamples = self.weights.len();
+
+                // sample with replacement and count the draws of each sample
+                let mut counts = vec![0usize; nsamples];
+                if self.bootstrap {
+                    for _ in 0..nsamples {
+                        counts[rng.gen_range(0, nsamples)] += 1;
+                    }
+                } else {
+                    counts.iter_mut().for_each(|x| *x = 1);
+                }
+
+                let mut mask = RowMask::none(nsamples);
+                let mut weights = vec![0.0; nsamples];
+                for (i, count) in counts.iter().enumerate().filter(|(_, count)| **count > 0) {
+                    mask.mark(i);
+                    weights[i] = *count as f32 * self.weights[i];
+                }
+
+                let nfeatures = self.nfeatures;
+                let max_features = self.max_features.num_features(nfeatures);
+                let mut features = || {
+                    if max_features == nfeatures {
+                        (0..nfeatures).collect()
+                    } else {
+                        let mut features =
+                            index::sample(&mut rng, nfeatures, max_features).into_vec();
+                        features.sort_unstable();
+                        features
+                    }
+                };
+
+                let model = fit(&mask, weights, &mut features);
+                let oob = counts.into_iter().map(|count| count == 0).collect();
+
+                (model, oob)
+            })
+            .collect()
+    }
+}
+
+/// Average the relative impurity decrease of each tree, trees without any split are skipped
+pub(crate) fn average_importance<F: Float, I: Iterator<Item = Vec<F>>>(
+    importances: I,
+    nfeatures: usize,
+) -> Vec<F> {
+    let mut sum = vec![F::zero(); nfeatures];
+    let mut num = 0;
+    for importance in importances.filter(|x| x.iter().all(|val| val.is_finite())) {
+        sum.iter_mut().zip(importance).for_each(|(s, x)| *s += x);
+        num += 1;
+    }
+
+    if num > 0 {
+        sum.iter_mut().for_each(|s| *s /= F::from(num).unwrap());
+    }
+
+    sum
+}
+
+/// A fitted random forest for classification.
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
+#[derive(Debug)]
+pub struct RandomForest<F: Float, L: Label> {
+    trees: Vec<DecisionTree<F, L>>,
+    classes: Vec<L>,
+    oob_score: Option<F>,
+    num_features: usize,
+}
+
+impl<F: Float, L: Label + Debug> RandomForest<F, L> {
+    /// Defaults are provided if the optional parameters are not specified:
+    /// * `tree_params = DecisionTree::params()`
+    /// * `num_trees = 100`
+    /// * `max_features = MaxFeatures::Sqrt`
+    /// * `bootstrap = true`
+    /// * `oob_score = false`
+    /// * `seed = 42`
+    // Violates the convention that new should return a value of type `Self`
+    #[allow(clippy::new_ret_no_self)]
+    pub fn params() -> RandomForestParams<F, L> {
+        RandomForestParams {
+            tree_params: DecisionTree::params(),
+            num_trees: 100,
+            max_features: MaxFeatures::Sqrt,
+            bootstrap: true,
+            oob_score: false,
+            seed: 42,
+        }
+    }
+
+    /// Return the trees of the forest
+    pub fn trees(&self) -> &[DecisionTree<F, L>] {
+        &self.trees
+    }
+
+    /// Return the classes in the order of the columns of `predict_proba`
+    pub fn classes(&self) -> &[L] {
+        &self.classes
+    }
+
+    /// Return the accuracy on the out-of-bag samples, if requested during fitting
+    ///
+    /// Each training sample is classified by the trees which did not see it during fitting.
+    /// Samples drawn by all trees are ignored.
+    pub fn oob_score(&self) -> Option<F> {
+        self.oob_score
+    }
+
+    /// Return the feature importance, i.e. the relative impurity decrease averaged over all
+    /// trees, for each feature
+    pub fn feature_importance(&self) -> Vec<F> {
+        average_importance(
+            self.trees.iter().map(|tree| tree.feature_importance()),
+            self.num_features,
+        )
+    }
+
+    /// Return the probability of each class for each row of a matrix of features `x`
+    ///
+    /// The probabilities are the class frequencies in the leaves of all trees, averaged over
+    /// the trees. The columns are ordered like [`classes`](#method.classes).
+    pub fn predict_proba<D: Data<Elem = F>>(&self, x: &ArrayBase<D, Ix2>) -> Array2<F> {
+        let class_idx = self.class_index();
+        let mut proba = Array2::zeros((x.nrows(), self.classes.len()));
+
+        for (row, mut proba) in x.genrows().into_iter().zip(proba.genrows_mut()) {
+            for tree in &self.trees {
+                add_leaf_proba(tree, &row, &class_idx, proba.view_mut());
+            }
+        }
+
+        proba / F::from(self.trees.len()).unwrap()
+    }
+
+    /// Map each class to its column in the probabilities
+    fn class_index(&self) -> HashMap<&L, usize> {
+        self.classes
+            .iter()
+            .enumerate()
+            .map(|(i, c)| (c, i))
+            .collect()
+    }
+
+    /// Return the class with the largest probability in each row
+    fn most_probable(&self, proba: &Array2<F>) -> Vec<L> {
+        proba
+            .genrows()
+            .into_iter()
+            .map(|row| self.classes[argmax(&row)].clone())
+            .collect()
+    }
+}
+
+/// Index of the largest value, the first one in case of ties
+fn argmax<F: Float>(x: &ArrayBase<impl Data<Elem = F>, Ix1>) -> usize {
+    x.iter()
+        .enumerate()
+        .fold((0, F::neg_infinity()), |(best_idx, best), (idx, val)| {
+            if *val > best {
+                (idx, *val)
+            } else {
+                (best_idx, best)
+            }
+        })
+        .0
+}
+
+impl<F: Float, L: Label + Debug, D: Data<Elem = F>> Predict<ArrayBase<D, Ix2>, Vec<L>>
+    for RandomForest<F, L>
+{
+    /// Make predictions for each row of a matrix of features `x`.
+    fn predict(&self, x: ArrayBase<D, Ix2>) -> Vec<L> {
+        self.most_probable(&self.predict_proba(&x))
+    }
+}
+
+impl<F: Float, L: Label + Debug, D: Data<Elem = F>> Predict<&ArrayBase<D, Ix2>, Vec<L>>
+    for RandomForest<F, L>
+{
+    fn predict(&self, x: &ArrayBase<D, Ix2>) -> Vec<L> {
+        self.most_probable(&self.predict_proba(x))
+    }
+}
+
+impl<'a, F: Float, L: Label + Debug + Send + Sync + 'a, D: Data<Elem = F>, T: Labels<Elem = L>>
+    Fit<'a, ArrayBase<D, Ix2>, T> for RandomForestParams<F, L>
+{
+    type Object = RandomForest<F, L>;
+
+    /// Fit a random forest using `hyperparamters` on the dataset consisting of
+    /// a matrix of features `x` and an array of labels `y`.
+    fn fit(&self, dataset: &Dataset<ArrayBase<D, Ix2>, T>) -> Self::Object {
+        self.validate().unwrap();
+
+        let x = dataset.records().view();
+        let targets = dataset.targets().as_slice();
+        let weights = (0..x.nrows())
+            .map(|i| dataset.weight_for(i))
+            .collect::<Vec<_>>();
+
+        // the features are sorted or binned only once for all trees
+        let index = FeatureIndex::new(&x, self.tree_params.split_search);
+
+        let bagging = Bagging {
+            num_trees: self.num_trees,
+            max_features: self.max_features,
+            bootstrap: self.bootstrap,
+            seed: self.seed,
+            weights: &weights,
+            nfeatures: x.ncols(),
+        };
+
+        let fitted = bagging.fit_trees(|mask, weights, features| {
+            let dataset = Dataset::new(x.view(), targets).with_weights(weights);
+            self.tree_params
+                .fit_with_features(&dataset, mask, &index, features)
+        });
+
+        let mut forest = RandomForest {
+            trees: Vec::with_capacity(fitted.len()),
+            // all trees see the same targets and share their order of classes
+            classes: fitted[0].0.classes().to_vec(),
+            oob_score: None,
+            num_features: x.ncols(),
+        };
+
+        let mut oob_proba = Array2::zeros((x.nrows(), forest.classes.len()));
+        {
+            let class_idx = forest.class_index();
+            for (tree, oob) in fitted.iter().filter(|_| self.oob_score) {
+                for (i, _) in oob.iter().enumerate().filter(|(_, oob)| **oob) {
+                    add_leaf_proba(tree, &x.row(i), &class_idx, oob_proba.row_mut(i));
+                }
+            }
+        }
+
+        if self.oob_score {
+            let (mut correct, mut total) = (0, 0);
+            let predicted = forest.most_probable(&oob_proba);
+            for ((pred, target), proba) in predicted.iter().zip(targets).zip(oob_proba.genrows()) {
+                if proba.sum() > F::zero() {
+                    total += 1;
+                    if pred == target {
+                        correct += 1;
+                    }
+                }
+            }
+
+            if total > 0 {
+                forest.oob_score = Some(F::from(correct).unwrap() / F::from(total).unwrap());
+            }
+        }
+
+        forest.trees = fitted.into_iter().map(|(tree, _)| tree).collect();
+
+        forest
+    }
+}
+
+/// A fitted random forest for regression.
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
+#[derive(Debug)]
+pub struct RandomForestRegressor<F: Float> {
+    trees: Vec<RegressionTree<F>>,
+    oob_score: Option<F>,
+    num_features: usize,
+}
+
+impl<F: Float> RandomForestRegressor<F> {
+    /// Defaults are provided if the optional parameters are not specified:
+    /// * `tree_params = RegressionTree::params()`
+    /// * `num_trees = 100`
+    /// * `max_features = MaxFeatures::All`
+    /// * `bootstrap = true`
+    /// * `oob_score = false`
+    /// * `seed = 42`
+    // Violates the convention that new should return a value of type `Self`
+    #[allow(clippy::new_ret_no_self)]
+    pub fn params() -> RandomForestRegressorParams<F> {
+        RandomForestRegressorParams {
+            tree_params: RegressionTree::params(),
+            num_trees: 100,
+            max_features: MaxFeatures::All,
+            bootstrap: true,
+            oob_score: false,
+            seed: 42,
+        }
+    }
+
+    /// Return the trees of the forest
+    pub fn trees(&self) -> &[RegressionTree<F>] {
+        &self.trees
+    }
+
+    /// Return the R squared on the out-of-bag samples, if requested during fitting
+    ///
+    /// Each training sample is predicted by the trees which did not see it during fitting.
+    /// Samples drawn by all trees are ignored.
+    pub fn oob_score(&self) -> Option<F> {
+        self.oob_score
+    }
+
+    /// Return the feature importance, i.e. the relative impurity decrease averaged over all
+    /// trees, for each feature
+    pub fn feature_importance(&self) -> Vec<F> {
+        average_importance(
+            self.trees.iter().map(|tree| tree.feature_importance()),
+            self.num_features,
+        )
+    }
+}
+
+impl<F: Float, D: Data<Elem = F>> Predict<ArrayBase<D, Ix2>, Array1<F>>
+    for RandomForestRegressor<F>
+{
+    /// Make predictions for each row of a matrix of features `x`.
+    fn predict(&self, x: ArrayBase<D, Ix2>) -> Array1<F> {
+        let mut predicted = Array1::zeros(x.nrows());
+        for tree in &self.trees {
+            predicted += &tree.predict(x.view());
+        }
+
+        predicted / F::from(self.trees.len()).unwrap()
+    }
+}
+
+impl<F: Float, D: Data<Elem = F>> Predict<&ArrayBase<D, Ix2>, Array1<F>>
+    for RandomForestRegressor<F>
+{
+    fn predict(&self, x: &ArrayBase<D, Ix2>) -> Array1<F> {
+        self.predict(x.view())
+    }
+}
+
+impl<'a, F: Float, D: Data<Elem = F>, T: Targets<Elem = F>> Fit<'a, ArrayBase<D, Ix2>, T>
+    for RandomForestRegressorParams<F>
+{
+    type Object = RandomForestRegressor<F>;
+
+    /// Fit a random forest using `hyperparamters` on the dataset consisting of
+    /// a matrix of features `x` and an array of targets `y`.
+    fn fit(&self, dataset: &Dataset<ArrayBase<D, Ix2>, T>) -> Self::Object {
+        self.validate().unwrap();
+
+        let x = dataset.records().view();
+        let targets = dataset.targets().as_slice();
+        let weights = (0..x.nrows())
+            .map(|i| dataset.weight_for(i))
+            .collect::<Vec<_>>();
+
+        // the features are sorted only once for all trees
+        let sorted_indices: Vec<_> = (0..(x.ncols()))
+            .map(|feature_idx| SortedIndex::of_array_column(&x, feature_idx))
+            .collect();
+
+        let bagging = Bagging {
+            num_trees: self.num_trees,
+            max_features: self.max_features,
+            bootstrap: self.bootstrap,
+            seed: self.seed,
+            weights: &weights,
+            nfeatures: x.ncols(),
+        };
+
+        let fitted = bagging.fit_trees(|mask, weights, features| {
+            let dataset = Dataset::new(x.view(), targets).with_weights(weights);
+            self.tree_params
+                .fit_with_features(&dataset, mask, &sorted_indices, features)
+        });
+
+        let oob_score = if self.oob_score {
+            let mut sums = Array1::<F>::zeros(x.nrows());
+            let mut counts = vec![0usize; x.nrows()];
+            for (tree, oob) in &fitted {
+                for (i, _) in oob.iter().enumerate().filter(|(_, oob)| **oob) {
+                    sums[i] += tree.leaf_for(&x.row(i)).prediction().unwrap();
+                    counts[i] += 1;
+                }
+            }
+
+            let idx = (0..x.nrows())
+                .filter(|i| counts[*i] > 0)
+                .collect::<Vec<_>>();
+            let predicted = idx
+                .iter()
+                .map(|i| sums[*i] / F::from(counts[*i]).unwrap())
+                .collect::<Array1<_>>();
+            let truth = idx.iter().map(|i| targets[*i]).collect::<Array1<_>>();
+
+            if idx.is_empty() {
+                None
+            } else {
+                Some(predicted.r2(&truth))
+            }
+        } else {
+            None
+        };
+
+        RandomForestRegressor {
+            trees: fitted.into_iter().map(|(tree, _)| tree).collect(),
+            oob_score,
+            num_features: x.ncols(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use approx::assert_abs_diff_eq;
+    use linfa::metrics::ToConfusionMatrix;
+    use ndarray::{array, s, Array, Axis};
+    use ndarray_rand::{rand_distr::Uniform, RandomExt};
+
+    /// Four clusters of 25 samples each, one in each quadrant
+    fn four_quadrants(rng: &mut Isaac64Rng) -> Dataset<Array2<f64>, Vec<usize>> {
+        let mut data = Array::random_using((100, 2), Uniform::new(-1., 1.), rng);
+        data.outer_iter_mut().enumerate().for_each(|(i, mut p)| {
+            p += &match i / 25 {
+                0 => array![-2., -2.],
+                1 => array![-2., 2.],
+                2 => array![2., -2.],
+                _ => array![2., 2.],
+            };
+        });
+        let targets = (0..100).map(|i| i / 25).collect::<Vec<_>>();
+
+        Dataset::new(data, targets)
+    }
+
+    #[test]
+    fn max_features_count() {
+        assert_eq!(MaxFeatures::All.num_features(16), 16);
+        assert_eq!(MaxFeatures::Sqrt.num_features(16), 4);
+        assert_eq!(MaxFeatures::Log2.num_features(16), 4);
+        assert_eq!(MaxFeatures::Fixed(20).num_features(16), 16);
+        assert_eq!(MaxFeatures::Fraction(0.5).num_features(16), 8);
+        assert_eq!(MaxFeatures::Fraction(0.01).num_features(16), 1);
+    }
+
+    #[test]
+    /// The forest separates four clusters and estimates its accuracy out-of-bag
+    fn multiclass_four_quadrants() {
+        let mut rng = Isaac64Rng::seed_from_u64(42);
+        let dataset = four_quadrants(&mut rng);
+
+        let model = RandomForest::params()
+            .num_trees(20)
+            .oob_score(true)
+            .fit(&dataset);
+        assert_eq!(model.trees().len(), 20);
+        assert_eq!(model.classes(), &[0, 1, 2, 3]);
+
+        let cm = model.predict(dataset.records()).confusion_matrix(&dataset);
+        assert!(cm.accuracy() > 0.99);
+        assert!(model.oob_score().unwrap() > 0.95);
+
+        // the probabilities of each sample sum to one and favour the predicted class
+        let proba = model.predict_proba(dataset.records());
+        assert_eq!(proba.dim(), (100, 4));
+        for (i, row) in proba.genrows().into_iter().enumerate() {
+            assert_abs_diff_eq!(row.sum(), 1.0, epsilon = 1e-10);
+            assert!(row[i / 25] > 0.5);
+        }
+    }
+
+    #[test]
+    /// Fitting twice with the same seed results in the same forest
+    fn deterministic_with_seed() {
+        let mut rng = Isaac64Rng::seed_from_u64(42);
+        let data = Array::random_using((60, 4), Uniform::new(-1., 1.), &mut rng);
+        let targets = data.column(0).iter().map(|x| *x > 0.0).collect::<Vec<_>>();
+        let dataset = Dataset::new(data, targets);
+
+        let params = RandomForest::params().num_trees(10).seed(7);
+        let first = params.fit(&dataset).predict_proba(dataset.records());
+        let second = params.fit(&dataset).predict_proba(dataset.records());
+        assert_eq!(first, second);
+
+        let other = params
+            .seed(8)
+            .fit(&dataset)
+            .predict_proba(dataset.records());
+        assert!(first != other);
+    }
+
+    #[test]
+    /// Only the informative feature is important
+    fn single_feature_importance() {
+        let mut rng = Isaac64Rng::seed_from_u64(42);
+        let mut data = Array::random_using((100, 6), Uniform::new(-4., 4.), &mut rng);
+        data.slice_mut(s![.., 3]).assign(
+            &(0..100)
+                .map(|x| if x < 50 { 0.0 } else { 1.0 })
+                .collect::<Array1<_>>(),
+        );
+        let targets = (0..100).map(|x| x < 50).collect::<Vec<_>>();
+        let dataset = Dataset::new(data, targets);
+
+        let model = RandomForest::params()
+            .num_trees(30)
+            .max_features(MaxFeatures::All)
+            .tree_params(DecisionTree::params().max_depth(Some(1)))
+            .fit(&dataset);
+
+        let importance = model.feature_importance();
+        assert_abs_diff_eq!(importance.iter().sum::<f64>(), 1.0, epsilon = 1e-10);
+        assert_abs_diff_eq!(importance[3], 1.0, epsilon = 1e-10);
+    }
+
+    #[test]
+    /// The regressor fits a smooth function and estimates its R squared out-of-bag
+    fn regression_parabola() {
+        let mut rng = Isaac64Rng::seed_from_u64(42);
+        let data = Array::random_using((200, 3), Uniform::new(-1., 1.), &mut rng);
+        let targets = data.column(0).mapv(|x| x * x);
+        let dataset = Dataset::new(data.clone(), targets.clone());
+
+        let model = RandomForestRegressor::params()
+            .num_trees(30)
+            .oob_score(true)
+            .fit(&dataset);
+
+        assert!(model.predict(&data).r2(&targets) > 0.95);
+        assert!(model.oob_score().unwrap() > 0.8);
+
+        let importance = model.feature_importance();
+        assert!(importance[0] > importance[1] && importance[0] > importance[2]);
+    }
+
+    #[test]
+    /// Without bootstrapping and with all features every tree is the same
+    fn regression_without_bootstrap() {
+        let data = Array::linspace(0., 1., 20).insert_axis(Axis(1));
+        let targets = data.column(0).mapv(|x| if x < 0.5 { 1.0 } else { 2.0 });
+        let dataset = Dataset::new(data.clone(), targets.clone());
+
+        let model = RandomForestRegressor::params()
+            .num_trees(3)
+            .bootstrap(false)
+            .fit(&dataset);
+        let tree = RegressionTree::params().fit(&dataset);
+
+        assert_abs_diff_eq!(model.predict(&data), tree.predict(&data), epsilon = 1e-10);
+    }
+
+    #[test]
+    fn invalid_parameters() {
+        assert!(RandomForest::<f64, bool>::params()
+            .num_trees(0)
+            .validate()
+            .is_err());
+        assert!(RandomForest::<f64, bool>::params()
+            .bootstrap(false)
+            .oob_score(true)
+            .validate()
+            .is_err());
+        assert!(RandomForestRegressor::<f64>::params()
+            .max_features(MaxFeatures::Fraction(0.0))
+            .validate()
+            .is_err());
+    }
+}
@@ -0,0 +1,208 @@
+use crate::decision_trees::{DecisionTreeParams, RegressionTreeParams};
+use linfa::{
+    error::{Error, Result},
+    Float, Label,
+};
+
+#[cfg(feature = "serde")]
+use serde_crate::{Deserialize, Serialize};
+
+/// The number of features considered at each split of a tree in the forest.
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MaxFeatures {
+    /// All features
+    All,
+    /// The square root of the number of features
+    Sqrt,
+    /// The binary logarithm of the number of features
+    Log2,
+    /// A fixed number of features
+    Fixed(usize),
+    /// A fraction of the features in the interval (0, 1]
+    Fraction(f32),
+}
+
+impl MaxFeatures {
+    /// Number of features drawn for each split, at least one and at most `nfeatures`
+    pub fn num_features(&self, nfeatures: usize) -> usize {
+        let num = match *self {
+            MaxFeatures::All => nfeatures,
+            MaxFeatures::Sqrt => (nfeatures as f32).sqrt() as usize,
+            MaxFeatures::Log2 => (nfeatures as f32).log2() as usize,
+            MaxFeatures::Fixed(num) => num,
+            MaxFeatures::Fraction(fraction) => (fraction * nfeatures as f32) as usize,
+        };
+
+        num.max(1).min(nfeatures)
+    }
+
+    fn validate(&self) -> Result<()> {
+        match *self {
+            MaxFeatures::Fixed(0) => Err(Error::Parameters(
+                "At least one feature should be considered at each split".to_string(),
+            )),
+            MaxFeatures::Fraction(fraction) if fraction <= 0.0 || fraction > 1.0 => {
+                Err(Error::Parameters(format!(
+                    "Fraction of features should be in the interval (0, 1], but was {}",
+                    fraction
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Validate the parameters of a forest, apart from the parameters of its trees
+fn validate_ensemble(
+    num_trees: usize,
+    max_features: &MaxFeatures,
+    bootstrap: bool,
+    oob_score: bool,
+) -> Result<()> {
+    if num_trees == 0 {
+        return Err(Error::Parameters(
+            "A random forest needs at least one tree".to_string(),
+        ));
+    }
+
+    if oob_score && !bootstrap {
+        return Err(Error::Parameters(
+            "Out-of-bag scores are only available with bootstrapping".to_string(),
+        ));
+    }
+
+    max_features.validate()
+}
+
+/// The set of hyperparameters that can be specified for fitting a
+/// [random forest](struct.RandomForest.html).
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
+#[derive(Clone, Copy, Debug)]
+pub struct RandomForestParams<F, L> {
+    pub tree_params: DecisionTreeParams<F, L>,
+    pub num_trees: usize,
+    pub max_features: MaxFeatures,
+    pub bootstrap: bool,
+    pub oob_score: bool,
+    pub seed: u64,
+}
+
+impl<F: Float, L: Label> RandomForestParams<F, L> {
+    /// Hyperparameters of each tree in the forest
+    pub fn tree_params(mut self, tree_params: DecisionTreeParams<F, L>) -> Self {
+        self.tree_params = tree_params;
+        self
+    }
+
+    pub fn num_trees(mut self, num_trees: usize) -> Self {
+        self.num_trees = num_trees;
+        self
+    }
+
+    pub fn max_features(mut self, max_features: MaxFeatures) -> Self {
+        self.max_features = max_features;
+        self
+    }
+
+    /// Fit each tree on a bootstrap sample instead of the whole dataset
+    pub fn bootstrap(mut self, bootstrap: bool) -> Self {
+        self.bootstrap = bootstrap;
+        self
+    }
+
+    /// Estimate the accuracy on the samples left out by the bootstrap of each tree
+    pub fn oob_score(mut self, oob_score: bool) -> Self {
+        self.oob_score = oob_score;
+        self
+    }
+
+    /// Seed of the random number generator drawing the bootstrap samples and features
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        validate_ensemble(
+            self.num_trees,
+            &self.max_features,
+            self.bootstrap,
+            self.oob_score,
+        )?;
+
+        self.tree_params.validate()
+    }
+}
+
+/// The set of hyperparameters that can be specified for fitting a
+/// [random forest regressor](struct.RandomForestRegressor.html).
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
+#[derive(Clone, Copy, Debug)]
+pub struct RandomForestRegressorParams<F> {
+    pub tree_params: RegressionTreeParams<F>,
+    pub num_trees: usize,
+    pub max_features: MaxFeatures,
+    pub bootstrap: bool,
+    pub oob_score: bool,
+    pub seed: u64,
+}
+
+impl<F: Float> RandomForestRegressorParams<F> {
+    /// Hyperparameters of each tree in the forest
+    pub fn tree_params(mut self, tree_params: RegressionTreeParams<F>) -> Self {
+        self.tree_params = tree_params;
+        self
+    }
+
+    pub fn num_trees(mut self, num_trees: usize) -> Self {
+        self.num_trees = num_trees;
+        self
+    }
+
+    pub fn max_features(mut self, max_features: MaxFeatures) -> Self {
+        self.max_features = max_features;
+        self
+    }
+
+    /// Fit each tree on a bootstrap sample instead of the whole dataset
+    pub fn bootstrap(mut self, bootstrap: bool) -> Self {
+        self.bootstrap = bootstrap;
+        self
+    }
+
+    /// Estimate the R squared on the samples left out by the bootstrap of each tree
+    pub fn oob_score(mut self, oob_score: bool) -> Self {
+        self.oob_score = oob_score;
+        self
+    }
+
+    /// Seed of the random number generator drawing the bootstrap samples and features
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        validate_ensemble(
+            self.num_trees,
+            &self.max_features,
+            self.bootstrap,
+            self.oob_score,
+        )?;
+
+        self.tree_params.validate()
+    }
+}
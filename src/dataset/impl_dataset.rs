@@ -1,6 +1,7 @@
 use ndarray::{Array1, Array2, ArrayBase, ArrayView1, ArrayView2, Axis, Data, Dimension, Ix2};
 use rand::{seq::SliceRandom, Rng};
 use std::collections::HashMap;
+use std::iter::FromIterator;
 
 use super::{iter::Iter, Dataset, Float, Label, Labels, Records, Targets};
 
@@ -134,6 +135,58 @@ impl<F: Float, T: Clone> Dataset<Array2<F>, Array1<T>> {
     }
 }
 
+impl<F: Float, E: Clone, T: Targets<Elem = E> + FromIterator<E>> Dataset<Array2<F>, T> {
+    /// Split the dataset into `k` folds for cross-validation
+    ///
+    /// Returns a pair of training and validation set for each fold. The validation sets are
+    /// consecutive, non-overlapping chunks of the dataset and the training set contains the
+    /// remaining observations, so the dataset should be shuffled beforehand. Weights are kept for
+    /// both sets.
+    ///
+    /// # Panics
+    ///
+    /// If `k` is smaller than two or larger than the number of observations.
+    pub fn fold(&self, k: usize) -> Vec<(Self, Self)> {
+        let n = self.observations();
+        assert!(
+            k >= 2 && k <= n,
+            "number of folds should be in [2, {}], but is {}",
+            n,
+            k
+        );
+
+        // the first `n % k` folds contain an additional observation
+        let (size, remainder) = (n / k, n % k);
+
+        (0..k)
+            .map(|i| {
+                let start = i * size + i.min(remainder);
+                let end = start + size + if i < remainder { 1 } else { 0 };
+
+                let valid = (start..end).collect::<Vec<_>>();
+                let train = (0..start).chain(end..n).collect::<Vec<_>>();
+
+                (self.select(&train), self.select(&valid))
+            })
+            .collect()
+    }
+
+    pub(crate) fn select(&self, indices: &[usize]) -> Self {
+        let records = self.records().select(Axis(0), indices);
+        let targets = indices
+            .iter()
+            .map(|x| self.targets.as_slice()[*x].clone())
+            .collect::<T>();
+        let weights = if self.weights.is_empty() {
+            Vec::new()
+        } else {
+            indices.iter().map(|x| self.weights[*x]).collect()
+        };
+
+        Dataset::new(records, targets).with_weights(weights)
+    }
+}
+
 #[allow(clippy::type_complexity)]
 impl<F: Float, T: Targets, D: Data<Elem = F>> Dataset<ArrayBase<D, Ix2>, T> {
     pub fn split_with_ratio(
@@ -0,0 +1,547 @@
+//! Model inspection
+//!
+//! Model-agnostic tools to understand how a fitted model uses its features. They only rely on
+//! the `Predict` trait and can therefore be used to compare different kinds of models.
+//!
+//! A model qualifies, if it implements `Predict<Array2<F>, P>` for an owned record matrix. This
+//! is the case for the decision trees, forests and gradient boosted trees, the SVMs, the
+//! ordinary least squares regression `FittedLinearRegression` and the PCA. Models which only
+//! predict from a reference, like the clustering algorithms, or have no `Predict`
+//! implementation, like the logistic regression, are not supported.
+use ndarray::{Array1, Array2, ArrayBase, ArrayD, Axis, Data, Ix2, IxDyn};
+use rand::{seq::SliceRandom, Rng};
+
+use crate::dataset::{Dataset, Float, Pr, Records, Targets};
+use crate::error::{Error, Result};
+use crate::traits::Predict;
+
+/// Result of the permutation feature importance
+///
+/// Contains the score of the unmodified dataset and the drop of the score for each feature and
+/// repetition.
+#[derive(Debug, Clone)]
+pub struct PermutationImportance<A> {
+    pub baseline: A,
+    /// Score drops with shape `(n_features, n_repeats)`
+    pub importances: Array2<A>,
+}
+
+impl<A: Float> PermutationImportance<A> {
+    /// Mean score drop of each feature
+    pub fn mean(&self) -> Array1<A> {
+        self.importances.mean_axis(Axis(1)).unwrap()
+    }
+
+    /// Standard deviation of the score drops of each feature
+    pub fn std(&self) -> Array1<A> {
+        self.importances.std_axis(Axis(1), A::zero())
+    }
+}
+
+/// Permutation feature importance
+///
+/// Measures how much the score of a fitted model drops, if the values of a single feature are
+/// shuffled and its relation to the target is destroyed. Each feature column is shuffled
+/// `n_repeats` times with the random number generator `rng`. The `score` closure compares the
+/// prediction with the ground truth and should be larger for better predictions, for example
+/// the accuracy or the r2 score. Error metrics can be negated to follow this convention.
+///
+/// # Example
+///
+/// ```ignore
+/// let mut rng = Isaac64Rng::seed_from_u64(42);
+/// let importance = permutation_importance(&model, &valid, 10, &mut rng, |pred: Vec<bool>, truth: &Vec<bool>| {
+///     pred.as_slice().confusion_matrix(truth.as_slice()).accuracy()
+/// })?;
+///
+/// println!("{} +- {}", importance.mean(), importance.std());
+/// ```
+pub fn permutation_importance<F, D, T, P, A, M, R, S>(
+    model: &M,
+    dataset: &Dataset<ArrayBase<D, Ix2>, T>,
+    n_repeats: usize,
+    rng: &mut R,
+    mut score: S,
+) -> Result<PermutationImportance<A>>
+where
+    F: Float,
+    D: Data<Elem = F>,
+    T: Targets,
+    A: Float,
+    M: Predict<Array2<F>, P>,
+    R: Rng,
+    S: FnMut(P, &T) -> A,
+{
+    if n_repeats == 0 {
+        return Err(Error::Parameters(
+            "Number of repetitions should be greater than zero".to_string(),
+        ));
+    }
+
+    if dataset.observations() == 0 {
+        return Err(Error::Parameters(
+            "Permutation importance needs at least one observation".to_string(),
+        ));
+    }
+
+    let baseline = score(
+        model.predict(dataset.records().to_owned()),
+        dataset.targets(),
+    );
+
+    let n_features = dataset.records().ncols();
+    let mut importances = Array2::zeros((n_features, n_repeats));
+    let mut records = dataset.records().to_owned();
+    let mut indices = (0..dataset.observations()).collect::<Vec<_>>();
+
+    for feature in 0..n_features {
+        let column = dataset.records().column(feature);
+
+        for repeat in 0..n_repeats {
+            indices.shuffle(rng);
+            records
+                .column_mut(feature)
+                .iter_mut()
+                .zip(indices.iter())
+                .for_each(|(x, idx)| *x = column[*idx]);
+
+            let permuted = score(model.predict(records.clone()), dataset.targets());
+            importances[(feature, repeat)] = baseline - permuted;
+        }
+
+        // restore the original column before shuffling the next one
+        records.column_mut(feature).assign(&column);
+    }
+
+    Ok(PermutationImportance {
+        baseline,
+        importances,
+    })
+}
+
+/// Grid of feature values for the partial dependence
+#[derive(Debug, Clone)]
+pub enum Grid<F> {
+    /// Equally spaced quantiles of each feature between the `lower` and `upper` percentile
+    ///
+    /// If a feature has fewer unique values than `resolution`, then these values are used
+    /// instead.
+    Quantiles {
+        resolution: usize,
+        lower: f32,
+        upper: f32,
+    },
+    /// Explicit grid values for each feature
+    Values(Vec<Array1<F>>),
+}
+
+impl<F> Default for Grid<F> {
+    /// Quantile grid with 100 points between the 5% and 95% percentile
+    fn default() -> Self {
+        Grid::Quantiles {
+            resolution: 100,
+            lower: 0.05,
+            upper: 0.95,
+        }
+    }
+}
+
+/// Predictions which can be averaged for the partial dependence
+///
+/// This is implemented for regression outputs and probabilities, in case of probabilities the
+/// averaged value is the probability of the positive class.
+pub trait PredictionValues<F> {
+    fn into_values(self) -> Array1<F>;
+}
+
+impl<F: Float> PredictionValues<F> for Array1<F> {
+    fn into_values(self) -> Array1<F> {
+        self
+    }
+}
+
+impl<F: Float> PredictionValues<F> for Vec<F> {
+    fn into_values(self) -> Array1<F> {
+        Array1::from(self)
+    }
+}
+
+impl<F: Float> PredictionValues<F> for Vec<Pr> {
+    fn into_values(self) -> Array1<F> {
+        self.into_iter().map(|x| F::from(*x).unwrap()).collect()
+    }
+}
+
+/// Result of the partial dependence
+///
+/// The averaged predictions have one axis for each feature, so a single feature produces a curve
+/// and a pair of features a surface. The individual conditional expectation (ICE) curves have an
+/// additional leading axis for the samples.
+#[derive(Debug, Clone)]
+pub struct PartialDependence<F> {
+    pub grid: Vec<Array1<F>>,
+    pub average: ArrayD<F>,
+    pub individual: Option<ArrayD<F>>,
+}
+
+/// Partial dependence of the prediction on a set of features
+///
+/// For each point of the grid the given `features` of all records are set to the grid values
+/// and the predictions of the model are averaged. This shows the marginal effect of the
+/// features on the prediction. If `individual` is set, then the predictions of each sample are
+/// kept as well, producing the individual conditional expectation (ICE) curves.
+///
+/// The number of model evaluations grows with the product of the grid sizes, so usually one
+/// or two features are used.
+///
+/// # Example
+///
+/// ```ignore
+/// let pd = partial_dependence(&model, dataset.records(), &[0, 2], Grid::default(), false)?;
+///
+/// // surface with shape (grid[0].len(), grid[1].len())
+/// println!("{}", pd.average);
+/// ```
+pub fn partial_dependence<F, D, P, M>(
+    model: &M,
+    records: &ArrayBase<D, Ix2>,
+    features: &[usize],
+    grid: Grid<F>,
+    individual: bool,
+) -> Result<PartialDependence<F>>
+where
+    F: Float,
+    D: Data<Elem = F>,
+    P: PredictionValues<F>,
+    M: Predict<Array2<F>, P>,
+{
+    if features.is_empty() {
+        return Err(Error::Parameters(
+            "Partial dependence needs at least one feature".to_string(),
+        ));
+    }
+
+    if let Some(idx) = features.iter().find(|x| **x >= records.ncols()) {
+        return Err(Error::Parameters(format!(
+            "Feature index {} is out of bounds for {} features",
+            idx,
+            records.ncols()
+        )));
+    }
+
+    if records.nrows() == 0 {
+        return Err(Error::Parameters(
+            "Partial dependence needs at least one observation".to_string(),
+        ));
+    }
+
+    let grid = match grid {
+        Grid::Quantiles {
+            resolution,
+            lower,
+            upper,
+        } => {
+            if resolution < 2 {
+                return Err(Error::Parameters(format!(
+                    "Grid resolution should be at least two, but got {}",
+                    resolution
+                )));
+            }
+
+            if lower < 0.0 || upper > 1.0 || lower >= upper {
+                return Err(Error::Parameters(format!(
+                    "Percentiles should satisfy 0 <= lower < upper <= 1, but got {} and {}",
+                    lower, upper
+                )));
+            }
+
+            let grid = features
+                .iter()
+                .map(|idx| {
+                    quantile_grid(
+                        records.column(*idx).to_vec(),
+                        resolution,
+                        lower as f64,
+                        upper as f64,
+                    )
+                })
+                .collect::<Vec<_>>();
+
+            if let Some(pos) = grid.iter().position(|x| x.is_empty()) {
+                return Err(Error::Parameters(format!(
+                    "Feature {} has no values apart from NaN",
+                    features[pos]
+                )));
+            }
+
+            grid
+        }
+        Grid::Values(values) => {
+            if values.len() != features.len() || values.iter().any(|x| x.is_empty()) {
+                return Err(Error::Parameters(
+                    "Expected non-empty grid values for each feature".to_string(),
+                ));
+            }
+
+            values
+        }
+    };
+
+    let shape = grid.iter().map(|x| x.len()).collect::<Vec<_>>();
+    let n_points = shape.iter().product::<usize>();
+    let n_samples = records.nrows();
+
+    let mut records = records.to_owned();
+    let mut average = Array1::zeros(n_points);
+    let mut predictions = if individual {
+        Some(Array2::zeros((n_samples, n_points)))
+    } else {
+        None
+    };
+
+    for point in 0..n_points {
+        // decompose the flat index in row-major order
+        let mut rest = point;
+        for (i, (feature, values)) in features.iter().zip(grid.iter()).enumerate().rev() {
+            let value = values[rest % shape[i]];
+            rest /= shape[i];
+
+            records.column_mut(*feature).fill(value);
+        }
+
+        let values = model.predict(records.clone()).into_values();
+        average[point] = values.mean().unwrap();
+
+        if let Some(ref mut predictions) = predictions {
+            predictions.column_mut(point).assign(&values);
+        }
+    }
+
+    let average = average.into_shape(IxDyn(&shape)).unwrap();
+    let individual = predictions.map(|x| {
+        let mut shape = shape.clone();
+        shape.insert(0, n_samples);
+
+        x.into_shape(IxDyn(&shape)).unwrap()
+    });
+
+    Ok(PartialDependence {
+        grid,
+        average,
+        individual,
+    })
+}
+
+/// Equally spaced quantiles of a feature, or its unique values if there are less
+///
+/// NaN values are ignored, the grid is empty if there are no other values.
+fn quantile_grid<F: Float>(values: Vec<F>, resolution: usize, lower: f64, upper: f64) -> Array1<F> {
+    let mut values = values
+        .into_iter()
+        .filter(|x| !x.is_nan())
+        .collect::<Vec<_>>();
+    // values without NaN are totally ordered
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    let mut unique = values.clone();
+    unique.dedup();
+    if unique.len() <= resolution {
+        return Array1::from(unique);
+    }
+
+    let mut grid = (0..resolution)
+        .map(|i| {
+            let q = lower + (upper - lower) * i as f64 / (resolution - 1) as f64;
+            let pos = q * (values.len() - 1) as f64;
+            let (low, high) = (pos.floor() as usize, pos.ceil() as usize);
+            let weight = F::from(pos - low as f64).unwrap();
+
+            values[low] + (values[high] - values[low]) * weight
+        })
+        .collect::<Vec<_>>();
+    grid.dedup();
+
+    Array1::from(grid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{partial_dependence, permutation_importance, quantile_grid, Grid};
+    use crate::dataset::{Dataset, Pr};
+    use crate::metrics::Regression;
+    use crate::traits::Predict;
+    use approx::assert_abs_diff_eq;
+    use ndarray::{array, Array1, Array2};
+    use rand::{distributions::Uniform, Rng, SeedableRng};
+    use rand_isaac::Isaac64Rng;
+
+    /// Linear model with fixed coefficients
+    struct Linear(Array1<f64>);
+
+    impl Predict<Array2<f64>, Array1<f64>> for Linear {
+        fn predict(&self, x: Array2<f64>) -> Array1<f64> {
+            x.outer_iter().map(|row| (&row * &self.0).sum()).collect()
+        }
+    }
+
+    /// Classifier with the probability clipped to the first feature
+    struct Clipped;
+
+    impl Predict<Array2<f64>, Vec<Pr>> for Clipped {
+        fn predict(&self, x: Array2<f64>) -> Vec<Pr> {
+            x.column(0)
+                .iter()
+                .map(|x| Pr(x.clamp(0.0, 1.0) as f32))
+                .collect()
+        }
+    }
+
+    #[test]
+    fn test_permutation_importance() {
+        let mut rng = Isaac64Rng::seed_from_u64(42);
+
+        let records = Array2::from_shape_fn((100, 3), |_| rng.sample(Uniform::new(0.0, 1.0)));
+        let model = Linear(Array1::from(vec![2.0, 0.5, 0.0]));
+        let targets = model.predict(records.clone());
+        let dataset = Dataset::new(records, targets);
+
+        let importance = permutation_importance(
+            &model,
+            &dataset,
+            5,
+            &mut rng,
+            |pred: Array1<f64>, truth: &Array1<f64>| pred.r2(truth),
+        )
+        .unwrap();
+
+        assert_abs_diff_eq!(importance.baseline, 1.0, epsilon = 1e-10);
+        assert_eq!(importance.importances.dim(), (3, 5));
+
+        let mean = importance.mean();
+        // the first feature dominates the prediction
+        assert!(mean[0] > mean[1]);
+        assert!(mean[1] > 0.0);
+        // the last feature is not used at all
+        assert_abs_diff_eq!(mean[2], 0.0);
+        assert_abs_diff_eq!(importance.std()[2], 0.0);
+    }
+
+    #[test]
+    fn test_seeded_permutation_importance() {
+        let mut rng = Isaac64Rng::seed_from_u64(42);
+
+        let records = Array2::from_shape_fn((50, 2), |_| rng.sample(Uniform::new(0.0, 1.0)));
+        let model = Linear(Array1::from(vec![1.0, -1.0]));
+        let targets = model.predict(records.clone());
+        let dataset = Dataset::new(records, targets);
+
+        let run = |seed| {
+            let mut rng = Isaac64Rng::seed_from_u64(seed);
+            permutation_importance(
+                &model,
+                &dataset,
+                3,
+                &mut rng,
+                |pred: Array1<f64>, truth: &Array1<f64>| -pred.mean_squared_error(truth),
+            )
+            .unwrap()
+            .importances
+        };
+
+        // the same seed reproduces the importances
+        assert_eq!(run(1), run(1));
+
+        let mut rng = Isaac64Rng::seed_from_u64(42);
+        assert!(permutation_importance(
+            &model,
+            &dataset,
+            0,
+            &mut rng,
+            |pred: Array1<f64>, truth: &Array1<f64>| pred.r2(truth),
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_quantile_grid() {
+        // few unique values are used directly
+        let grid = quantile_grid(vec![3.0, 1.0, 2.0, 1.0], 10, 0.05, 0.95);
+        assert_eq!(grid, array![1.0, 2.0, 3.0]);
+
+        let values = (0..=100).map(|x| x as f64).collect::<Vec<_>>();
+        let grid = quantile_grid(values, 5, 0.1, 0.9);
+        assert_eq!(grid.len(), 5);
+        for (value, expected) in grid.iter().zip(&[10.0, 30.0, 50.0, 70.0, 90.0]) {
+            assert_abs_diff_eq!(*value, *expected, epsilon = 1e-10);
+        }
+
+        // missing values are ignored
+        let grid = quantile_grid(vec![2.0, f64::NAN, 1.0], 10, 0.05, 0.95);
+        assert_eq!(grid, array![1.0, 2.0]);
+        assert!(quantile_grid(vec![f64::NAN], 10, 0.05, 0.95).is_empty());
+    }
+
+    #[test]
+    fn test_partial_dependence() {
+        let records = array![[0.0, 1.0], [1.0, 3.0], [2.0, 5.0]];
+        let model = Linear(Array1::from(vec![2.0, 0.5]));
+
+        let pd = partial_dependence(
+            &model,
+            &records,
+            &[0],
+            Grid::Values(vec![array![0.0, 1.0, 2.0]]),
+            true,
+        )
+        .unwrap();
+
+        // the second feature contributes 0.5 * 3 on average
+        assert_eq!(pd.average.shape(), &[3]);
+        for (avg, expected) in pd.average.iter().zip(&[1.5, 3.5, 5.5]) {
+            assert_abs_diff_eq!(*avg, *expected);
+        }
+
+        let ice = pd.individual.unwrap();
+        assert_eq!(ice.shape(), &[3, 3]);
+        assert_abs_diff_eq!(ice[[0, 1]], 2.5);
+        assert_abs_diff_eq!(ice[[2, 0]], 2.5);
+
+        // a pair of features produces a surface
+        let pd = partial_dependence(&model, &records, &[0, 1], Grid::default(), false).unwrap();
+        assert_eq!(pd.average.shape(), &[3, 3]);
+        assert_eq!(pd.grid[1], array![1.0, 3.0, 5.0]);
+        assert_abs_diff_eq!(pd.average[[2, 1]], 5.5);
+        assert!(pd.individual.is_none());
+
+        assert!(partial_dependence(&model, &records, &[2], Grid::default(), false).is_err());
+        assert!(partial_dependence(&model, &records, &[], Grid::default(), false).is_err());
+
+        // a feature with only missing values has no grid
+        let mut missing = records.clone();
+        missing.column_mut(0).fill(f64::NAN);
+        assert!(partial_dependence(&model, &missing, &[0], Grid::default(), false).is_err());
+    }
+
+    #[test]
+    fn test_partial_dependence_probabilities() {
+        let records = Array2::from_shape_fn((20, 2), |(i, j)| (i + j) as f64 / 10.0);
+
+        let pd = partial_dependence(
+            &Clipped,
+            &records,
+            &[0],
+            Grid::Quantiles {
+                resolution: 5,
+                lower: 0.0,
+                upper: 1.0,
+            },
+            false,
+        )
+        .unwrap();
+
+        assert_eq!(pd.grid[0].len(), 5);
+        assert_abs_diff_eq!(pd.average[0], 0.0);
+        assert_abs_diff_eq!(pd.average[2], 0.95, epsilon = 1e-6);
+        assert_abs_diff_eq!(pd.average[4], 1.0);
+    }
+}
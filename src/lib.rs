@@ -30,8 +30,11 @@
 
 pub mod dataset;
 pub mod error;
+pub mod inspection;
+mod metrics_bootstrap;
 mod metrics_classification;
 mod metrics_regression;
+pub mod model_selection;
 pub mod prelude;
 pub mod traits;
 
@@ -49,10 +52,13 @@ extern crate openblas_src;
 #[cfg(any(feature = "netblas-system", feature = "netblas-static"))]
 extern crate netblas_src;
 
-/// Common metrics functions for classification and regression
+/// Common metrics functions for classification and regression, and bootstrapped confidence intervals
 pub mod metrics {
+    pub use crate::metrics_bootstrap::{
+        Bootstrap, BootstrapParams, ConfidenceInterval, PairedBootstrap,
+    };
     pub use crate::metrics_classification::{
         BinaryClassification, ConfusionMatrix, ReceiverOperatingCharacteristic, ToConfusionMatrix,
     };
-    pub use crate::metrics_regression::Regression;
+    pub use crate::metrics_regression::{MultiOutput, MultiTargetRegression, Regression};
 }
@@ -0,0 +1,699 @@
+//! Bootstrapped confidence intervals for metrics
+//!
+//! A metric evaluated on a single test split is only a point estimate. This module resamples the
+//! pairs of prediction and ground truth with replacement, evaluates an arbitrary metric for each
+//! resample and derives confidence intervals from the resulting distribution. A paired bootstrap
+//! test compares the predictions of two models on the same ground truth.
+use ndarray::{Array1, Array2, ArrayView1};
+use rand::Rng;
+
+use crate::dataset::{Dataset, Float, Targets};
+use crate::error::{Error, Result};
+
+/// A two-sided confidence interval
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConfidenceInterval<A> {
+    pub lower: A,
+    pub upper: A,
+}
+
+impl<A: Float> ConfidenceInterval<A> {
+    /// Returns true if the value lies inside the interval
+    pub fn contains(&self, value: A) -> bool {
+        value >= self.lower && value <= self.upper
+    }
+}
+
+/// Parameters of the bootstrap procedure
+///
+/// The same parameters can be used to estimate the distribution of a single metric with
+/// `evaluate` or to compare two models with `compare`.
+#[derive(Debug, Clone)]
+pub struct BootstrapParams {
+    num_resamples: usize,
+    jackknife: bool,
+}
+
+impl BootstrapParams {
+    /// Number of resamples drawn with replacement
+    pub fn num_resamples(mut self, num_resamples: usize) -> Self {
+        self.num_resamples = num_resamples;
+
+        self
+    }
+
+    /// Evaluate the leave-one-out jackknife estimates
+    ///
+    /// These are necessary for BCa intervals, but require to evaluate the metric once for each
+    /// sample. For large test sets this can be disabled and only percentile intervals are
+    /// available.
+    pub fn jackknife(mut self, jackknife: bool) -> Self {
+        self.jackknife = jackknife;
+
+        self
+    }
+
+    fn validate(&self, n_samples: usize) -> Result<()> {
+        if self.num_resamples == 0 {
+            return Err(Error::Parameters(
+                "Number of resamples should be greater than zero".to_string(),
+            ));
+        }
+
+        if n_samples < 2 {
+            return Err(Error::Parameters(format!(
+                "Bootstrapping needs at least two samples, but got {}",
+                n_samples
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Estimate the distribution of a metric
+    ///
+    /// The pairs of `prediction` and `ground_truth` are resampled with replacement and the
+    /// `metric` is evaluated for each resample. Resamples for which the metric is not finite,
+    /// for example the precision of a resample without positive predictions, are dropped and
+    /// counted in [`num_dropped`](struct.Bootstrap.html#method.num_dropped). Returns an error if
+    /// the metric is not finite on the original data or on all resamples.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let bootstrap = Bootstrap::params()
+    ///     .num_resamples(1000)
+    ///     .evaluate(&prediction, &ground_truth, &mut rng, |pred, truth| {
+    ///         pred.confusion_matrix(truth).accuracy()
+    ///     })?;
+    ///
+    /// println!("accuracy in {:?}", bootstrap.bca_interval(0.95)?);
+    /// ```
+    pub fn evaluate<P, T, A, R, S1, S2, F>(
+        &self,
+        prediction: &S1,
+        ground_truth: &S2,
+        rng: &mut R,
+        mut metric: F,
+    ) -> Result<Bootstrap<A>>
+    where
+        P: Clone,
+        T: Clone,
+        A: Float,
+        R: Rng,
+        S1: Targets<Elem = P>,
+        S2: Targets<Elem = T>,
+        F: FnMut(ArrayView1<P>, ArrayView1<T>) -> A,
+    {
+        let prediction = ArrayView1::from(prediction.as_slice());
+        let ground_truth = ArrayView1::from(ground_truth.as_slice());
+        check_lengths(prediction.len(), ground_truth.len())?;
+        self.validate(prediction.len())?;
+
+        let estimate = check_finite(metric(prediction, ground_truth))?;
+
+        let samples = resample(prediction.len(), self.num_resamples, rng, |indices| {
+            let prediction = select(&prediction, indices);
+            let ground_truth = select(&ground_truth, indices);
+
+            metric(prediction.view(), ground_truth.view())
+        });
+        let (samples, num_dropped) = finite_sorted(samples)?;
+
+        let jackknife = if self.jackknife {
+            Some(
+                (0..prediction.len())
+                    .map(|i| {
+                        let indices = leave_one_out(prediction.len(), i);
+                        let prediction = select(&prediction, &indices);
+                        let ground_truth = select(&ground_truth, &indices);
+
+                        metric(prediction.view(), ground_truth.view())
+                    })
+                    .collect(),
+            )
+        } else {
+            None
+        };
+
+        Ok(Bootstrap {
+            estimate,
+            samples,
+            num_dropped,
+            jackknife,
+        })
+    }
+
+    /// Compare the predictions of two models with a paired bootstrap test
+    ///
+    /// Both predictions are resampled with the same indices and the difference
+    /// `metric(prediction_a) - metric(prediction_b)` is evaluated for each resample. The test
+    /// checks the null hypothesis that both models perform equally well. Non-finite differences
+    /// are handled like in [`evaluate`](#method.evaluate).
+    pub fn compare<P, T, A, R, S1, S2, F>(
+        &self,
+        prediction_a: &S1,
+        prediction_b: &S1,
+        ground_truth: &S2,
+        rng: &mut R,
+        mut metric: F,
+    ) -> Result<PairedBootstrap<A>>
+    where
+        P: Clone,
+        T: Clone,
+        A: Float,
+        R: Rng,
+        S1: Targets<Elem = P>,
+        S2: Targets<Elem = T>,
+        F: FnMut(ArrayView1<P>, ArrayView1<T>) -> A,
+    {
+        let prediction_a = ArrayView1::from(prediction_a.as_slice());
+        let prediction_b = ArrayView1::from(prediction_b.as_slice());
+        let ground_truth = ArrayView1::from(ground_truth.as_slice());
+        check_lengths(prediction_a.len(), ground_truth.len())?;
+        check_lengths(prediction_b.len(), ground_truth.len())?;
+        self.validate(ground_truth.len())?;
+
+        let difference =
+            check_finite(metric(prediction_a, ground_truth) - metric(prediction_b, ground_truth))?;
+
+        let samples = resample(ground_truth.len(), self.num_resamples, rng, |indices| {
+            let prediction_a = select(&prediction_a, indices);
+            let prediction_b = select(&prediction_b, indices);
+            let ground_truth = select(&ground_truth, indices);
+
+            metric(prediction_a.view(), ground_truth.view())
+                - metric(prediction_b.view(), ground_truth.view())
+        });
+        let (samples, num_dropped) = finite_sorted(samples)?;
+
+        Ok(PairedBootstrap {
+            difference,
+            samples,
+            num_dropped,
+        })
+    }
+}
+
+/// Bootstrapped distribution of a metric
+///
+/// Contains the metric evaluated on the full data, the sorted bootstrap replicates and
+/// optionally the jackknife estimates.
+#[derive(Debug, Clone)]
+pub struct Bootstrap<A> {
+    estimate: A,
+    samples: Vec<A>,
+    num_dropped: usize,
+    jackknife: Option<Vec<A>>,
+}
+
+impl Bootstrap<()> {
+    /// Defaults are provided if the optional parameters are not specified:
+    /// * `num_resamples = 1000`
+    /// * `jackknife = true`
+    pub fn params() -> BootstrapParams {
+        BootstrapParams {
+            num_resamples: 1000,
+            jackknife: true,
+        }
+    }
+}
+
+impl<A: Float> Bootstrap<A> {
+    /// Metric evaluated on the original prediction and ground truth
+    pub fn estimate(&self) -> A {
+        self.estimate
+    }
+
+    /// Sorted metric values of all resamples with a finite metric
+    pub fn samples(&self) -> &[A] {
+        &self.samples
+    }
+
+    /// Number of resamples dropped because the metric was not finite
+    pub fn num_dropped(&self) -> usize {
+        self.num_dropped
+    }
+
+    /// Standard deviation of the bootstrap replicates
+    pub fn standard_error(&self) -> A {
+        Array1::from(self.samples.clone()).std_axis(ndarray::Axis(0), A::one())[()]
+    }
+
+    /// Percentile confidence interval
+    ///
+    /// Takes the `(1 - confidence) / 2` and `(1 + confidence) / 2` quantiles of the bootstrap
+    /// distribution.
+    pub fn percentile_interval(&self, confidence: f32) -> Result<ConfidenceInterval<A>> {
+        check_confidence(confidence)?;
+        let alpha = (1.0 - confidence as f64) / 2.0;
+
+        Ok(ConfidenceInterval {
+            lower: quantile(&self.samples, alpha),
+            upper: quantile(&self.samples, 1.0 - alpha),
+        })
+    }
+
+    /// Bias-corrected and accelerated (BCa) confidence interval
+    ///
+    /// Adjusts the percentiles of the bootstrap distribution for its bias and skewness. The bias
+    /// is estimated from the fraction of replicates below the original estimate, the
+    /// acceleration from the finite jackknife estimates. Returns an error if the jackknife
+    /// estimates were disabled.
+    pub fn bca_interval(&self, confidence: f32) -> Result<ConfidenceInterval<A>> {
+        check_confidence(confidence)?;
+
+        let jackknife = self.jackknife.as_ref().ok_or_else(|| {
+            Error::Parameters("BCa intervals need the jackknife estimates".to_string())
+        })?;
+
+        // bias correction from the fraction of replicates below the estimate
+        let below = self.samples.iter().filter(|x| **x < self.estimate).count() as f64;
+        let fraction = below / self.samples.len() as f64;
+        // clamp to avoid infinite values for degenerated distributions
+        let min_fraction = 0.5 / self.samples.len() as f64;
+        let z0 = normal_quantile(fraction.max(min_fraction).min(1.0 - min_fraction));
+
+        // acceleration from the skewness of the jackknife estimates
+        let jackknife = jackknife
+            .iter()
+            .filter(|x| x.is_finite())
+            .map(|x| x.to_f64().unwrap())
+            .collect::<Vec<_>>();
+        let mean = jackknife.iter().sum::<f64>() / jackknife.len() as f64;
+        let (num, denom) = jackknife.iter().fold((0.0, 0.0), |(num, denom), x| {
+            let diff = mean - x;
+            (num + diff * diff * diff, denom + diff * diff)
+        });
+        let acceleration = if denom > 0.0 {
+            num / (6.0 * denom.powf(1.5))
+        } else {
+            0.0
+        };
+
+        let adjusted = |alpha: f64| {
+            let z = z0 + normal_quantile(alpha);
+            normal_cdf(z0 + z / (1.0 - acceleration * z))
+        };
+
+        let alpha = (1.0 - confidence as f64) / 2.0;
+
+        Ok(ConfidenceInterval {
+            lower: quantile(&self.samples, adjusted(alpha)),
+            upper: quantile(&self.samples, adjusted(1.0 - alpha)),
+        })
+    }
+}
+
+/// Result of a paired bootstrap test
+///
+/// Contains the difference of the metric between the first and second model and the sorted
+/// bootstrap replicates of this difference.
+#[derive(Debug, Clone)]
+pub struct PairedBootstrap<A> {
+    difference: A,
+    samples: Vec<A>,
+    num_dropped: usize,
+}
+
+impl<A: Float> PairedBootstrap<A> {
+    /// Difference of the metric on the original predictions
+    pub fn difference(&self) -> A {
+        self.difference
+    }
+
+    /// Sorted differences of all resamples with a finite difference
+    pub fn samples(&self) -> &[A] {
+        &self.samples
+    }
+
+    /// Number of resamples dropped because the difference was not finite
+    pub fn num_dropped(&self) -> usize {
+        self.num_dropped
+    }
+
+    /// Percentile confidence interval of the difference
+    pub fn percentile_interval(&self, confidence: f32) -> Result<ConfidenceInterval<A>> {
+        check_confidence(confidence)?;
+        let alpha = (1.0 - confidence as f64) / 2.0;
+
+        Ok(ConfidenceInterval {
+            lower: quantile(&self.samples, alpha),
+            upper: quantile(&self.samples, 1.0 - alpha),
+        })
+    }
+
+    /// Two-sided p-value for the null hypothesis of equal performance
+    ///
+    /// This is twice the fraction of resampled differences on the less frequent side of zero.
+    pub fn p_value(&self) -> f32 {
+        let n = self.samples.len() as f32;
+        let below = self.samples.iter().filter(|x| **x <= A::zero()).count() as f32;
+        let above = self.samples.iter().filter(|x| **x >= A::zero()).count() as f32;
+
+        f32::min(1.0, 2.0 * f32::min(below, above) / n)
+    }
+}
+
+fn check_lengths(a: usize, b: usize) -> Result<()> {
+    if a != b {
+        return Err(Error::Parameters(format!(
+            "Prediction and ground truth should have the same length, but got {} and {}",
+            a, b
+        )));
+    }
+
+    Ok(())
+}
+
+fn check_confidence(confidence: f32) -> Result<()> {
+    if confidence <= 0.0 || confidence >= 1.0 {
+        return Err(Error::Parameters(format!(
+            "Confidence level should be in (0, 1), but got {}",
+            confidence
+        )));
+    }
+
+    Ok(())
+}
+
+fn check_finite<A: Float>(value: A) -> Result<A> {
+    if !value.is_finite() {
+        return Err(Error::Parameters(format!(
+            "Metric should be finite on the original data, but was {}",
+            value
+        )));
+    }
+
+    Ok(value)
+}
+
+/// Evaluate `f` for the indices of each resample
+///
+/// The resamples are drawn with `Dataset::bootstrap` from a dataset without features, whose
+/// targets are the indices of the samples.
+fn resample<R: Rng, A, F: FnMut(&[usize]) -> A>(
+    n_samples: usize,
+    num_resamples: usize,
+    rng: &mut R,
+    mut f: F,
+) -> Vec<A> {
+    let indices = Dataset::new(
+        Array2::<f32>::zeros((n_samples, 0)),
+        (0..n_samples).collect(),
+    );
+
+    indices
+        .bootstrap(n_samples, rng)
+        .take(num_resamples)
+        .map(|resample| f(resample.targets()))
+        .collect()
+}
+
+/// Sort the finite values and count the dropped ones
+fn finite_sorted<A: Float>(values: Vec<A>) -> Result<(Vec<A>, usize)> {
+    let num_values = values.len();
+    let mut finite = values
+        .into_iter()
+        .filter(|x| x.is_finite())
+        .collect::<Vec<_>>();
+    // finite values are totally ordered
+    finite.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    if finite.is_empty() {
+        return Err(Error::Parameters(
+            "Metric is not finite on any resample".to_string(),
+        ));
+    }
+
+    let num_dropped = num_values - finite.len();
+
+    Ok((finite, num_dropped))
+}
+
+fn leave_one_out(n_samples: usize, idx: usize) -> Vec<usize> {
+    (0..n_samples).filter(|x| *x != idx).collect()
+}
+
+fn select<T: Clone>(values: &ArrayView1<T>, indices: &[usize]) -> Array1<T> {
+    indices.iter().map(|x| values[*x].clone()).collect()
+}
+
+/// Quantile of sorted values with linear interpolation
+fn quantile<A: Float>(sorted: &[A], q: f64) -> A {
+    let pos = q.clamp(0.0, 1.0) * (sorted.len() - 1) as f64;
+    let (lower, upper) = (pos.floor() as usize, pos.ceil() as usize);
+    let weight = A::from(pos - lower as f64).unwrap();
+
+    sorted[lower] + (sorted[upper] - sorted[lower]) * weight
+}
+
+/// Cumulative distribution function of the standard normal distribution
+///
+/// Uses the approximation of the error function 7.1.26 in Abramowitz and Stegun with a maximal
+/// error of 1.5e-7.
+fn normal_cdf(x: f64) -> f64 {
+    let z = x.abs() / std::f64::consts::SQRT_2;
+    let t = 1.0 / (1.0 + 0.327_591_1 * z);
+    let poly = t
+        * (0.254_829_592
+            + t * (-0.284_496_736
+                + t * (1.421_413_741 + t * (-1.453_152_027 + t * 1.061_405_429))));
+    let erf = 1.0 - poly * (-z * z).exp();
+
+    if x >= 0.0 {
+        0.5 * (1.0 + erf)
+    } else {
+        0.5 * (1.0 - erf)
+    }
+}
+
+/// Quantile function of the standard normal distribution
+///
+/// Uses the rational approximation of Peter Acklam with a relative error of 1.15e-9.
+fn normal_quantile(p: f64) -> f64 {
+    const A: [f64; 6] = [
+        -3.969_683_028_665_376e1,
+        2.209_460_984_245_205e2,
+        -2.759_285_104_469_687e2,
+        1.383_577_518_672_69e2,
+        -3.066_479_806_614_716e1,
+        2.506_628_277_459_239,
+    ];
+    const B: [f64; 5] = [
+        -5.447_609_879_822_406e1,
+        1.615_858_368_580_409e2,
+        -1.556_989_798_598_866e2,
+        6.680_131_188_771_972e1,
+        -1.328_068_155_288_572e1,
+    ];
+    const C: [f64; 6] = [
+        -7.784_894_002_430_293e-3,
+        -3.223_964_580_411_365e-1,
+        -2.400_758_277_161_838,
+        -2.549_732_539_343_734,
+        4.374_664_141_464_968,
+        2.938_163_982_698_783,
+    ];
+    const D: [f64; 4] = [
+        7.784_695_709_041_462e-3,
+        3.224_671_290_700_398e-1,
+        2.445_134_137_142_996,
+        3.754_408_661_907_416,
+    ];
+    const P_LOW: f64 = 0.024_25;
+
+    if p <= 0.0 {
+        return f64::NEG_INFINITY;
+    } else if p >= 1.0 {
+        return f64::INFINITY;
+    }
+
+    if p < P_LOW {
+        let q = (-2.0 * p.ln()).sqrt();
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    } else if p <= 1.0 - P_LOW {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    } else {
+        -normal_quantile(1.0 - p)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{normal_cdf, normal_quantile, quantile, Bootstrap};
+    use crate::metrics::{Regression, ToConfusionMatrix};
+    use approx::assert_abs_diff_eq;
+    use ndarray::{Array1, ArrayView1};
+    use rand::{distributions::Uniform, Rng, SeedableRng};
+    use rand_isaac::Isaac64Rng;
+
+    #[test]
+    fn test_normal_distribution() {
+        assert_abs_diff_eq!(normal_cdf(0.0), 0.5, epsilon = 1e-7);
+        assert_abs_diff_eq!(normal_cdf(1.959_964), 0.975, epsilon = 1e-6);
+        assert_abs_diff_eq!(normal_quantile(0.975), 1.959_964, epsilon = 1e-6);
+        assert_abs_diff_eq!(normal_quantile(0.01), -2.326_348, epsilon = 1e-6);
+
+        for p in &[0.001, 0.1, 0.3, 0.5, 0.7, 0.9, 0.999] {
+            assert_abs_diff_eq!(normal_cdf(normal_quantile(*p)), *p, epsilon = 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_quantile() {
+        let values = [0.0, 1.0, 2.0, 3.0, 4.0];
+
+        assert_abs_diff_eq!(quantile(&values, 0.0), 0.0);
+        assert_abs_diff_eq!(quantile(&values, 0.5), 2.0);
+        assert_abs_diff_eq!(quantile(&values, 0.625), 2.5);
+        assert_abs_diff_eq!(quantile(&values, 1.0), 4.0);
+    }
+
+    #[test]
+    fn test_mean_absolute_error_interval() {
+        let mut rng = Isaac64Rng::seed_from_u64(42);
+
+        // uniform errors in [0, 1] have an expected mean absolute error of 0.5
+        let prediction = Array1::zeros(200);
+        let ground_truth = (0..200)
+            .map(|_| rng.sample(Uniform::new(0.0, 1.0)))
+            .collect::<Array1<f64>>();
+
+        let bootstrap = Bootstrap::params()
+            .num_resamples(500)
+            .evaluate(&prediction, &ground_truth, &mut rng, |a, b| {
+                a.mean_absolute_error(&b)
+            })
+            .unwrap();
+
+        let percentile = bootstrap.percentile_interval(0.95).unwrap();
+        let bca = bootstrap.bca_interval(0.95).unwrap();
+
+        assert!(percentile.contains(0.5));
+        assert!(bca.contains(0.5));
+        assert!(percentile.contains(bootstrap.estimate()));
+
+        // the standard error of the mean is 1/sqrt(12 * 200)
+        assert_abs_diff_eq!(bootstrap.standard_error(), 0.0204, epsilon = 5e-3);
+        // the interval should be roughly four standard errors wide
+        assert_abs_diff_eq!(
+            bca.upper - bca.lower,
+            4.0 * bootstrap.standard_error(),
+            epsilon = 0.02
+        );
+    }
+
+    #[test]
+    fn test_accuracy_without_jackknife() {
+        let mut rng = Isaac64Rng::seed_from_u64(42);
+
+        let ground_truth = (0..100).map(|x| x % 2 == 0).collect::<Vec<_>>();
+        // ten percent of the predictions are wrong
+        let prediction = (0..100)
+            .map(|x| if x % 10 == 0 { x % 2 != 0 } else { x % 2 == 0 })
+            .collect::<Vec<_>>();
+
+        let bootstrap = Bootstrap::params()
+            .jackknife(false)
+            .evaluate(&prediction, &ground_truth, &mut rng, |a, b| {
+                a.confusion_matrix(b).accuracy()
+            })
+            .unwrap();
+
+        assert_abs_diff_eq!(bootstrap.estimate(), 0.9);
+        assert!(bootstrap.percentile_interval(0.9).unwrap().contains(0.9));
+        assert!(bootstrap.bca_interval(0.9).is_err());
+        assert!(bootstrap.percentile_interval(1.0).is_err());
+    }
+
+    #[test]
+    fn test_paired_bootstrap() {
+        let mut rng = Isaac64Rng::seed_from_u64(42);
+
+        let ground_truth = (0..200)
+            .map(|_| rng.sample(Uniform::new(-1.0, 1.0)))
+            .collect::<Vec<f64>>();
+        let good = ground_truth
+            .iter()
+            .map(|x| x + rng.sample(Uniform::new(-0.1, 0.1)))
+            .collect::<Vec<_>>();
+        let bad = ground_truth
+            .iter()
+            .map(|x| x + rng.sample(Uniform::new(-0.5, 0.5)))
+            .collect::<Vec<_>>();
+
+        let metric = |a: ArrayView1<f64>, b: ArrayView1<f64>| a.mean_squared_error(&b);
+
+        // the first model has a significantly smaller error
+        let test = Bootstrap::params()
+            .compare(&good, &bad, &ground_truth, &mut rng, metric)
+            .unwrap();
+
+        assert!(test.difference() < 0.0);
+        assert!(test.p_value() < 0.01);
+        assert!(test.percentile_interval(0.95).unwrap().upper < 0.0);
+
+        // comparing a model with itself shows no difference
+        let test = Bootstrap::params()
+            .compare(&good, &good, &ground_truth, &mut rng, metric)
+            .unwrap();
+
+        assert_abs_diff_eq!(test.difference(), 0.0);
+        assert_abs_diff_eq!(test.p_value(), 1.0);
+    }
+
+    #[test]
+    fn test_non_finite_resamples() {
+        let mut rng = Isaac64Rng::seed_from_u64(42);
+
+        // only two positives, many resamples contain none of them
+        let ground_truth = (0..30).map(|x| x < 2).collect::<Vec<_>>();
+        let prediction = (0..30).map(|x| x < 1).collect::<Vec<_>>();
+
+        let recall = |a: ArrayView1<bool>, b: ArrayView1<bool>| {
+            let positives = b.iter().filter(|x| **x).count();
+            let found = a.iter().zip(b.iter()).filter(|(a, b)| **a && **b).count();
+
+            found as f64 / positives as f64
+        };
+
+        let bootstrap = Bootstrap::params()
+            .num_resamples(500)
+            .evaluate(&prediction, &ground_truth, &mut rng, recall)
+            .unwrap();
+
+        assert_abs_diff_eq!(bootstrap.estimate(), 0.5);
+        assert!(bootstrap.num_dropped() > 0);
+        assert_eq!(bootstrap.samples().len() + bootstrap.num_dropped(), 500);
+        assert!(bootstrap.samples().iter().all(|x| x.is_finite()));
+        assert!(bootstrap.bca_interval(0.9).is_ok());
+
+        // the metric is not finite on the original data
+        let no_positives = vec![false; 30];
+        let result = Bootstrap::params().evaluate(&prediction, &no_positives, &mut rng, recall);
+        assert!(result.is_err());
+
+        let result =
+            Bootstrap::params().compare(&prediction, &prediction, &no_positives, &mut rng, recall);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_mismatched_lengths() {
+        let mut rng = Isaac64Rng::seed_from_u64(42);
+
+        let result = Bootstrap::params().evaluate(
+            &vec![1.0, 2.0],
+            &vec![1.0, 2.0, 3.0],
+            &mut rng,
+            |a: ArrayView1<f64>, b: ArrayView1<f64>| a.mean_squared_error(&b),
+        );
+
+        assert!(result.is_err());
+    }
+}
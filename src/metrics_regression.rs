@@ -1,20 +1,19 @@
 //! Common metrics for regression
 //!
-//! This module implements common comparison metrices for continuous variables.
+//! This module implements common comparison metrices for continuous variables. The predicted
+//! values are always compared against the ground truth given as `compare_to`. If the ground
+//! truth is passed as a `Dataset`, then the sample weights of the dataset are respected.
 
 use ndarray::prelude::*;
 use ndarray::IntoNdProducer;
 use ndarray::{Data, NdFloat};
 use num_traits::FromPrimitive;
-use std::ops::Sub;
+
+use crate::dataset::{Dataset, Records, Targets};
+use crate::error::{Error, Result};
 
 /// Regression metrices trait
-pub trait Regression<
-    'a,
-    A: 'a,
-    T: IntoNdProducer<Item = &'a A, Dim = Ix1, Output = ArrayView1<'a, A>>,
->
-{
+pub trait Regression<'a, A: 'a, T> {
     /// Maximal error between two continuous variables
     fn max_error(&self, compare_to: T) -> A;
     /// Mean error between two continuous variables
@@ -25,13 +24,366 @@ pub trait Regression<
     fn mean_squared_log_error(&self, compare_to: T) -> A;
     /// Median absolute error between two continuous variables
     fn median_absolute_error(&self, compare_to: T) -> A;
+    /// Mean absolute percentage error between two continuous variables
+    ///
+    /// The relative error is calculated in respect to the ground truth and is not multiplied by
+    /// 100, e.g. a value of `0.1` corresponds to an error of ten percent. Values of the ground
+    /// truth close to zero are clamped to the machine epsilon.
+    fn mean_absolute_percentage_error(&self, compare_to: T) -> A;
     /// R squared coefficient, is the proprtion of the variance in the dependent variable that is
     /// predictable from the independent variable
+    ///
+    /// The residual sum of squares is divided by the total sum of squares of the ground truth
+    /// around its mean. Up to version 0.2.1 the squared distances of the prediction to the mean of
+    /// the ground truth were used as denominator instead.
     fn r2(&self, compare_to: T) -> A;
     /// Same as R-Squared but with biased variance
+    ///
+    /// The variance of the residuals around their mean is divided by the variance of the ground
+    /// truth. Up to version 0.2.1 the mean residual was subtracted from the residual sum of
+    /// squares and the same denominator as in `r2` was used.
     fn explained_variance(&self, compare_to: T) -> A;
+    /// Mean Tweedie deviance with a certain `power`
+    ///
+    /// The deviance follows the definition of `TweedieDistribution` in `linfa-linear`:
+    ///
+    /// | Power  | Distribution           |
+    /// | ------ | ---------------------- |
+    /// | 0      | Normal                 |
+    /// | 1      | Poisson                |
+    /// | (1, 2) | Compound Poisson Gamma |
+    /// | 2      | Gamma                  |
+    /// | 3      | Inverse Gaussian       |
+    ///
+    /// Returns an error if the power lies in (0, 1) or the values are outside of the valid range
+    /// of the distribution.
+    fn mean_tweedie_deviance(&self, compare_to: T, power: A) -> Result<A>;
+    /// Mean Poisson deviance, the Tweedie deviance with `power = 1`
+    fn mean_poisson_deviance(&self, compare_to: T) -> Result<A>;
+    /// Mean Gamma deviance, the Tweedie deviance with `power = 2`
+    fn mean_gamma_deviance(&self, compare_to: T) -> Result<A>;
+    /// Mean pinball loss for quantile predictions
+    ///
+    /// The loss penalizes under-predictions with `alpha` and over-predictions with `1 - alpha`,
+    /// where `alpha` is the quantile in (0, 1) targeted by the prediction. For `alpha = 0.5` this
+    /// is half the mean absolute error.
+    fn mean_pinball_loss(&self, compare_to: T, alpha: A) -> A;
+}
+
+/// Aggregation of scores for multi-output regression
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MultiOutput {
+    /// Return a score for each output
+    RawValues,
+    /// Average the scores of all outputs
+    UniformAverage,
+    /// Average the scores weighted by the variance of each output in the ground truth
+    VarianceWeighted,
+}
+
+/// Regression metrices for multiple outputs
+///
+/// Each column of the prediction is compared to the corresponding column of the ground truth.
+/// The scores are then aggregated according to `multi_output`. For
+/// `MultiOutput::RawValues` a score for each column is returned, otherwise the returned array
+/// contains a single aggregated value.
+pub trait MultiTargetRegression<'a, A: 'a, T> {
+    /// Maximal error between two continuous variables
+    fn max_error(&self, compare_to: T, multi_output: MultiOutput) -> Array1<A>;
+    /// Mean error between two continuous variables
+    fn mean_absolute_error(&self, compare_to: T, multi_output: MultiOutput) -> Array1<A>;
+    /// Mean squared error between two continuous variables
+    fn mean_squared_error(&self, compare_to: T, multi_output: MultiOutput) -> Array1<A>;
+    /// Mean squared log error between two continuous variables
+    fn mean_squared_log_error(&self, compare_to: T, multi_output: MultiOutput) -> Array1<A>;
+    /// Median absolute error between two continuous variables
+    fn median_absolute_error(&self, compare_to: T, multi_output: MultiOutput) -> Array1<A>;
+    /// Mean absolute percentage error between two continuous variables
+    fn mean_absolute_percentage_error(&self, compare_to: T, multi_output: MultiOutput)
+        -> Array1<A>;
+    /// R squared coefficient
+    fn r2(&self, compare_to: T, multi_output: MultiOutput) -> Array1<A>;
+    /// Same as R-Squared but with biased variance
+    fn explained_variance(&self, compare_to: T, multi_output: MultiOutput) -> Array1<A>;
+    /// Mean pinball loss for quantile predictions
+    fn mean_pinball_loss(&self, compare_to: T, alpha: A, multi_output: MultiOutput) -> Array1<A>;
+}
+
+/// Weighted sum of a sequence of values
+fn weighted_sum<A: NdFloat>(values: &Array1<A>, weights: &ArrayView1<A>) -> A {
+    values
+        .iter()
+        .zip(weights.iter())
+        .fold(A::zero(), |sum, (v, w)| sum + *v * *w)
+}
+
+/// Weighted mean of a sequence of values
+fn weighted_mean<A: NdFloat>(values: &Array1<A>, weights: &ArrayView1<A>) -> A {
+    weighted_sum(values, weights) / weights.sum()
+}
+
+/// Weighted median of a sequence of values
+///
+/// For equal weights this corresponds to the ordinary median and averages the two middle values
+/// for sequences of even length.
+fn weighted_median<A: NdFloat>(values: &Array1<A>, weights: &ArrayView1<A>) -> A {
+    let mut pairs = values
+        .iter()
+        .copied()
+        .zip(weights.iter().copied())
+        .collect::<Vec<_>>();
+    pairs.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+    let half = weights.sum() / A::from(2.0).unwrap();
+    let mut cumulative = A::zero();
+    for (i, (value, weight)) in pairs.iter().enumerate() {
+        cumulative += *weight;
+
+        if (cumulative - half).abs() <= A::epsilon() * half {
+            // exactly half of the weight is on each side, average with next non-zero entry
+            return match pairs[i + 1..].iter().find(|x| x.1 > A::zero()) {
+                Some((next, _)) => (*value + *next) / A::from(2.0).unwrap(),
+                None => *value,
+            };
+        } else if cumulative > half {
+            return *value;
+        }
+    }
+
+    pairs.last().map(|x| x.0).unwrap()
+}
+
+fn max_error<A: NdFloat>(pred: ArrayView1<A>, truth: ArrayView1<A>, weights: ArrayView1<A>) -> A {
+    pred.iter()
+        .zip(truth.iter())
+        .zip(weights.iter())
+        .filter(|(_, w)| **w > A::zero())
+        .map(|((a, b), _)| (*a - *b).abs())
+        .fold(A::neg_infinity(), A::max)
+}
+
+fn mean_absolute_error<A: NdFloat>(
+    pred: ArrayView1<A>,
+    truth: ArrayView1<A>,
+    weights: ArrayView1<A>,
+) -> A {
+    weighted_mean(&(&pred - &truth).mapv(|x| x.abs()), &weights)
+}
+
+fn mean_squared_error<A: NdFloat>(
+    pred: ArrayView1<A>,
+    truth: ArrayView1<A>,
+    weights: ArrayView1<A>,
+) -> A {
+    weighted_mean(&(&pred - &truth).mapv(|x| x * x), &weights)
+}
+
+fn mean_squared_log_error<A: NdFloat>(
+    pred: ArrayView1<A>,
+    truth: ArrayView1<A>,
+    weights: ArrayView1<A>,
+) -> A {
+    let pred = pred.mapv(|x| (A::one() + x).ln());
+    let truth = truth.mapv(|x| (A::one() + x).ln());
+
+    mean_squared_error(pred.view(), truth.view(), weights)
+}
+
+fn median_absolute_error<A: NdFloat>(
+    pred: ArrayView1<A>,
+    truth: ArrayView1<A>,
+    weights: ArrayView1<A>,
+) -> A {
+    weighted_median(&(&pred - &truth).mapv(|x| x.abs()), &weights)
+}
+
+fn mean_absolute_percentage_error<A: NdFloat>(
+    pred: ArrayView1<A>,
+    truth: ArrayView1<A>,
+    weights: ArrayView1<A>,
+) -> A {
+    let relative_error = pred
+        .iter()
+        .zip(truth.iter())
+        .map(|(a, b)| (*a - *b).abs() / A::max(b.abs(), A::epsilon()))
+        .collect::<Array1<_>>();
+
+    weighted_mean(&relative_error, &weights)
+}
+
+fn r2<A: NdFloat>(pred: ArrayView1<A>, truth: ArrayView1<A>, weights: ArrayView1<A>) -> A {
+    let mean = weighted_mean(&truth.to_owned(), &weights);
+
+    A::one()
+        - weighted_sum(&(&pred - &truth).mapv(|x| x * x), &weights)
+            / (weighted_sum(&truth.mapv(|x| (x - mean) * (x - mean)), &weights)
+                + A::from(1e-10).unwrap())
+}
+
+fn explained_variance<A: NdFloat>(
+    pred: ArrayView1<A>,
+    truth: ArrayView1<A>,
+    weights: ArrayView1<A>,
+) -> A {
+    let diff = &truth - &pred;
+
+    let mean = weighted_mean(&truth.to_owned(), &weights);
+    let mean_error = weighted_mean(&diff, &weights);
+
+    A::one()
+        - weighted_sum(
+            &diff.mapv(|x| (x - mean_error) * (x - mean_error)),
+            &weights,
+        ) / (weighted_sum(&truth.mapv(|x| (x - mean) * (x - mean)), &weights)
+            + A::from(1e-10).unwrap())
+}
+
+/// Unit deviance of a single prediction `mu` for ground truth `y`
+///
+/// The validity of `power` and the values is checked beforehand in `mean_tweedie_deviance`.
+fn unit_deviance<A: NdFloat>(y: A, mu: A, power: A) -> A {
+    let two = A::from(2.0).unwrap();
+
+    if power == A::zero() {
+        // Normal distribution
+        (y - mu) * (y - mu)
+    } else if power == A::one() {
+        // Poisson distribution
+        let ylogy = if y == A::zero() {
+            A::zero()
+        } else {
+            y * (y / mu).ln()
+        };
+
+        two * (ylogy - y + mu)
+    } else if power == two {
+        // Gamma distribution
+        two * ((mu / y).ln() + y / mu - A::one())
+    } else {
+        let left = A::max(y, A::zero()).powf(two - power) / ((A::one() - power) * (two - power));
+        let middle = y * mu.powf(A::one() - power) / (A::one() - power);
+        let right = mu.powf(two - power) / (two - power);
+
+        two * (left - middle + right)
+    }
+}
+
+fn mean_tweedie_deviance<A: NdFloat>(
+    pred: ArrayView1<A>,
+    truth: ArrayView1<A>,
+    weights: ArrayView1<A>,
+    power: A,
+) -> Result<A> {
+    let pred_positive = || pred.iter().all(|x| *x > A::zero());
+
+    // check that the power and values are in the domain of the distribution
+    let valid = if power < A::zero() {
+        pred_positive()
+    } else if power == A::zero() {
+        true
+    } else if power < A::one() {
+        return Err(Error::Parameters(format!(
+            "Power value cannot be between 0 and 1, got: {}",
+            power
+        )));
+    } else if power < A::from(2.0).unwrap() {
+        pred_positive() && truth.iter().all(|x| *x >= A::zero())
+    } else {
+        pred_positive() && truth.iter().all(|x| *x > A::zero())
+    };
+
+    if !valid {
+        return Err(Error::Parameters(format!(
+            "Values are outside of the domain of the Tweedie deviance with power {}",
+            power
+        )));
+    }
+
+    let deviance = pred
+        .iter()
+        .zip(truth.iter())
+        .map(|(mu, y)| unit_deviance(*y, *mu, power))
+        .collect::<Array1<_>>();
+
+    Ok(weighted_mean(&deviance, &weights))
+}
+
+fn mean_pinball_loss<A: NdFloat>(
+    pred: ArrayView1<A>,
+    truth: ArrayView1<A>,
+    weights: ArrayView1<A>,
+    alpha: A,
+) -> A {
+    let loss = pred
+        .iter()
+        .zip(truth.iter())
+        .map(|(a, b)| {
+            let diff = *b - *a;
+            if diff >= A::zero() {
+                alpha * diff
+            } else {
+                (alpha - A::one()) * diff
+            }
+        })
+        .collect::<Array1<_>>();
+
+    weighted_mean(&loss, &weights)
+}
+
+/// Implement the regression metrices for a prediction, ground truth and weights
+macro_rules! impl_regression {
+    ($this:ident, $compare_to:ident: $t:ty => $pred:expr, $truth:expr, $weights:expr) => {
+        fn max_error(&$this, $compare_to: $t) -> A {
+            max_error($pred, $truth, $weights)
+        }
+
+        fn mean_absolute_error(&$this, $compare_to: $t) -> A {
+            mean_absolute_error($pred, $truth, $weights)
+        }
+
+        fn mean_squared_error(&$this, $compare_to: $t) -> A {
+            mean_squared_error($pred, $truth, $weights)
+        }
+
+        fn mean_squared_log_error(&$this, $compare_to: $t) -> A {
+            mean_squared_log_error($pred, $truth, $weights)
+        }
+
+        fn median_absolute_error(&$this, $compare_to: $t) -> A {
+            median_absolute_error($pred, $truth, $weights)
+        }
+
+        fn mean_absolute_percentage_error(&$this, $compare_to: $t) -> A {
+            mean_absolute_percentage_error($pred, $truth, $weights)
+        }
+
+        fn r2(&$this, $compare_to: $t) -> A {
+            r2($pred, $truth, $weights)
+        }
+
+        fn explained_variance(&$this, $compare_to: $t) -> A {
+            explained_variance($pred, $truth, $weights)
+        }
+
+        fn mean_tweedie_deviance(&$this, $compare_to: $t, power: A) -> Result<A> {
+            mean_tweedie_deviance($pred, $truth, $weights, power)
+        }
+
+        fn mean_poisson_deviance(&$this, $compare_to: $t) -> Result<A> {
+            $this.mean_tweedie_deviance($compare_to, A::one())
+        }
+
+        fn mean_gamma_deviance(&$this, $compare_to: $t) -> Result<A> {
+            $this.mean_tweedie_deviance($compare_to, A::from(2.0).unwrap())
+        }
+
+        fn mean_pinball_loss(&$this, $compare_to: $t, alpha: A) -> A {
+            mean_pinball_loss($pred, $truth, $weights, alpha)
+        }
+    };
 }
 
+/// Compare a prediction to an unweighted ground truth
 impl<
         'a,
         A: 'a + NdFloat + FromPrimitive,
@@ -39,74 +391,186 @@ impl<
         T: IntoNdProducer<Item = &'a A, Dim = Ix1, Output = ArrayView1<'a, A>>,
     > Regression<'a, A, T> for ArrayBase<D, Ix1>
 {
-    fn max_error(&self, compare_to: T) -> A {
-        let compare_to: ArrayView1<'a, A> = compare_to.into_producer();
+    impl_regression!(self, compare_to: T =>
+        self.view(),
+        compare_to.into_producer(),
+        Array1::ones(self.len()).view()
+    );
+}
 
-        self.sub(&compare_to)
-            .iter()
-            .map(|x| x.abs())
-            .fold(A::neg_infinity(), A::max)
-    }
+/// Convert the weights of a dataset to the element type of the metric
+fn dataset_weights<A: NdFloat + FromPrimitive, R: Records, T: Targets>(
+    dataset: &Dataset<R, T>,
+) -> Array1<A> {
+    (0..dataset.targets().as_slice().len())
+        .map(|i| A::from_f32(dataset.weight_for(i)).unwrap())
+        .collect()
+}
 
-    fn mean_absolute_error(&self, compare_to: T) -> A {
-        let compare_to = compare_to.into_producer();
+/// Compare a prediction to a ground truth dataset, weighted by the dataset's weights
+impl<'a, A: 'a + NdFloat + FromPrimitive, D: Data<Elem = A>, R: Records, T: Targets<Elem = A>>
+    Regression<'a, A, &'a Dataset<R, T>> for ArrayBase<D, Ix1>
+{
+    impl_regression!(self, compare_to: &'a Dataset<R, T> =>
+        self.view(),
+        ArrayView1::from(compare_to.targets().as_slice()),
+        dataset_weights(compare_to).view()
+    );
+}
 
-        self.sub(&compare_to).mapv(|x| x.abs()).mean().unwrap()
-    }
+/// Compare a predicted dataset to a ground truth dataset, weighted by the ground truth's weights
+impl<
+        'a,
+        A: 'a + NdFloat + FromPrimitive,
+        R: Records,
+        R2: Records,
+        T: Targets<Elem = A>,
+        T2: Targets<Elem = A>,
+    > Regression<'a, A, &'a Dataset<R, T>> for Dataset<R2, T2>
+{
+    impl_regression!(self, compare_to: &'a Dataset<R, T> =>
+        ArrayView1::from(self.targets().as_slice()),
+        ArrayView1::from(compare_to.targets().as_slice()),
+        dataset_weights(compare_to).view()
+    );
+}
 
-    fn mean_squared_error(&self, compare_to: T) -> A {
-        let compare_to = compare_to.into_producer();
+/// Aggregate the scores of multiple outputs
+fn aggregate<A: NdFloat + FromPrimitive>(
+    scores: Array1<A>,
+    truth: &ArrayView2<A>,
+    multi_output: MultiOutput,
+) -> Array1<A> {
+    match multi_output {
+        MultiOutput::RawValues => scores,
+        MultiOutput::UniformAverage => array![scores.mean().unwrap()],
+        MultiOutput::VarianceWeighted => {
+            let variance = truth.var_axis(Axis(0), A::zero());
 
-        self.sub(&compare_to).mapv(|x| x * x).mean().unwrap()
+            // fall back to uniform average if all outputs are constant
+            if variance.sum() > A::zero() {
+                array![weighted_mean(&scores, &variance.view())]
+            } else {
+                array![scores.mean().unwrap()]
+            }
+        }
     }
+}
 
-    fn mean_squared_log_error(&self, compare_to: T) -> A {
-        let compare_to = compare_to.into_producer();
+/// Evaluate a single-output metric for each pair of columns and aggregate the scores
+fn per_output<
+    A: NdFloat + FromPrimitive,
+    F: Fn(ArrayView1<A>, ArrayView1<A>, ArrayView1<A>) -> A,
+>(
+    pred: ArrayView2<A>,
+    truth: ArrayView2<A>,
+    multi_output: MultiOutput,
+    metric: F,
+) -> Array1<A> {
+    let weights = Array1::ones(pred.nrows());
+    let scores = pred
+        .gencolumns()
+        .into_iter()
+        .zip(truth.gencolumns())
+        .map(|(a, b)| metric(a, b, weights.view()))
+        .collect::<Array1<_>>();
 
-        self.mapv(|x| (A::one() + x).ln())
-            .mean_squared_error(&compare_to.mapv(|x| (A::one() + x).ln()))
+    aggregate(scores, &truth, multi_output)
+}
+
+impl<
+        'a,
+        A: 'a + NdFloat + FromPrimitive,
+        D: Data<Elem = A>,
+        T: IntoNdProducer<Item = &'a A, Dim = Ix2, Output = ArrayView2<'a, A>>,
+    > MultiTargetRegression<'a, A, T> for ArrayBase<D, Ix2>
+{
+    fn max_error(&self, compare_to: T, multi_output: MultiOutput) -> Array1<A> {
+        per_output(
+            self.view(),
+            compare_to.into_producer(),
+            multi_output,
+            max_error,
+        )
     }
 
-    fn median_absolute_error(&self, compare_to: T) -> A {
-        let compare_to = compare_to.into_producer();
+    fn mean_absolute_error(&self, compare_to: T, multi_output: MultiOutput) -> Array1<A> {
+        per_output(
+            self.view(),
+            compare_to.into_producer(),
+            multi_output,
+            mean_absolute_error,
+        )
+    }
 
-        let mut abs_error = self.sub(&compare_to).mapv(|x| x.abs()).to_vec();
-        abs_error.sort_by(|a, b| a.partial_cmp(&b).unwrap());
-        let mid = abs_error.len() / 2;
-        if abs_error.len() % 2 == 0 {
-            (abs_error[mid - 1] + abs_error[mid]) / A::from(2.0).unwrap()
-        } else {
-            abs_error[mid]
-        }
+    fn mean_squared_error(&self, compare_to: T, multi_output: MultiOutput) -> Array1<A> {
+        per_output(
+            self.view(),
+            compare_to.into_producer(),
+            multi_output,
+            mean_squared_error,
+        )
     }
 
-    fn r2(&self, compare_to: T) -> A {
-        let compare_to = compare_to.into_producer();
+    fn mean_squared_log_error(&self, compare_to: T, multi_output: MultiOutput) -> Array1<A> {
+        per_output(
+            self.view(),
+            compare_to.into_producer(),
+            multi_output,
+            mean_squared_log_error,
+        )
+    }
 
-        let mean = compare_to.mean().unwrap();
+    fn median_absolute_error(&self, compare_to: T, multi_output: MultiOutput) -> Array1<A> {
+        per_output(
+            self.view(),
+            compare_to.into_producer(),
+            multi_output,
+            median_absolute_error,
+        )
+    }
 
-        A::one()
-            - self.sub(&compare_to).mapv(|x| x * x).sum()
-                / (self.mapv(|x| (x - mean) * (x - mean)).sum() + A::from(1e-10).unwrap())
+    fn mean_absolute_percentage_error(
+        &self,
+        compare_to: T,
+        multi_output: MultiOutput,
+    ) -> Array1<A> {
+        per_output(
+            self.view(),
+            compare_to.into_producer(),
+            multi_output,
+            mean_absolute_percentage_error,
+        )
     }
 
-    fn explained_variance(&self, compare_to: T) -> A {
-        let compare_to = compare_to.into_producer();
-        let diff = self.sub(&compare_to);
+    fn r2(&self, compare_to: T, multi_output: MultiOutput) -> Array1<A> {
+        per_output(self.view(), compare_to.into_producer(), multi_output, r2)
+    }
 
-        let mean = compare_to.mean().unwrap();
-        let mean_error = diff.mean().unwrap();
+    fn explained_variance(&self, compare_to: T, multi_output: MultiOutput) -> Array1<A> {
+        per_output(
+            self.view(),
+            compare_to.into_producer(),
+            multi_output,
+            explained_variance,
+        )
+    }
 
-        A::one()
-            - (diff.mapv(|x| x * x).sum() - mean_error)
-                / (self.mapv(|x| (x - mean) * (x - mean)).sum() + A::from(1e-10).unwrap())
+    fn mean_pinball_loss(&self, compare_to: T, alpha: A, multi_output: MultiOutput) -> Array1<A> {
+        per_output(
+            self.view(),
+            compare_to.into_producer(),
+            multi_output,
+            |a, b, w| mean_pinball_loss(a, b, w, alpha),
+        )
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::Regression;
-    use approx::abs_diff_eq;
+    use super::{MultiOutput, MultiTargetRegression, Regression};
+    use crate::dataset::Dataset;
+    use approx::{abs_diff_eq, assert_abs_diff_eq};
     use ndarray::prelude::*;
 
     #[test]
@@ -146,4 +610,138 @@ mod tests {
 
         abs_diff_eq!(a.mean_squared_error(&b), 0.1, epsilon = 1e-5);
     }
+
+    #[test]
+    fn test_r2_explained_variance() {
+        let pred = array![2.5, 0.0, 2.0, 8.0];
+        let truth = array![3.0, -0.5, 2.0, 7.0];
+
+        // reference values from scikit-learn
+        assert_abs_diff_eq!(pred.r2(&truth), 0.948608, epsilon = 1e-5);
+        assert_abs_diff_eq!(pred.explained_variance(&truth), 0.957173, epsilon = 1e-5);
+    }
+
+    #[test]
+    fn test_mean_absolute_percentage_error() {
+        let pred = array![2.5, 0.0, 2.0, 8.0];
+        let truth = array![3.0, -0.5, 2.0, 7.0];
+
+        assert_abs_diff_eq!(
+            pred.mean_absolute_percentage_error(&truth),
+            0.327380,
+            epsilon = 1e-5
+        );
+    }
+
+    #[test]
+    fn test_tweedie_deviance() {
+        let pred = array![2.0, 0.5, 1.0, 4.0];
+        let truth = array![0.5, 0.5, 2.0, 2.0];
+
+        // reference values calculated with the closed form of the unit deviance
+        assert_abs_diff_eq!(
+            pred.mean_tweedie_deviance(&truth, 0.0).unwrap(),
+            pred.mean_squared_error(&truth),
+            epsilon = 1e-10
+        );
+        assert_abs_diff_eq!(
+            pred.mean_poisson_deviance(&truth).unwrap(),
+            0.903426,
+            epsilon = 1e-5
+        );
+        assert_abs_diff_eq!(
+            pred.mean_gamma_deviance(&truth).unwrap(),
+            0.568147,
+            epsilon = 1e-5
+        );
+        assert_abs_diff_eq!(
+            pred.mean_tweedie_deviance(&truth, 3.0).unwrap(),
+            0.4375,
+            epsilon = 1e-5
+        );
+
+        // power in (0, 1) and negative predictions are not allowed
+        assert!(pred.mean_tweedie_deviance(&truth, 0.5).is_err());
+        assert!((-&pred).mean_poisson_deviance(&truth).is_err());
+    }
+
+    #[test]
+    fn test_pinball_loss() {
+        let pred = array![0.0, 1.0, 2.0, 3.0];
+        let truth = array![1.0, 1.0, 1.0, 1.0];
+
+        // one under-prediction of 1 and two over-predictions of 1 and 2
+        assert_abs_diff_eq!(pred.mean_pinball_loss(&truth, 0.1), 0.7, epsilon = 1e-10);
+        assert_abs_diff_eq!(
+            pred.mean_pinball_loss(&truth, 0.5),
+            pred.mean_absolute_error(&truth) / 2.0,
+            epsilon = 1e-10
+        );
+    }
+
+    #[test]
+    fn test_weighted() {
+        let pred = array![0.0, 1.0, 2.0, 3.0];
+        let truth = Dataset::new((), array![1.0, 1.0, 1.0, 1.0]);
+
+        // unit weights are equal to the unweighted metrices
+        assert_abs_diff_eq!(
+            pred.mean_absolute_error(&truth),
+            pred.mean_absolute_error(truth.targets()),
+            epsilon = 1e-10
+        );
+        assert_abs_diff_eq!(
+            pred.median_absolute_error(&truth),
+            pred.median_absolute_error(truth.targets()),
+            epsilon = 1e-10
+        );
+
+        // a weight of two is equal to duplicating a sample
+        let truth = truth.with_weights(vec![2.0, 1.0, 1.0, 0.0]);
+        let pred_dup = array![0.0, 0.0, 1.0, 2.0];
+        let truth_dup = array![1.0, 1.0, 1.0, 1.0];
+
+        assert_abs_diff_eq!(
+            pred.mean_squared_error(&truth),
+            pred_dup.mean_squared_error(&truth_dup),
+            epsilon = 1e-10
+        );
+        assert_abs_diff_eq!(
+            pred.median_absolute_error(&truth),
+            pred_dup.median_absolute_error(&truth_dup),
+            epsilon = 1e-10
+        );
+        assert_abs_diff_eq!(
+            pred.mean_pinball_loss(&truth, 0.9),
+            pred_dup.mean_pinball_loss(&truth_dup, 0.9),
+            epsilon = 1e-10
+        );
+
+        // the last sample has zero weight and is ignored in the maximal error
+        assert_abs_diff_eq!(pred.max_error(&truth), 1.0, epsilon = 1e-10);
+    }
+
+    #[test]
+    fn test_multi_output() {
+        let pred = array![[0.5, 1.0], [-1.0, 1.0], [7.0, -6.0]];
+        let truth = array![[0.0, 2.0], [-1.0, 2.0], [8.0, -5.0]];
+
+        // reference values calculated column-wise
+        let raw = pred.r2(&truth, MultiOutput::RawValues);
+        assert_eq!(raw.len(), 2);
+        assert_abs_diff_eq!(raw[0], 0.974315, epsilon = 1e-5);
+        assert_abs_diff_eq!(raw[1], 0.908163, epsilon = 1e-5);
+
+        let uniform = pred.r2(&truth, MultiOutput::UniformAverage);
+        assert_eq!(uniform.len(), 1);
+        assert_abs_diff_eq!(uniform[0], 0.941239, epsilon = 1e-5);
+
+        let weighted = pred.r2(&truth, MultiOutput::VarianceWeighted);
+        assert_eq!(weighted.len(), 1);
+        assert_abs_diff_eq!(weighted[0], 0.947746, epsilon = 1e-5);
+
+        let mae = pred.mean_absolute_error(&truth, MultiOutput::RawValues);
+        assert_abs_diff_eq!(mae[0], 0.5, epsilon = 1e-10);
+        assert_abs_diff_eq!(mae[1], 1.0, epsilon = 1e-10);
+    }
 }
@@ -0,0 +1,470 @@
+//! Learning and validation curves
+//!
+//! Both curves evaluate a model with k-fold cross-validation over a dataset. A learning curve
+//! varies the size of the training set and shows whether more data would improve the model. A
+//! validation curve varies a single hyperparameter and shows where the model starts to under- or
+//! overfit. Both results can be written as CSV for plotting.
+//!
+//! The curves work with every model whose hyperparameters implement `Fit` for a
+//! `Dataset<Array2<F>, T>` and whose fitted model implements `Predict<Array2<F>, P>`, the same
+//! bound as the helpers in the `inspection` module. This covers the decision trees, forests and
+//! gradient boosted trees and models which borrow their training set. The linear SVM returns a
+//! `Result` from `fit` and kernel SVMs are fitted on a `Kernel` instead of a record matrix, so
+//! neither can be evaluated here.
+use ndarray::{Array1, Array2, Axis};
+use std::fmt::Display;
+use std::io::{self, Write};
+use std::iter::FromIterator;
+
+use crate::dataset::{Dataset, Float, Records, Targets};
+use crate::error::{Error, Result};
+use crate::traits::{Fit, Predict};
+
+/// Scores of a learning curve
+///
+/// The score matrices have one row per training set size and one column per fold.
+#[derive(Debug, Clone)]
+pub struct LearningCurve<A> {
+    pub train_sizes: Vec<usize>,
+    pub train_scores: Array2<A>,
+    pub validation_scores: Array2<A>,
+}
+
+/// Scores of a validation curve
+///
+/// The score matrices have one row per hyperparameter value and one column per fold.
+#[derive(Debug, Clone)]
+pub struct ValidationCurve<V, A> {
+    pub values: Vec<V>,
+    pub train_scores: Array2<A>,
+    pub validation_scores: Array2<A>,
+}
+
+impl<A: Float> LearningCurve<A> {
+    /// Mean training score for each training set size
+    pub fn mean_train_scores(&self) -> Array1<A> {
+        self.train_scores.mean_axis(Axis(1)).unwrap()
+    }
+
+    /// Mean validation score for each training set size
+    pub fn mean_validation_scores(&self) -> Array1<A> {
+        self.validation_scores.mean_axis(Axis(1)).unwrap()
+    }
+
+    /// Write the mean and standard deviation of the scores as CSV
+    ///
+    /// The columns are `train_size,train_mean,train_std,validation_mean,validation_std`.
+    pub fn write_csv<W: Write>(&self, writer: W) -> io::Result<()> {
+        write_csv(
+            writer,
+            "train_size",
+            &self.train_sizes,
+            &self.train_scores,
+            &self.validation_scores,
+        )
+    }
+}
+
+impl<V: Display, A: Float> ValidationCurve<V, A> {
+    /// Mean training score for each hyperparameter value
+    pub fn mean_train_scores(&self) -> Array1<A> {
+        self.train_scores.mean_axis(Axis(1)).unwrap()
+    }
+
+    /// Mean validation score for each hyperparameter value
+    pub fn mean_validation_scores(&self) -> Array1<A> {
+        self.validation_scores.mean_axis(Axis(1)).unwrap()
+    }
+
+    /// Write the mean and standard deviation of the scores as CSV
+    ///
+    /// The columns are `value,train_mean,train_std,validation_mean,validation_std`.
+    pub fn write_csv<W: Write>(&self, writer: W) -> io::Result<()> {
+        write_csv(
+            writer,
+            "value",
+            &self.values,
+            &self.train_scores,
+            &self.validation_scores,
+        )
+    }
+}
+
+/// Evaluate a model for increasing sizes of the training set
+///
+/// The dataset is split into `k` folds. For each fold and each fraction in `train_fractions` the
+/// model is fitted on the corresponding leading part of the training set and scored on the
+/// training part used and on the held-out fold. The `score` closure compares the prediction with
+/// the ground truth, for example the accuracy or the r2 score.
+///
+/// The dataset should be shuffled beforehand, because the folds are consecutive chunks.
+///
+/// # Example
+///
+/// ```ignore
+/// let curve = learning_curve(
+///     &DecisionTree::params().max_depth(Some(4)),
+///     &dataset,
+///     &[0.1, 0.25, 0.5, 1.0],
+///     5,
+///     |pred: Vec<usize>, truth: &Vec<usize>| pred.as_slice().confusion_matrix(truth.as_slice()).accuracy(),
+/// )?;
+///
+/// curve.write_csv(File::create("learning_curve.csv")?)?;
+/// ```
+pub fn learning_curve<F, E, T, P, A, M, S>(
+    params: &M,
+    dataset: &Dataset<Array2<F>, T>,
+    train_fractions: &[f32],
+    k: usize,
+    mut score: S,
+) -> Result<LearningCurve<A>>
+where
+    F: Float,
+    E: Clone,
+    T: Targets<Elem = E> + FromIterator<E>,
+    A: Float,
+    M: for<'b> Fit<'b, Array2<F>, T>,
+    for<'b> <M as Fit<'b, Array2<F>, T>>::Object: Predict<Array2<F>, P>,
+    S: FnMut(P, &T) -> A,
+{
+    check_folds(dataset.observations(), k)?;
+
+    if train_fractions.is_empty() {
+        return Err(Error::Parameters(
+            "At least one training fraction is required".to_string(),
+        ));
+    }
+
+    if let Some(x) = train_fractions.iter().find(|x| **x <= 0.0 || **x > 1.0) {
+        return Err(Error::Parameters(format!(
+            "Training fractions should be in (0, 1], but got {}",
+            x
+        )));
+    }
+
+    let folds = dataset.fold(k);
+
+    // the smallest training fold determines the sizes, so that all folds are comparable
+    let n_train = folds
+        .iter()
+        .map(|(train, _)| train.observations())
+        .min()
+        .unwrap();
+    let train_sizes = train_fractions
+        .iter()
+        .map(|x| ((n_train as f32 * x).ceil() as usize).max(1))
+        .collect::<Vec<_>>();
+
+    let mut train_scores = Array2::zeros((train_sizes.len(), k));
+    let mut validation_scores = Array2::zeros((train_sizes.len(), k));
+
+    for (j, (train, valid)) in folds.iter().enumerate() {
+        for (i, size) in train_sizes.iter().enumerate() {
+            let indices = (0..*size).collect::<Vec<_>>();
+            let subset = train.select(&indices);
+
+            let (train_score, validation_score) = evaluate(params, &subset, valid, &mut score);
+            train_scores[(i, j)] = train_score;
+            validation_scores[(i, j)] = validation_score;
+        }
+    }
+
+    Ok(LearningCurve {
+        train_sizes,
+        train_scores,
+        validation_scores,
+    })
+}
+
+/// Evaluate a model for different values of a single hyperparameter
+///
+/// For each value in `values` the closure `params` creates the hyperparameters of the model,
+/// which is then fitted on each of the `k` folds and scored on the training set and on the
+/// held-out fold.
+///
+/// The dataset should be shuffled beforehand, because the folds are consecutive chunks.
+///
+/// # Example
+///
+/// ```ignore
+/// let curve = validation_curve(
+///     &dataset,
+///     &[1, 2, 4, 8, 16],
+///     5,
+///     |depth| DecisionTree::params().max_depth(Some(*depth)),
+///     |pred: Vec<usize>, truth: &Vec<usize>| pred.as_slice().confusion_matrix(truth.as_slice()).accuracy(),
+/// )?;
+/// ```
+pub fn validation_curve<F, E, T, P, A, V, M, G, S>(
+    dataset: &Dataset<Array2<F>, T>,
+    values: &[V],
+    k: usize,
+    mut params: G,
+    mut score: S,
+) -> Result<ValidationCurve<V, A>>
+where
+    F: Float,
+    E: Clone,
+    T: Targets<Elem = E> + FromIterator<E>,
+    A: Float,
+    V: Clone,
+    M: for<'b> Fit<'b, Array2<F>, T>,
+    for<'b> <M as Fit<'b, Array2<F>, T>>::Object: Predict<Array2<F>, P>,
+    G: FnMut(&V) -> M,
+    S: FnMut(P, &T) -> A,
+{
+    check_folds(dataset.observations(), k)?;
+
+    if values.is_empty() {
+        return Err(Error::Parameters(
+            "At least one hyperparameter value is required".to_string(),
+        ));
+    }
+
+    let folds = dataset.fold(k);
+
+    let mut train_scores = Array2::zeros((values.len(), k));
+    let mut validation_scores = Array2::zeros((values.len(), k));
+
+    for (i, value) in values.iter().enumerate() {
+        let params = params(value);
+
+        for (j, (train, valid)) in folds.iter().enumerate() {
+            let (train_score, validation_score) = evaluate(&params, train, valid, &mut score);
+            train_scores[(i, j)] = train_score;
+            validation_scores[(i, j)] = validation_score;
+        }
+    }
+
+    Ok(ValidationCurve {
+        values: values.to_vec(),
+        train_scores,
+        validation_scores,
+    })
+}
+
+/// Fit the model on the training set and score it on both sets
+fn evaluate<F, T, P, A, M, S>(
+    params: &M,
+    train: &Dataset<Array2<F>, T>,
+    valid: &Dataset<Array2<F>, T>,
+    score: &mut S,
+) -> (A, A)
+where
+    F: Float,
+    T: Targets,
+    M: for<'b> Fit<'b, Array2<F>, T>,
+    for<'b> <M as Fit<'b, Array2<F>, T>>::Object: Predict<Array2<F>, P>,
+    S: FnMut(P, &T) -> A,
+{
+    let model = params.fit(train);
+
+    let train_score = score(model.predict(train.records().clone()), train.targets());
+    let validation_score = score(model.predict(valid.records().clone()), valid.targets());
+
+    (train_score, validation_score)
+}
+
+fn check_folds(n_observations: usize, k: usize) -> Result<()> {
+    if k < 2 || k > n_observations {
+        return Err(Error::Parameters(format!(
+            "Number of folds should be in [2, {}], but got {}",
+            n_observations, k
+        )));
+    }
+
+    Ok(())
+}
+
+fn write_csv<W: Write, V: Display, A: Float>(
+    mut writer: W,
+    name: &str,
+    values: &[V],
+    train_scores: &Array2<A>,
+    validation_scores: &Array2<A>,
+) -> io::Result<()> {
+    writeln!(
+        writer,
+        "{},train_mean,train_std,validation_mean,validation_std",
+        name
+    )?;
+
+    for (i, value) in values.iter().enumerate() {
+        let (train, valid) = (train_scores.row(i), validation_scores.row(i));
+
+        writeln!(
+            writer,
+            "{},{},{},{},{}",
+            value,
+            train.mean().unwrap(),
+            train.std_axis(Axis(0), A::zero())[()],
+            valid.mean().unwrap(),
+            valid.std_axis(Axis(0), A::zero())[()]
+        )?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{learning_curve, validation_curve};
+    use crate::dataset::Dataset;
+    use crate::metrics::Regression;
+    use crate::traits::{Fit, Predict};
+    use approx::assert_abs_diff_eq;
+    use ndarray::{array, Array1, Array2, Axis};
+
+    /// Predicts the mean target of the `k` nearest training observations
+    struct NearestNeighbours {
+        k: usize,
+    }
+
+    /// Borrows the training set, like a model fitted on a kernel matrix
+    struct FittedNearestNeighbours<'a> {
+        k: usize,
+        records: &'a Array2<f64>,
+        targets: &'a Array1<f64>,
+    }
+
+    impl<'a> Fit<'a, Array2<f64>, Array1<f64>> for NearestNeighbours {
+        type Object = FittedNearestNeighbours<'a>;
+
+        fn fit(&self, dataset: &'a Dataset<Array2<f64>, Array1<f64>>) -> Self::Object {
+            FittedNearestNeighbours {
+                k: self.k.min(dataset.targets().len()),
+                records: dataset.records(),
+                targets: dataset.targets(),
+            }
+        }
+    }
+
+    impl<'a> Predict<Array2<f64>, Array1<f64>> for FittedNearestNeighbours<'a> {
+        fn predict(&self, x: Array2<f64>) -> Array1<f64> {
+            x.outer_iter()
+                .map(|row| {
+                    let mut dists = self
+                        .records
+                        .outer_iter()
+                        .map(|other| (&other - &row).mapv(|x| x * x).sum())
+                        .zip(self.targets.iter())
+                        .collect::<Vec<_>>();
+                    dists.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+                    dists.iter().take(self.k).map(|x| x.1).sum::<f64>() / self.k as f64
+                })
+                .collect()
+        }
+    }
+
+    fn dataset() -> Dataset<Array2<f64>, Array1<f64>> {
+        // a noisy line, the noise alternates in sign
+        let records = Array1::linspace(0.0, 1.0, 20).insert_axis(Axis(1));
+        let targets = records
+            .column(0)
+            .indexed_iter()
+            .map(|(i, x)| x + if i % 2 == 0 { 0.1 } else { -0.1 })
+            .collect::<Array1<_>>();
+
+        Dataset::new(records, targets)
+    }
+
+    #[test]
+    fn test_fold() {
+        let dataset = Dataset::new(array![[1.], [2.], [3.], [4.], [5.]], vec![1, 2, 3, 4, 5])
+            .with_weights(vec![1.0, 2.0, 3.0, 4.0, 5.0]);
+
+        let folds = dataset.fold(2);
+        assert_eq!(folds.len(), 2);
+
+        let (train, valid) = &folds[0];
+        assert_eq!(train.targets(), &[4, 5]);
+        assert_eq!(valid.targets(), &[1, 2, 3]);
+        assert_eq!(valid.weights(), Some(&[1.0, 2.0, 3.0][..]));
+        assert_eq!(train.records(), &array![[4.], [5.]]);
+
+        let (train, valid) = &folds[1];
+        assert_eq!(train.targets(), &[1, 2, 3]);
+        assert_eq!(valid.targets(), &[4, 5]);
+    }
+
+    #[test]
+    fn test_learning_curve() {
+        let dataset = dataset();
+
+        let curve = learning_curve(
+            &NearestNeighbours { k: 1 },
+            &dataset,
+            &[0.25, 0.5, 1.0],
+            4,
+            |pred: Array1<f64>, truth: &Array1<f64>| pred.mean_absolute_error(truth),
+        )
+        .unwrap();
+
+        assert_eq!(curve.train_sizes, vec![4, 8, 15]);
+        assert_eq!(curve.train_scores.dim(), (3, 4));
+        assert_eq!(curve.validation_scores.dim(), (3, 4));
+
+        // a single nearest neighbour reproduces the training set
+        assert_abs_diff_eq!(curve.train_scores.sum(), 0.0);
+        // and the validation error decreases with more training data
+        let validation = curve.mean_validation_scores();
+        assert!(validation[2] < validation[0]);
+
+        assert!(learning_curve(
+            &NearestNeighbours { k: 1 },
+            &dataset,
+            &[0.0, 1.0],
+            4,
+            |pred: Array1<f64>, truth: &Array1<f64>| pred.mean_absolute_error(truth),
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_validation_curve() {
+        let dataset = dataset();
+
+        let curve = validation_curve(
+            &dataset,
+            &[1, 2, 4],
+            5,
+            |k| NearestNeighbours { k: *k },
+            |pred: Array1<f64>, truth: &Array1<f64>| pred.mean_absolute_error(truth),
+        )
+        .unwrap();
+
+        assert_eq!(curve.values, vec![1, 2, 4]);
+        assert_eq!(curve.train_scores.dim(), (3, 5));
+
+        // averaging two neighbours cancels the alternating noise
+        let validation = curve.mean_validation_scores();
+        assert!(validation[1] < validation[0]);
+        // the training error increases with the number of neighbours
+        let train = curve.mean_train_scores();
+        assert!(train[0] < train[1]);
+
+        let mut csv = Vec::new();
+        curve.write_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines = csv.lines().collect::<Vec<_>>();
+
+        assert_eq!(lines.len(), 4);
+        assert_eq!(
+            lines[0],
+            "value,train_mean,train_std,validation_mean,validation_std"
+        );
+        assert!(lines[1].starts_with("1,0,0,"));
+        assert_eq!(lines[3].split(',').count(), 5);
+
+        assert!(validation_curve(
+            &dataset,
+            &[1],
+            21,
+            |k| NearestNeighbours { k: *k },
+            |pred: Array1<f64>, truth: &Array1<f64>| pred.mean_absolute_error(truth),
+        )
+        .is_err());
+    }
+}
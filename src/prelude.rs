@@ -17,4 +17,4 @@ pub use crate::dataset::{Dataset, Float};
 pub use crate::metrics_classification::{BinaryClassification, ConfusionMatrix, ToConfusionMatrix};
 
 #[doc(no_inline)]
-pub use crate::metrics_regression::Regression;
+pub use crate::metrics_regression::{MultiTargetRegression, Regression};